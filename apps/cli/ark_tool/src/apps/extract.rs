@@ -0,0 +1,107 @@
+use crate::apps::{filter_entries, Archive, ArkToolError, SubApp};
+use clap::Parser;
+use log::info;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Parser, Debug)]
+pub struct ExtractApp {
+    #[arg(help = "Path to input hdr, ark, package or U8 archive", required = true)]
+    pub input_path: String,
+    #[arg(help = "Path to output directory", required = true)]
+    pub output_path: String,
+    #[arg(short, long, help = "Glob pattern to filter entries by (can be used multiple times)")]
+    pub filter: Vec<String>,
+    #[arg(long, help = "Write entries as stored in ark (skip decompression)")]
+    pub raw: bool,
+}
+
+impl SubApp for ExtractApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let archive = Archive::from_path(&self.input_path)?;
+        let entries = filter_entries(&archive, &self.filter)?;
+        let output_dir = Path::new(&self.output_path);
+
+        for entry in entries.iter() {
+            let file_path = get_output_file_path(output_dir, &entry.path)?;
+
+            if let Some(parent_dir) = file_path.parent() {
+                std::fs::create_dir_all(parent_dir)?;
+            }
+
+            let mut writer = BufWriter::new(File::create(&file_path)?);
+
+            if self.raw {
+                writer.write_all(&archive.get_raw_stream(entry.id)?)?;
+            } else {
+                let mut reader = archive.get_reader(entry.id)?;
+                std::io::copy(&mut reader, &mut writer)?;
+            }
+
+            writer.flush()?;
+            println!("{}", file_path.display());
+        }
+
+        info!("Extracted {} entries", entries.len());
+        Ok(())
+    }
+}
+
+/// Joins entry path to output directory, rejecting paths that could escape it (ex: "../", "/")
+fn get_output_file_path(output_dir: &Path, entry_path: &str) -> Result<PathBuf, ArkToolError> {
+    let entry_path_fixed = entry_path.replace('\\', "/");
+    let mut file_path = output_dir.to_path_buf();
+    let mut has_file_name = false;
+
+    for component in Path::new(&entry_path_fixed).components() {
+        match component {
+            Component::Normal(name) => {
+                file_path.push(name);
+                has_file_name = true;
+            },
+            Component::CurDir => continue,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(ArkToolError::InvalidEntryPath {
+                    path: entry_path.to_owned()
+                });
+            }
+        }
+    }
+
+    if !has_file_name {
+        return Err(ArkToolError::InvalidEntryPath {
+            path: entry_path.to_owned()
+        });
+    }
+
+    Ok(file_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+
+    #[rstest]
+    #[case("config/gen/rb.dtb", "out/config/gen/rb.dtb")]
+    #[case("./songs/song.mid", "out/songs/song.mid")]
+    #[case("songs\\gen\\song.milo_xbox", "out/songs/gen/song.milo_xbox")]
+    fn get_output_file_path_test(#[case] entry_path: &str, #[case] expected: &str) {
+        let file_path = get_output_file_path(Path::new("out"), entry_path).unwrap();
+        assert_eq!(Path::new(expected), file_path);
+    }
+
+    #[rstest]
+    #[case("../evil.dta")]
+    #[case("songs/../../evil.dta")]
+    #[case("songs\\..\\..\\evil.dta")]
+    #[case("/etc/evil.dta")]
+    #[case("")]
+    #[case(".")]
+    fn get_output_file_path_rejects_escaping_paths(#[case] entry_path: &str) {
+        let result = get_output_file_path(Path::new("out"), entry_path);
+        assert!(matches!(result, Err(ArkToolError::InvalidEntryPath { .. })));
+    }
+}
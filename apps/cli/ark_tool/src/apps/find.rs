@@ -0,0 +1,43 @@
+use crate::apps::{filter_entries, Archive, ArkToolError, SubApp};
+use clap::Parser;
+use std::error::Error;
+
+#[derive(Parser, Debug)]
+pub struct FindApp {
+    #[arg(help = "Path to input hdr, ark, package or U8 archive", required = true)]
+    pub input_path: String,
+    #[arg(help = "Glob patterns or names to search for (names without wildcards match anywhere in path)", required = true)]
+    pub patterns: Vec<String>,
+    #[arg(short, long, help = "Only match gen files")]
+    pub gen_only: bool,
+}
+
+impl SubApp for FindApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let archive = Archive::from_path(&self.input_path)?;
+
+        let patterns = self.patterns
+            .iter()
+            .map(|p| match p.contains(['*', '?', '[']) {
+                true => p.to_owned(),
+                false => format!("*{p}*"),
+            })
+            .collect::<Vec<_>>();
+
+        let entries = filter_entries(&archive, &patterns)?
+            .into_iter()
+            .filter(|e| !self.gen_only || e.is_gen_file())
+            .collect::<Vec<_>>();
+
+        if entries.is_empty() {
+            // Non-zero exit code so it can be used in scripts
+            return Err(Box::new(ArkToolError::NoEntriesFound));
+        }
+
+        for entry in entries {
+            println!("{}", entry.path);
+        }
+
+        Ok(())
+    }
+}
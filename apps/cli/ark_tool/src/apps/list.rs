@@ -0,0 +1,45 @@
+use crate::apps::{filter_entries, Archive, SubApp};
+use clap::Parser;
+use std::error::Error;
+
+#[derive(Parser, Debug)]
+pub struct ListApp {
+    #[arg(help = "Path to input hdr, ark, package or U8 archive", required = true)]
+    pub input_path: String,
+    #[arg(short, long, help = "Glob pattern to filter entries by (can be used multiple times)")]
+    pub filter: Vec<String>,
+    #[arg(short, long, help = "Only print entry paths")]
+    pub paths_only: bool,
+}
+
+impl SubApp for ListApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let archive = Archive::from_path(&self.input_path)?;
+        let entries = filter_entries(&archive, &self.filter)?;
+
+        if self.paths_only {
+            for entry in entries {
+                println!("{}", entry.path);
+            }
+
+            return Ok(());
+        }
+
+        // Tab-separated so output can be easily consumed by other tools
+        println!("path\tsize\tinflated_size\tpart\toffset\tgen");
+
+        for entry in entries {
+            println!(
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                entry.path,
+                entry.size,
+                entry.inflated_size,
+                entry.part,
+                entry.offset,
+                entry.is_gen_file()
+            );
+        }
+
+        Ok(())
+    }
+}
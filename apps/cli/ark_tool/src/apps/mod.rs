@@ -0,0 +1,164 @@
+use clap::{Parser, Subcommand};
+use glob::{MatchOptions, Pattern};
+use pikaxe::ark::{Ark, ArkOffsetEntry};
+use pikaxe::package::stfs::StfsPackage;
+use pikaxe::package::u8arc::U8Archive;
+use std::error::Error;
+use std::io::Read;
+use std::path::Path;
+use thiserror::Error;
+
+mod extract;
+mod find;
+mod list;
+mod pack;
+mod verify;
+pub use self::extract::*;
+pub use self::find::*;
+pub use self::list::*;
+pub use self::pack::*;
+pub use self::verify::*;
+
+// From Cargo.toml
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub(crate) trait SubApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+#[derive(Debug, Error)]
+pub enum ArkToolError {
+    #[error("Invalid glob pattern \"{pattern}\"")]
+    InvalidPattern {
+        pattern: String
+    },
+    #[error("No entries found")]
+    NoEntriesFound,
+    #[error("Package hashes don't match content")]
+    InvalidPackageHashes,
+    #[error("Entry path \"{path}\" is outside of output directory")]
+    InvalidEntryPath {
+        path: String
+    },
+    #[error("{count} of {total} entries failed verification")]
+    VerificationFailed {
+        count: usize,
+        total: usize
+    },
+}
+
+#[derive(Parser, Debug)]
+#[command(name = PKG_NAME, version = VERSION, about = "Use this tool for reading and writing ark archives from milo engine based games")]
+struct Options {
+    #[command(subcommand)]
+    commands: SubCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum SubCommand {
+    #[command(name = "list", about = "Lists entries in ark or archive (optionally filtered by glob pattern)")]
+    List(ListApp),
+    #[command(name = "extract", about = "Extracts entries from ark or archive to directory")]
+    Extract(ExtractApp),
+    #[command(name = "find", about = "Finds entries in ark or archive by name or glob pattern")]
+    Find(FindApp),
+    #[command(name = "verify", about = "Verifies every entry in ark or archive can be read")]
+    Verify(VerifyApp),
+    #[command(name = "pack", about = "Creates ark or archive from input directory")]
+    Pack(PackApp),
+}
+
+#[derive(Debug)]
+pub struct ArkTool {
+    options: Options,
+}
+
+impl ArkTool {
+    pub fn new() -> ArkTool {
+        ArkTool {
+            options: Options::parse()
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        match &mut self.options.commands {
+            SubCommand::List(app) => app.process(),
+            SubCommand::Extract(app) => app.process(),
+            SubCommand::Find(app) => app.process(),
+            SubCommand::Verify(app) => app.process(),
+            SubCommand::Pack(app) => app.process(),
+        }
+    }
+}
+
+/// Ark, 360 package (CON/LIVE) or Wii U8 archive
+pub(crate) enum Archive {
+    Ark(Ark),
+    Stfs(StfsPackage),
+    U8(U8Archive),
+}
+
+impl Archive {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Archive, Box<dyn Error>> {
+        let path = path.as_ref();
+
+        if StfsPackage::is_stfs_package(path) {
+            return Ok(Archive::Stfs(StfsPackage::from_path(path)?));
+        }
+
+        if U8Archive::is_u8_archive(path) {
+            return Ok(Archive::U8(U8Archive::from_path(path)?));
+        }
+
+        Ok(Archive::Ark(Ark::from_path(path)?))
+    }
+
+    pub fn get_entries(&self) -> &Vec<ArkOffsetEntry> {
+        match self {
+            Archive::Ark(ark) => &ark.entries,
+            Archive::Stfs(package) => &package.entries,
+            Archive::U8(archive) => &archive.entries,
+        }
+    }
+
+    pub fn get_reader(&self, id: u32) -> Result<Box<dyn Read + '_>, Box<dyn Error>> {
+        match self {
+            Archive::Ark(ark) => Ok(ark.get_reader(id)?),
+            Archive::Stfs(package) => Ok(package.get_reader(id)?),
+            Archive::U8(archive) => Ok(archive.get_reader(id)?),
+        }
+    }
+
+    /// Gets entry data as stored (no decompression)
+    pub fn get_raw_stream(&self, id: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            Archive::Ark(ark) => Ok(ark.get_raw_stream(id)?),
+            Archive::Stfs(package) => Ok(package.get_stream(id)?),
+            Archive::U8(archive) => Ok(archive.get_stream(id)?),
+        }
+    }
+}
+
+/// Returns entries matching any of the glob patterns (or all entries if none given)
+pub(crate) fn filter_entries<'a>(archive: &'a Archive, patterns: &[String]) -> Result<Vec<&'a ArkOffsetEntry>, ArkToolError> {
+    let patterns = patterns
+        .iter()
+        .map(|p| Pattern::new(p)
+            .map_err(|_| ArkToolError::InvalidPattern { pattern: p.to_owned() }))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let options = MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: false,
+        require_literal_leading_dot: false
+    };
+
+    Ok(archive
+        .get_entries()
+        .iter()
+        .filter(|e| patterns.is_empty() || patterns
+            .iter()
+            .any(|p| p.matches_with(&e.path, options)))
+        .collect())
+}
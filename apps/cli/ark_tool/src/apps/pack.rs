@@ -0,0 +1,109 @@
+use crate::apps::SubApp;
+use clap::Parser;
+use log::info;
+use pikaxe::ark::{ArkBuilder, ArkEncryption};
+use pikaxe::package::stfs::{StfsBuilder, StfsMetadata, StfsPackageType};
+use pikaxe::package::u8arc::U8Builder;
+use std::error::Error;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PackError {
+    #[error("Package type \"{name}\" not supported (use con, live or u8)")]
+    UnsupportedPackageType {
+        name: String
+    },
+}
+
+#[derive(Parser, Debug)]
+pub struct PackApp {
+    #[arg(help = "Path to input directory", required = true)]
+    pub input_path: String,
+    #[arg(help = "Path to output hdr (or ark for versions 1 and 2, or package/archive)", required = true)]
+    pub output_path: String,
+    #[arg(short, long, default_value = "3", help = "Ark version (1, 2, 3, 5)")]
+    pub version: i32,
+    #[arg(short, long, allow_negative_numbers = true, help = "Key to encrypt hdr with (negative keys use new encryption)")]
+    pub key: Option<i32>,
+    #[arg(long, help = "Max size of each ark part in bytes")]
+    pub part_size: Option<u64>,
+    #[arg(long, help = "Create 360 package or Wii archive instead of ark (con, live, u8)")]
+    pub package: Option<String>,
+    #[arg(long, help = "Title id of package (hex)", value_parser = parse_hex)]
+    pub title_id: Option<u32>,
+    #[arg(long, help = "Display name of package")]
+    pub display_name: Option<String>,
+}
+
+fn parse_hex(value: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(value.trim_start_matches("0x"), 16)
+}
+
+impl SubApp for PackApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(package_type) = self.package.as_ref() {
+            if package_type.eq_ignore_ascii_case("u8") {
+                return self.pack_u8();
+            }
+
+            return self.pack_stfs(package_type);
+        }
+
+        let mut builder = ArkBuilder::from_dir(&self.input_path, self.version)?;
+
+        if let Some(key) = self.key {
+            builder = builder.with_encryption(ArkEncryption::from_key(key));
+        }
+
+        if let Some(part_size) = self.part_size {
+            builder = builder.with_part_size(part_size);
+        }
+
+        let ark = builder.build(&self.output_path)?;
+
+        println!("{}", ark.path.display());
+        for part_path in ark.part_paths.iter().filter(|p| **p != ark.path) {
+            println!("{}", part_path.display());
+        }
+
+        info!("Packed {} entries into {}", ark.entries.len(), self.output_path);
+        Ok(())
+    }
+}
+
+impl PackApp {
+    fn pack_stfs(&self, package_type: &str) -> Result<(), Box<dyn Error>> {
+        let package_type = match package_type.to_ascii_lowercase().as_str() {
+            "con" => StfsPackageType::Con,
+            "live" => StfsPackageType::Live,
+            _ => return Err(Box::new(PackError::UnsupportedPackageType {
+                name: package_type.to_owned()
+            }))
+        };
+
+        let metadata = StfsMetadata {
+            title_id: self.title_id.unwrap_or_default(),
+            display_name: self.display_name.to_owned().unwrap_or_default(),
+            ..Default::default()
+        };
+
+        let package = StfsBuilder::from_dir(&self.input_path, package_type)?
+            .with_metadata(metadata)
+            .build(&self.output_path)?;
+
+        println!("{}", package.path.display());
+
+        info!("Packed {} entries into {}", package.entries.len(), self.output_path);
+        Ok(())
+    }
+
+    fn pack_u8(&self) -> Result<(), Box<dyn Error>> {
+        let archive = U8Builder::from_dir(&self.input_path)?
+            .build(&self.output_path)?;
+
+        println!("{}", self.output_path);
+
+        info!("Packed {} entries into {}", archive.entries.len(), self.output_path);
+        Ok(())
+    }
+}
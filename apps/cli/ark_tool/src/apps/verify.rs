@@ -0,0 +1,58 @@
+use crate::apps::{Archive, ArkToolError, SubApp};
+use clap::Parser;
+use log::{error, info};
+use std::error::Error;
+
+#[derive(Parser, Debug)]
+pub struct VerifyApp {
+    #[arg(help = "Path to input hdr, ark, package or U8 archive", required = true)]
+    pub input_path: String,
+}
+
+impl SubApp for VerifyApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let archive = Archive::from_path(&self.input_path)?;
+        let entries = archive.get_entries();
+        let mut failed_count = 0;
+
+        if let Archive::Stfs(package) = &archive && !package.verify()? {
+            return Err(Box::new(ArkToolError::InvalidPackageHashes));
+        }
+
+        for entry in entries.iter() {
+            // Stream entry to nowhere so every byte is read (and inflated)
+            let result = archive
+                .get_reader(entry.id)
+                .map_err(|e| e.to_string())
+                .and_then(|mut reader| std::io::copy(&mut reader, &mut std::io::sink())
+                    .map_err(|e| e.to_string()));
+
+            let expected_size = match entry.is_compressed() {
+                true => entry.inflated_size,
+                _ => entry.size
+            };
+
+            match result {
+                Ok(size) if size as usize == expected_size => {},
+                Ok(size) => {
+                    failed_count += 1;
+                    error!("{}: expected {} bytes but read {}", entry.path, expected_size, size);
+                },
+                Err(err) => {
+                    failed_count += 1;
+                    error!("{}: {}", entry.path, err);
+                }
+            }
+        }
+
+        if failed_count > 0 {
+            return Err(Box::new(ArkToolError::VerificationFailed {
+                count: failed_count,
+                total: entries.len()
+            }));
+        }
+
+        info!("Verified {} entries", entries.len());
+        Ok(())
+    }
+}
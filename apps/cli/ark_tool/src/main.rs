@@ -0,0 +1,26 @@
+mod apps;
+use apps::ArkTool;
+use simplelog::*;
+
+#[cfg(debug_assertions)]
+const LOG_LEVEL: LevelFilter = LevelFilter::Debug;
+
+#[cfg(not(debug_assertions))]
+const LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let log_config = ConfigBuilder::new()
+        .add_filter_allow_str("pikaxe")
+        .add_filter_allow_str("ark_tool")
+        .build();
+
+    // Setup logging (stderr so stdout can be piped)
+    CombinedLogger::init(
+        vec![
+            TermLogger::new(LOG_LEVEL, log_config, TerminalMode::Stderr, ColorChoice::Auto),
+        ]
+    )?;
+
+    let mut tool = ArkTool::new();
+    tool.run()
+}
@@ -0,0 +1,53 @@
+use crate::apps::{SubApp, encrypt_dtb, get_io_settings, parse_number};
+use clap::Parser;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use pikaxe::dta::parse_dta;
+use pikaxe::io::{BinaryStream, MemoryStream};
+
+#[derive(Parser, Debug)]
+pub struct Dta2DtbApp {
+    #[arg(help = "Path to input dta", required = true)]
+    pub dta_path: String,
+    #[arg(help = "Path to output dtb", required = true)]
+    pub dtb_path: String,
+    #[arg(short, long, default_value = "milo", help = "Dtb format (milo, amplitude, forge)")]
+    pub format: String,
+    #[arg(short, long, value_parser = parse_number::<i32>, help = "Encrypt output dtb with key (written to first 4 bytes)")]
+    pub key: Option<i32>,
+    #[arg(short, long, requires = "key", value_parser = parse_number::<u8>, help = "Xor applied when encrypting (ex: 0xFF for newer games). Requires --key")]
+    pub xor: Option<u8>,
+}
+
+impl SubApp for Dta2DtbApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let dta_path = Path::new(&self.dta_path);
+        let dtb_path = Path::new(&self.dtb_path);
+
+        let settings = get_io_settings(&self.format)?;
+
+        let text = fs::read(dta_path)?;
+        let root = parse_dta(&text)?;
+
+        let mut data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        let mut writer = Box::new(BinaryStream::from_stream(&mut stream));
+
+        root.save_with_settings(&mut writer, settings)?;
+
+        if let Some(key) = self.key {
+            data = encrypt_dtb(&data, key, self.xor);
+        }
+
+        fs::write(dtb_path, data)?;
+
+        if let Some(file_name) = dtb_path.file_name() {
+            let file_name = file_name.to_str().unwrap_or("file");
+            println!("Successfully wrote {}", file_name);
+        }
+
+        Ok(())
+    }
+}
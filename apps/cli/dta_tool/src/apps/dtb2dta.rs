@@ -0,0 +1,80 @@
+use crate::apps::{DtaToolError, SubApp, decrypt_dtb, get_io_settings, parse_number};
+use clap::Parser;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use pikaxe::dta::{DTAFormat, RootData};
+use pikaxe::io::{BinaryStream, MemoryStream};
+
+#[derive(Parser, Debug)]
+pub struct Dtb2DtaApp {
+    #[arg(help = "Path to input dtb", required = true)]
+    pub dtb_path: String,
+    #[arg(help = "Path to output dta", required = true)]
+    pub dta_path: String,
+    #[arg(short, long, default_value = "milo", help = "Dtb format (milo, amplitude, forge)")]
+    pub format: String,
+    #[arg(short, long, help = "Decrypt input dtb (key is read from first 4 bytes)")]
+    pub encrypted: bool,
+    #[arg(short, long, value_parser = parse_number::<u8>, help = "Xor applied when decrypting (ex: 0xFF for newer games). Implies --encrypted")]
+    pub xor: Option<u8>,
+    #[arg(short, long, help = "Write symbols in single quotes")]
+    pub quoted_symbols: bool,
+    #[arg(short, long, help = "Indent with tabs instead of spaces")]
+    pub tabs: bool,
+    #[arg(short, long, default_value = "3", help = "Number of indent characters per level")]
+    pub indent: u8,
+    #[arg(long, help = "Write func/object nodes as bare names even though they're read back as symbols")]
+    pub allow_lossy: bool,
+}
+
+impl SubApp for Dtb2DtaApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let dtb_path = Path::new(&self.dtb_path);
+        let dta_path = Path::new(&self.dta_path);
+
+        let settings = get_io_settings(&self.format)?;
+
+        let mut data = fs::read(dtb_path)?;
+        if self.encrypted || self.xor.is_some() {
+            data = decrypt_dtb(&data, self.xor)?;
+        }
+
+        let mut stream = MemoryStream::from_slice_as_read(&data);
+        let mut reader = Box::new(BinaryStream::from_stream(&mut stream));
+
+        let mut root = RootData::new();
+        root.load_with_settings(&mut reader, settings)?;
+
+        // No dta syntax for func/object nodes so node types would change when converted back to dtb
+        let lossy_count = root.count_lossy_print_nodes();
+        if lossy_count > 0 {
+            if !self.allow_lossy {
+                return Err(Box::new(DtaToolError::LossyNodes {
+                    count: lossy_count
+                }));
+            }
+
+            eprintln!("WARN: {lossy_count} func/object node(s) will be read back as symbols");
+        }
+
+        let format = DTAFormat {
+            use_quoted_symbols: self.quoted_symbols,
+            indent_char: if self.tabs { b'\t' } else { b' ' },
+            indent_char_count: self.indent,
+        };
+
+        let mut writer = BufWriter::new(File::create(dta_path)?);
+        root.print_with_format(&mut writer, format)?;
+        writer.flush()?;
+
+        if let Some(file_name) = dta_path.file_name() {
+            let file_name = file_name.to_str().unwrap_or("file");
+            println!("Successfully wrote {}", file_name);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use clap::{Parser, Subcommand};
+use pikaxe::dta::DataArrayIOSettings;
+use pikaxe::io::crypt_dtb_style;
+use std::error::Error;
+use thiserror::Error;
+
+mod dta2dtb;
+mod dtb2dta;
+mod re_encrypt;
+pub use self::dta2dtb::*;
+pub use self::dtb2dta::*;
+pub use self::re_encrypt::*;
+
+// From Cargo.toml
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub(crate) trait SubApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+#[derive(Debug, Error)]
+pub enum DtaToolError {
+    #[error("Unknown dtb format \"{format}\" (expected milo, amplitude or forge)")]
+    UnknownFormat {
+        format: String
+    },
+    #[error("Encrypted dtb is too small to contain key")]
+    MissingEncryptionKey,
+    #[error("Dtb contains {count} func/object node(s) which would be read back as symbols (use --allow-lossy to convert anyway)")]
+    LossyNodes {
+        count: usize
+    },
+}
+
+#[derive(Parser, Debug)]
+#[command(name = PKG_NAME, version = VERSION, about = "Use this tool for converting dtb scripts from milo engine based games")]
+struct Options {
+    #[command(subcommand)]
+    commands: SubCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum SubCommand {
+    #[command(name = "dtb2dta", about = "Converts binary dtb to dta text")]
+    Dtb2Dta(Dtb2DtaApp),
+    #[command(name = "dta2dtb", about = "Converts dta text to binary dtb")]
+    Dta2Dtb(Dta2DtbApp),
+    #[command(name = "re-encrypt", about = "Decrypts dtb and encrypts again with other key or xor")]
+    ReEncrypt(ReEncryptApp),
+}
+
+#[derive(Debug)]
+pub struct DtaTool {
+    options: Options,
+}
+
+impl DtaTool {
+    pub fn new() -> DtaTool {
+        DtaTool {
+            options: Options::parse()
+        }
+    }
+
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        match &mut self.options.commands {
+            SubCommand::Dtb2Dta(app) => app.process(),
+            SubCommand::Dta2Dtb(app) => app.process(),
+            SubCommand::ReEncrypt(app) => app.process(),
+        }
+    }
+}
+
+/// Parses decimal or hex (0x prefix) number
+pub(crate) fn parse_number<T: TryFrom<i64>>(text: &str) -> Result<T, String> {
+    let num = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16),
+        None => text.parse::<i64>(),
+    }.map_err(|e| e.to_string())?;
+
+    T::try_from(num).map_err(|_| format!("{text} is out of range"))
+}
+
+pub(crate) fn get_io_settings(format: &str) -> Result<DataArrayIOSettings, DtaToolError> {
+    match format.to_lowercase().as_str() {
+        "milo" => Ok(DataArrayIOSettings::Milo),
+        "amp" | "amplitude" => Ok(DataArrayIOSettings::Amplitude),
+        "forge" => Ok(DataArrayIOSettings::Forge),
+        _ => Err(DtaToolError::UnknownFormat {
+            format: format.to_owned()
+        })
+    }
+}
+
+/// Decrypts dtb data (key is stored in first 4 bytes)
+pub(crate) fn decrypt_dtb(data: &[u8], xor: Option<u8>) -> Result<Vec<u8>, DtaToolError> {
+    if data.len() < 4 {
+        return Err(DtaToolError::MissingEncryptionKey);
+    }
+
+    let key = i32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+
+    let mut decrypted = data[4..].to_vec();
+    crypt_dtb_style(&mut decrypted, key, xor);
+
+    Ok(decrypted)
+}
+
+/// Encrypts dtb data (key is written before encrypted data)
+pub(crate) fn encrypt_dtb(data: &[u8], key: i32, xor: Option<u8>) -> Vec<u8> {
+    let mut encrypted = data.to_vec();
+    crypt_dtb_style(&mut encrypted, key, xor);
+
+    [&key.to_le_bytes()[..], &encrypted].concat()
+}
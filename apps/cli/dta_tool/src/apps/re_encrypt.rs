@@ -0,0 +1,47 @@
+use crate::apps::{SubApp, decrypt_dtb, encrypt_dtb, parse_number};
+use clap::Parser;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+#[derive(Parser, Debug)]
+pub struct ReEncryptApp {
+    #[arg(help = "Path to input encrypted dtb", required = true)]
+    pub in_dtb_path: String,
+    #[arg(help = "Path to output dtb", required = true)]
+    pub out_dtb_path: String,
+    #[arg(long, value_parser = parse_number::<u8>, help = "Xor applied when decrypting input (ex: 0xFF for newer games)")]
+    pub in_xor: Option<u8>,
+    #[arg(short, long, value_parser = parse_number::<i32>, help = "Key to encrypt output with (key of input is used if not provided)")]
+    pub key: Option<i32>,
+    #[arg(short, long, value_parser = parse_number::<u8>, help = "Xor applied when encrypting output (ex: 0xFF for newer games)")]
+    pub xor: Option<u8>,
+    #[arg(short, long, conflicts_with_all = ["key", "xor"], help = "Write decrypted dtb without key")]
+    pub decrypt: bool,
+}
+
+impl SubApp for ReEncryptApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let in_dtb_path = Path::new(&self.in_dtb_path);
+        let out_dtb_path = Path::new(&self.out_dtb_path);
+
+        let data = fs::read(in_dtb_path)?;
+        let decrypted = decrypt_dtb(&data, self.in_xor)?;
+
+        let out_data = match (self.decrypt, self.key) {
+            (true, _) => decrypted,
+            (_, Some(key)) => encrypt_dtb(&decrypted, key, self.xor),
+            // Re-use key of input (decrypt_dtb already checked length)
+            _ => encrypt_dtb(&decrypted, i32::from_le_bytes([data[0], data[1], data[2], data[3]]), self.xor),
+        };
+
+        fs::write(out_dtb_path, out_data)?;
+
+        if let Some(file_name) = out_dtb_path.file_name() {
+            let file_name = file_name.to_str().unwrap_or("file");
+            println!("Successfully wrote {}", file_name);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+mod apps;
+use apps::DtaTool;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tool = DtaTool::new();
+    tool.run()
+}
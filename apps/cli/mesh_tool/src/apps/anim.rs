@@ -3,7 +3,7 @@ use clap::Parser;
 
 use std::error::Error;
 
-use pikaxe::{Platform, SystemInfo};
+use pikaxe::{GamePreset, Platform, SystemInfo};
 use pikaxe::model::*;
 use pikaxe::io::*;
 
@@ -15,23 +15,27 @@ pub struct AnimApp {
     pub output_path: String,
     #[arg(short, long, help = "Use big endian serialization")]
     pub big_endian: bool,
+    #[arg(long, help = "Game preset (gh2_x360, rb1, tbrb, ...). Uses milo version 25 for Xbox 360 if not provided")]
+    pub preset: Option<String>,
     #[arg(short, long, help = "Optimize for smaller output file size")]
     pub optimize: bool,
 }
 
 impl SubApp for AnimApp {
     fn process(&mut self) -> Result<(), Box<dyn Error>> {
-        // TODO: Get from args
-        let system_info: SystemInfo = SystemInfo {
-            version: 25,
-            platform: Platform::X360,
-            endian: if self.big_endian {
-                IOEndian::Big
-            } else {
-                IOEndian::Little
-            },
+        let mut system_info = match self.preset.as_deref() {
+            Some(name) => GamePreset::from_name(name)?.get_system_info(None),
+            None => SystemInfo {
+                version: 25,
+                platform: Platform::X360,
+                endian: IOEndian::Little,
+            }
         };
 
+        if self.big_endian {
+            system_info.endian = IOEndian::Big;
+        }
+
         let importer = GltfImporter2::new(&self.anim_path)?;
         let assets = importer.process();
 
@@ -3,7 +3,7 @@ use clap::Parser;
 
 use std::error::Error;
 
-use pikaxe::{Platform, SystemInfo};
+use pikaxe::{GamePreset, Platform};
 use pikaxe::model::*;
 use pikaxe::io::*;
 
@@ -13,18 +13,18 @@ pub struct Model2GroupApp {
     pub model_path: String,
     #[arg(help = "Path to output directory", required = true)]
     pub output_path: String,
+    #[arg(long, default_value = "rb1", help = "Game preset (gh2_x360, rb1, tbrb, ...)")]
+    pub preset: String,
+    #[arg(long, default_value = "ps3", help = "Platform (ps2, ps3, wii, x360)")]
+    pub platform: String,
 }
 
-// TODO: Get from args
-const SYSTEM_INFO: SystemInfo = SystemInfo {
-    version: 25,
-    platform: Platform::PS3,
-    endian: IOEndian::Big,
-};
-
 impl SubApp for Model2GroupApp {
     fn process(&mut self) -> Result<(), Box<dyn Error>> {
-        let asset_man = open_model(&self.model_path, SYSTEM_INFO)?;
+        let system_info = GamePreset::from_name(&self.preset)?
+            .get_system_info(Platform::from_name(&self.platform));
+
+        let asset_man = open_model(&self.model_path, system_info)?;
         asset_man.dump_to_directory(&self.output_path)
     }
 }
@@ -0,0 +1,159 @@
+use crate::apps::SubApp;
+use clap::Parser;
+use std::error::Error;
+use std::path::Path;
+use thiserror::Error;
+
+use pikaxe::{GamePreset, Platform, SystemInfo};
+use pikaxe::convert::{convert_object_dir_with_settings, ConvertSettings};
+use pikaxe::io::*;
+
+#[derive(Debug, Error)]
+pub enum ConvertArgError {
+    #[error("Platform \"{name}\" not supported (available: ps2, ps3, wii, x360)")]
+    UnknownPlatform {
+        name: String
+    },
+    #[error("Unable to convert {count} entries, output milo not written (use --force to remove failed entries and write anyway)")]
+    ConversionFailed {
+        count: usize
+    },
+}
+
+#[derive(Parser, Debug)]
+pub struct ConvertApp {
+    #[arg(help = "Path to input milo scene", required = true)]
+    pub in_milo_path: String,
+    #[arg(help = "Path to output milo scene", required = true)]
+    pub out_milo_path: String,
+    #[arg(long, help = "Game preset of input milo (gh2, rb1, tbrb, ...). Guessed from milo if not provided")]
+    pub in_preset: Option<String>,
+    #[arg(short = 'g', long, help = "Game preset of output milo (gh2, rb1, tbrb, ...). Sets version + endian of output, entries not matching object versions of preset fail")]
+    pub preset: Option<String>,
+    #[arg(short = 'p', long, help = "Output platform (ps2, ps3, wii, x360). Guessed from output extension if not provided")]
+    pub platform: Option<String>,
+    #[arg(short = 'm', long, help = "Output milo archive version (10, 24, 25)")]
+    pub milo_version: Option<u32>,
+    #[arg(short = 'b' , long, help = "Use big endian serialization (defaults to endian of output preset or platform)")]
+    pub big_endian: Option<bool>,
+    #[arg(short = 'u' , long, help = "Leave output milo archive uncompressed")]
+    pub uncompressed: bool,
+    #[arg(long, help = "Guess entry sizes if they can't be determined exactly")]
+    pub guess_entry_sizes: bool,
+    #[arg(short = 'f', long, help = "Remove entries that can't be converted and write output milo anyway (directory entries that can't be converted are written as is)")]
+    pub force: bool,
+}
+
+impl ConvertApp {
+    fn get_out_system_info(&self, in_sys_info: &SystemInfo, out_milo_path: &Path) -> Result<SystemInfo, Box<dyn Error>> {
+        let platform = match self.platform.as_deref() {
+            Some(name) => Platform::from_name(name)
+                .ok_or_else(|| ConvertArgError::UnknownPlatform { name: name.to_owned() })?,
+            None => Platform::guess_platform(out_milo_path)
+        };
+
+        let mut out_sys_info = match self.preset.as_deref() {
+            Some(name) => GamePreset::from_name(name)?.get_system_info(Some(platform)),
+            None => SystemInfo {
+                version: in_sys_info.version,
+                platform,
+                endian: match platform {
+                    p if p == in_sys_info.platform => in_sys_info.endian,
+                    Platform::PS2 => IOEndian::Little,
+                    _ => IOEndian::Big,
+                }
+            }
+        };
+
+        if let Some(version) = self.milo_version {
+            out_sys_info.version = version;
+        }
+
+        if let Some(big_endian) = self.big_endian {
+            out_sys_info.endian = match big_endian {
+                true => IOEndian::Big,
+                _ => IOEndian::Little
+            };
+        }
+
+        Ok(out_sys_info)
+    }
+}
+
+impl SubApp for ConvertApp {
+    fn process(&mut self) -> Result<(), Box<dyn Error>> {
+        let in_milo_path = Path::new(&self.in_milo_path);
+        let out_milo_path = Path::new(&self.out_milo_path);
+
+        if let Some(file_name) = in_milo_path.file_name() {
+            let file_name = file_name.to_str().unwrap();
+            println!("Opening {}", file_name);
+        }
+
+        let milo = MiloArchive::from_path(in_milo_path)?;
+
+        let in_sys_info = SystemInfo::from_preset_or_guess(self.in_preset.as_deref(), &milo, in_milo_path)?;
+        let out_sys_info = self.get_out_system_info(&in_sys_info, out_milo_path)?;
+
+        println!(
+            "Converting from {:?} (v{}, {:?}) to {:?} (v{}, {:?})",
+            in_sys_info.platform, in_sys_info.version, in_sys_info.endian,
+            out_sys_info.platform, out_sys_info.version, out_sys_info.endian
+        );
+
+        let settings = MiloUnpackSettings {
+            guess_entry_sizes: self.guess_entry_sizes
+        };
+
+        let mut obj_dir = milo.unpack_directory_with_settings(&in_sys_info, &settings)?;
+        let convert_settings = ConvertSettings {
+            preset: self.preset
+                .as_deref()
+                .map(GamePreset::from_name)
+                .transpose()?,
+            remove_failed: self.force
+        };
+        let report = convert_object_dir_with_settings(&mut obj_dir, &in_sys_info, &out_sys_info, &convert_settings);
+
+        for name in report.copied.iter() {
+            println!("Copied \"{name}\" as is");
+        }
+
+        for error in report.errors.iter() {
+            match self.force {
+                true => println!("WARN: Unable to convert (removed or written as is): {error}"),
+                _ => println!("ERROR: Unable to convert: {error}"),
+            }
+        }
+
+        println!(
+            "Converted {} entries, copied {}, failed {}",
+            report.converted.len(),
+            report.copied.len(),
+            report.errors.len()
+        );
+
+        if !report.errors.is_empty() && !self.force {
+            return Err(Box::new(ConvertArgError::ConversionFailed {
+                count: report.errors.len()
+            }));
+        }
+
+        // Write to new milo archive
+        let pack_settings = MiloPackSettings {
+            block_type: self.uncompressed.then_some(BlockType::TypeA),
+            ..Default::default()
+        };
+        let archive = MiloArchive::from_object_dir_with_settings(&obj_dir, &out_sys_info, &pack_settings)?;
+
+        let mut stream = FileStream::from_path_as_read_write_create(out_milo_path)?;
+        archive.write_to_stream(&mut stream)?;
+
+        if let Some(file_name) = out_milo_path.file_name() {
+            let file_name = file_name.to_str().unwrap();
+            println!("Successfully wrote {}", file_name);
+        }
+
+        Ok(())
+    }
+}
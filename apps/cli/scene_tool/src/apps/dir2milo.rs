@@ -4,12 +4,19 @@ use clap::Parser;
 use std::error::Error;
 
 use std::path::Path;
+use thiserror::Error;
 
-
-use pikaxe::{Platform, SystemInfo};
+use pikaxe::{GamePreset, Platform};
 use pikaxe::io::*;
 use pikaxe::scene::{Object, ObjectDir, PackedObject, Tex};
 
+#[derive(Debug, Error)]
+pub enum Dir2MiloError {
+    #[error("Directory entry is required for milo version {version} but wasn't found (extract milo with milo2dir to create dir.dta or use preset with milo version 10)")]
+    MissingDirectoryEntry {
+        version: u32
+    }
+}
 
 #[derive(Parser, Debug)]
 pub struct Dir2MiloApp {
@@ -17,22 +24,25 @@ pub struct Dir2MiloApp {
     pub dir_path: String,
     #[arg(help = "Path to output milo scene", required = true)]
     pub milo_path: String,
+    #[arg(long, default_value = "gh1", help = "Game preset (gh1, gh2, gh80s, gh2_x360, rb1, rb2, tbrb, gdrb, rb3, dc1)")]
+    pub preset: String,
 }
 
-// TODO: Get from args
-const SYSTEM_INFO: SystemInfo = SystemInfo {
-    version: 10,
-    platform: Platform::PS2,
-    endian: IOEndian::Little,
-};
-
 impl SubApp for Dir2MiloApp {
     fn process(&mut self) -> Result<(), Box<dyn Error>> {
         let dir_path = Path::new(&self.dir_path);
         let milo_path = Path::new(&self.milo_path);
 
-        let dir_obj = ObjectDir::from_path(dir_path, &SYSTEM_INFO)?;
-        let archive = MiloArchive::from_object_dir(&dir_obj, &SYSTEM_INFO, None)?;
+        let system_info = GamePreset::from_name(&self.preset)?
+            .get_system_info(Some(Platform::guess_platform(milo_path)));
+
+        let dir_obj = ObjectDir::from_path(dir_path, &system_info)?;
+
+        if system_info.version > 10 {
+            check_dir_entries(&dir_obj, system_info.version)?;
+        }
+
+        let archive = MiloArchive::from_object_dir(&dir_obj, &system_info, None)?;
 
         // Write to file
         let mut stream = FileStream::from_path_as_read_write_create(milo_path)?;
@@ -40,4 +50,18 @@ impl SubApp for Dir2MiloApp {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+fn check_dir_entries(obj_dir: &ObjectDir, version: u32) -> Result<(), Dir2MiloError> {
+    let ObjectDir::ObjectDir(dir) = obj_dir;
+
+    if dir.dir_entry.is_none() {
+        return Err(Dir2MiloError::MissingDirectoryEntry { version });
+    }
+
+    for sub_dir in dir.sub_dirs.iter() {
+        check_dir_entries(sub_dir, version)?;
+    }
+
+    Ok(())
+}
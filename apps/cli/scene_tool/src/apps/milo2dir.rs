@@ -8,7 +8,7 @@ use thiserror::Error;
 
 use pikaxe::{Platform, SystemInfo};
 use pikaxe::io::*;
-use pikaxe::scene::{Object, ObjectDir, PackedObject, Tex};
+use pikaxe::scene::{DIR_INFO_FILE_NAME, Object, ObjectDir, PackedObject, Tex};
 use pikaxe::texture::write_rgba_to_file;
 
 // TODO: Use this error somewhere or refactor
@@ -32,12 +32,14 @@ pub struct Milo2DirApp {
     pub big_endian: bool,
     #[arg(long, default_value = "ps2", help = "Platform (ps2, ps3, wii, x360)")]
     pub platform: String,
-    #[arg(long, help = "Game preset (gh1, gh2, gh80s, gh2_x360)")]
+    #[arg(long, help = "Game preset (gh1, gh2, gh80s, gh2_x360, rb1, rb2, tbrb, gdrb, rb3, dc1). Guessed from milo if not provided")]
     pub preset: Option<String>, // Using Option<> because default of "" is unsupported
     #[arg(help = "Path to input milo scene", required = true)]
     pub milo_path: String,
     #[arg(help = "Path to output directory", required = true)]
     pub dir_path: String,
+    #[arg(long, help = "Guess entry sizes if they can't be determined exactly")]
+    pub guess_entry_sizes: bool,
     #[arg(long, help = "Automatically convert textures to PNG")]
     pub convert_textures: bool
 }
@@ -46,16 +48,7 @@ impl GameOptions for Milo2DirApp {
     fn get_system_info(&self) -> SystemInfo {
         SystemInfo {
             version: self.milo_version,
-            platform: match self.platform.to_lowercase().as_str() {
-                "ps2" => Platform::PS2,
-                "ps3" => Platform::PS3,
-                "wii" => Platform::Wii,
-                "xbox 360" => Platform::X360,
-                "xbox360" => Platform::X360,
-                "x360" => Platform::X360,
-                "360" => Platform::X360,
-                _ => Platform::PS2
-            },
+            platform: Platform::from_name(&self.platform).unwrap_or(Platform::PS2),
             endian: match self.big_endian {
                 true => IOEndian::Big,
                 _ => IOEndian::Little
@@ -75,14 +68,15 @@ impl SubApp for Milo2DirApp {
             println!("Opening {}", file_name);
         }
 
-        let mut stream = FileStream::from_path_as_read_open(milo_path)?;
-        let milo = MiloArchive::from_stream(&mut stream)?;
+        let milo = MiloArchive::from_path(milo_path)?;
 
-        // TODO: First get system info from args then guess if not supplied
-        //let system_info = self.get_system_info();
-        let system_info = SystemInfo::guess_system_info(&milo, &milo_path);
+        let system_info = SystemInfo::from_preset_or_guess(self.preset.as_deref(), &milo, milo_path)?;
 
-        let obj_dir = milo.unpack_directory(&system_info)?;
+        let settings = MiloUnpackSettings {
+            guess_entry_sizes: self.guess_entry_sizes
+        };
+
+        let obj_dir = milo.unpack_directory_with_settings(&system_info, &settings)?;
         //obj_dir.unpack_entries(&SYSTEM_INFO);
 
         //obj_dir.entries.sort_by(compare_entries_by_name);
@@ -126,6 +120,42 @@ fn extract_contents(milo_dir: &ObjectDir, output_path: &Path, convert_texures: b
         }
     }
 
+    let ObjectDir::ObjectDir(dir) = milo_dir;
+
+    if let Some(dir_entry) = &dir.dir_entry {
+        // Write directory entry (without inline sub directories)
+        let packed = PackedObject {
+            name: dir.name.to_owned(),
+            object_type: dir.dir_type.to_owned(),
+            data: dir_entry.data.to_owned()
+        };
+
+        let entry_dir = Path::join(output_path, &packed.object_type);
+        fs::create_dir_all(&entry_dir)?;
+
+        if extract_packed_object(&packed, &entry_dir).is_err() {
+            println!("There was an error extracting {}", packed.name);
+        }
+    }
+
+    // Extract inline sub directories to own folders
+    for sub_dir in dir.sub_dirs.iter() {
+        let ObjectDir::ObjectDir(sub_dir_base) = sub_dir;
+        extract_contents(sub_dir, &Path::join(output_path, &sub_dir_base.name), convert_texures, info)?;
+    }
+
+    // Write directory info so directory entry and sub directories can be read back
+    fs::create_dir_all(output_path)?;
+    let dir_info_path = Path::join(output_path, DIR_INFO_FILE_NAME);
+
+    let mut dir_info = Vec::new();
+    milo_dir.create_dir_info().print(&mut dir_info)?;
+    fs::write(&dir_info_path, dir_info)?;
+
+    if let Some(name) = dir_info_path.to_str() {
+        println!("Wrote {}", name);
+    }
+
     Ok(())
 }
 
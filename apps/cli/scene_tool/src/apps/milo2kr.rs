@@ -22,6 +22,8 @@ pub struct Milo2KrApp {
     pub midi: String,
     #[arg(short, long, help = "Enable to leave output milo archive uncompressed", required = false)]
     pub uncompressed: bool,
+    #[arg(long, help = "Game preset of input milo (gh2, rb1, tbrb, ...). Guessed from milo if not provided")]
+    pub preset: Option<String>,
 }
 
 const LIPSYNC_FPS: u32 = 30;
@@ -43,12 +45,9 @@ impl SubApp for Milo2KrApp {
             println!("Opening {}", file_name);
         }
 
-        let mut stream = FileStream::from_path_as_read_open(milo_path)?;
-        let milo = MiloArchive::from_stream(&mut stream)?;
+        let milo = MiloArchive::from_path(milo_path)?;
 
-        // TODO: First get system info from args then guess if not supplied
-        //let system_info = self.get_system_info();
-        let system_info = SystemInfo::guess_system_info(&milo, &milo_path);
+        let system_info = SystemInfo::from_preset_or_guess(self.preset.as_deref(), &milo, milo_path)?;
 
         let obj_dir = milo.unpack_directory(&system_info)?;
         //obj_dir.unpack_entries(&SYSTEM_INFO);
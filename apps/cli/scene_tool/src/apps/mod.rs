@@ -3,10 +3,12 @@ use std::error::Error;
 
 use pikaxe::SystemInfo;
 
+mod convert;
 mod dir2milo;
 mod milo2dir;
 mod milo2kr;
 mod savemilo;
+pub use self::convert::*;
 pub use self::dir2milo::*;
 pub use self::milo2dir::*;
 pub use self::milo2kr::*;
@@ -29,6 +31,8 @@ struct Options {
 
 #[derive(Subcommand, Debug)]
 enum SubCommand {
+    #[command(name = "convert", about = "Converts milo scene to other platform, version or endian")]
+    Convert(ConvertApp),
     #[command(name = "dir2milo", about = "Creates milo scene from input directory")]
     Dir2Milo(Dir2MiloApp),
     #[command(name = "milo2dir", about = "Extracts content of milo scene to directory")]
@@ -53,6 +57,7 @@ impl SceneTool {
 
     pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
         match &mut self.options.commands {
+            SubCommand::Convert(app) => app.process(),
             SubCommand::Dir2Milo(app) => app.process(),
             SubCommand::Milo2Dir(app) => app.process(),
             SubCommand::Milo2Kr(app) => app.process(),
@@ -9,7 +9,8 @@ use thiserror::Error;
 use pikaxe::{Platform, SystemInfo};
 use pikaxe::io::*;
 use pikaxe::scene::{EncodedSamples, Object, ObjectDir, ObjectReadWrite, PackedObject, Tex};
-use pikaxe::texture::{Bitmap, Image, swap_image_bytes, write_rgba_to_file};
+use pikaxe::convert::convert_bitmap;
+use pikaxe::texture::write_rgba_to_file;
 
 
 #[derive(Parser, Debug)]
@@ -18,12 +19,20 @@ pub struct SaveMiloApp {
     pub in_milo_path: String,
     #[arg(help = "Path to output milo scene", required = true)]
     pub out_milo_path: String,
+    #[arg(long, help = "Game preset of input milo (gh2, rb1, tbrb, ...). Guessed from milo if not provided")]
+    pub in_preset: Option<String>,
     #[arg(short = 'm', long, help = "Milo archive version (10, 24, 25)")]
     pub milo_version: Option<u32>,
     #[arg(short = 'b' , long, help = "Use big endian serialization")]
     pub big_endian: Option<bool>,
     #[arg(short = 'u' , long, help = "Leave output milo archive uncompressed")]
     pub uncompressed: bool,
+    #[arg(short = 'g' , long, help = "Write output milo archive as single gzip stream (PS2 .gz)")]
+    pub gzip: bool,
+    #[arg(long, help = "Guess entry sizes if they can't be determined exactly")]
+    pub guess_entry_sizes: bool,
+    #[arg(short = 'p', long, help = "Keep entry order and block layout of input milo (unchanged milos are written back byte-for-byte)")]
+    pub preserve_layout: bool,
 }
 
 impl SubApp for SaveMiloApp {
@@ -37,20 +46,19 @@ impl SubApp for SaveMiloApp {
         }
 
         // Open milo file
-        let mut stream = FileStream::from_path_as_read_open(in_milo_path)?;
-        let milo = MiloArchive::from_stream(&mut stream)?;
+        let read_settings = MiloReadSettings {
+            preserve_layout: self.preserve_layout,
+            ..Default::default()
+        };
+        let milo = MiloArchive::from_path_with_settings(in_milo_path, &read_settings)?;
 
         // Guess platform info
-        let in_sys_info = SystemInfo::guess_system_info(&milo, in_milo_path);
+        let in_sys_info = SystemInfo::from_preset_or_guess(self.in_preset.as_deref(), &milo, in_milo_path)?;
         let mut out_sys_info = SystemInfo {
             platform: Platform::guess_platform(out_milo_path),
             ..in_sys_info
         };
 
-        if in_sys_info.platform.ne(&out_sys_info.platform) && out_sys_info.platform.eq(&Platform::Wii) {
-            todo!("Converting milo to wii is not currently supported!");
-        }
-
         if out_sys_info.platform.eq(&Platform::PS3) {
             // Force big endian if ps3
             out_sys_info.endian = IOEndian::Big;
@@ -76,7 +84,11 @@ impl SubApp for SaveMiloApp {
         }
 
         // Unpack milo
-        let mut obj_dir = milo.unpack_directory(&in_sys_info)?;
+        let settings = MiloUnpackSettings {
+            guess_entry_sizes: self.guess_entry_sizes
+        };
+
+        let mut obj_dir = milo.unpack_directory_with_settings(&in_sys_info, &settings)?;
         unpack_entries(&mut obj_dir, &in_sys_info, false);
 
         if in_sys_info.platform.ne(&out_sys_info.platform) {
@@ -106,7 +118,17 @@ impl SubApp for SaveMiloApp {
 
         // Write to new milo archive
         let block_type = self.uncompressed.then(|| BlockType::TypeA);
-        let archive = MiloArchive::from_object_dir(&obj_dir, &out_sys_info, block_type)?;
+        let structure_type = match self.gzip {
+            true => MiloStructureType::GZIP,
+            _ => MiloStructureType::Blocked
+        };
+
+        let pack_settings = MiloPackSettings {
+            structure_type,
+            block_type,
+            original: self.preserve_layout.then_some(&milo),
+        };
+        let archive = MiloArchive::from_object_dir_with_settings(&obj_dir, &out_sys_info, &pack_settings)?;
 
         let mut stream = FileStream::from_path_as_read_write_create(out_milo_path)?;
         archive.write_to_stream(&mut stream)?;
@@ -121,11 +143,6 @@ impl SubApp for SaveMiloApp {
 }
 
 fn unpack_entries(milo_dir: &mut ObjectDir, info: &SystemInfo, all: bool) {
-    if all {
-        milo_dir.unpack_entries(info).unwrap();
-        return;
-    }
-
     let supported_types = [
         "CharClipSamples",
         "CubeTex",
@@ -136,14 +153,10 @@ fn unpack_entries(milo_dir: &mut ObjectDir, info: &SystemInfo, all: bool) {
         "Trans"
     ];
 
-    for entry in milo_dir.get_entries_mut() {
-        if !supported_types.contains(&entry.get_type()) {
-            continue
-        }
+    let errors = milo_dir.try_unpack_entries_with_filter(info, |entry| all || supported_types.contains(&entry.get_type()));
 
-        if let Some(new_entry) = entry.unpack(info) {
-            *entry = new_entry;
-        }
+    for error in errors {
+        println!("WARN: Unable to unpack: {error}");
     }
 }
 
@@ -157,29 +170,14 @@ fn convert_textures(milo_dir: &mut ObjectDir, in_sys_info: &SystemInfo, out_sys_
 
 fn transcode_texture(tex: &mut Tex, in_sys_info: &SystemInfo, out_sys_info: &SystemInfo) {
     if let Some(bitmap) = &mut tex.bitmap {
-        match (bitmap.encoding, &in_sys_info.platform, &out_sys_info.platform) {
-            (32, &Platform::X360, _) => {},
-            (_, &Platform::PS3, &Platform::X360) | (_, &Platform::X360, &Platform::PS3) => {
-                // Just swap bytes to save time
-                if !bitmap.raw_data.is_empty() {
-                    swap_image_bytes(&mut bitmap.raw_data);
-                    println!("Swapped bytes for \"{}\"", tex.name.as_str());
-                }
-                return;
-            },
-            _ => {}
-        }
-
-        // Decode proper
-        if let Some(rgba) = bitmap.unpack_rgba(in_sys_info).ok() {
-            println!("Successfully decoded \"{}\"", tex.name.as_str());
+        match convert_bitmap(bitmap, in_sys_info, out_sys_info) {
+            Ok(converted) => {
+                println!("Successfully converted \"{}\"", tex.name.as_str());
 
-            *bitmap = Bitmap::from_image(Image::FromRGBA {
-                rgba: rgba.as_slice(),
-                width: bitmap.width,
-                height: bitmap.height,
-                mips: 0,
-            }, out_sys_info);
+                tex.bpp = converted.bpp as u32;
+                *bitmap = converted;
+            },
+            Err(error) => println!("WARN: Unable to convert \"{}\": {error}", tex.name.as_str()),
         }
     }
-}
\ No newline at end of file
+}
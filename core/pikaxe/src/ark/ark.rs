@@ -1,5 +1,10 @@
+use crate::ark::ArkReadError;
+use crate::io::{crypt_dtb_style, inflate_deflate_block_no_buffer, inflate_gzip_block_no_buffer};
+use flate2::read::{DeflateDecoder, GzDecoder};
 #[cfg(feature = "python")] use pyo3::prelude::*;
-use std::{path::PathBuf, todo};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Take};
+use std::path::PathBuf;
 
 #[derive(Debug, Default)]
 #[cfg_attr(feature = "python", pyclass)]
@@ -9,9 +14,12 @@ pub struct Ark {
     #[cfg_attr(feature = "pyo3", pyo3(get, set))] pub entries: Vec<ArkOffsetEntry>,
     pub path: PathBuf, // Hdr/ark path,
     pub part_paths: Vec<PathBuf>,
+    pub part_sizes: Vec<u64>, // Sizes from hdr (v3+)
+    pub part_checksums: Vec<u32>, // v6+
+    pub file_name_hashes: Vec<u32>, // v9+ (sparse hash table, empty slots are 0)
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 //#[cfg_attr(feature = "python", pyclass)]
 pub enum ArkEncryption {
     None,
@@ -55,6 +63,42 @@ impl Default for ArkEncryption {
     }
 }
 
+impl ArkEncryption {
+    pub fn from_key(key: i32) -> ArkEncryption {
+        // Newer games use negative keys
+        if key < 0 {
+            ArkEncryption::NewEncryption(key)
+        } else {
+            ArkEncryption::ClassicEncryption(key)
+        }
+    }
+
+    pub fn get_key(&self) -> Option<i32> {
+        match self {
+            ArkEncryption::None => None,
+            ArkEncryption::ClassicEncryption(key) => Some(*key),
+            ArkEncryption::NewEncryption(key) => Some(*key),
+        }
+    }
+
+    pub fn is_key_valid(&self) -> bool {
+        match self {
+            ArkEncryption::None => true,
+            ArkEncryption::ClassicEncryption(key) => *key >= 0,
+            ArkEncryption::NewEncryption(key) => *key < 0,
+        }
+    }
+
+    pub fn crypt(&self, data: &mut [u8]) {
+        match self {
+            ArkEncryption::None => {},
+            ArkEncryption::ClassicEncryption(key) => crypt_dtb_style(data, *key, None),
+            // Same as classic but each byte is also xor'd with 0xFF
+            ArkEncryption::NewEncryption(key) => crypt_dtb_style(data, *key, Some(0xFF)),
+        }
+    }
+}
+
 #[cfg(feature = "python")]
 #[pymethods]
 impl Ark {
@@ -67,39 +111,190 @@ impl Ark {
 
     #[getter]
     fn get_encryption(&self) -> PyResult<Option<i32>> {
-        let key = match self.encryption {
-            ArkEncryption::ClassicEncryption(key) => Some(key),
-            ArkEncryption::NewEncryption(key) => Some(key),
-            _ => None
-        };
+        Ok(self.encryption.get_key())
+    }
+}
 
-        Ok(key)
+impl ArkOffsetEntry {
+    pub fn is_compressed(&self) -> bool {
+        self.inflated_size != 0 && self.inflated_size != self.size
     }
 }
 
 impl Ark {
-    pub fn get_stream(&self, id: u32) -> Result<Vec<u8>, std::io::Error> {
-        use std::io::{Read, Seek, SeekFrom};
+    /// Reads entry data, inflating it if entry is compressed
+    pub fn get_stream(&self, id: u32) -> Result<Vec<u8>, ArkReadError> {
+        let entry = self.get_entry(id)?;
+        let data = self.get_raw_stream(id)?;
+
+        if !entry.is_compressed() {
+            return Ok(data);
+        }
+
+        let inflated_data = if is_gzip(&data) {
+            inflate_gzip_block_no_buffer(&data)
+        } else {
+            inflate_deflate_block_no_buffer(&data)
+        }.map_err(|_| ArkReadError::CantInflateEntry { path: entry.path.to_owned() })?;
+
+        if inflated_data.len() != entry.inflated_size {
+            return Err(ArkReadError::CantInflateEntry { path: entry.path.to_owned() });
+        }
+
+        Ok(inflated_data)
+    }
+
+    /// Reads entry data as stored in ark (no decompression)
+    pub fn get_raw_stream(&self, id: u32) -> Result<Vec<u8>, ArkReadError> {
+        let entry = self.get_entry(id)?;
+
+        let mut buffer = vec![0u8; entry.size];
+        self.open_entry_file(entry)?
+            .read_exact(&mut buffer)
+            .map_err(|_| ArkReadError::CantReadEntry { path: entry.path.to_owned() })?;
 
-        let entry = self
+        Ok(buffer)
+    }
+
+    /// Opens streaming reader over entry data, inflating it if entry is compressed
+    pub fn get_reader(&self, id: u32) -> Result<Box<dyn Read>, ArkReadError> {
+        let entry = self.get_entry(id)?;
+        let mut reader = BufReader::new(self.open_entry_file(entry)?);
+
+        if !entry.is_compressed() {
+            return Ok(Box::new(reader));
+        }
+
+        // Peek at magic to determine compression type
+        let is_gzip = reader
+            .fill_buf()
+            .map(is_gzip)
+            .map_err(|_| ArkReadError::CantReadEntry { path: entry.path.to_owned() })?;
+
+        if is_gzip {
+            Ok(Box::new(GzDecoder::new(reader)))
+        } else {
+            Ok(Box::new(DeflateDecoder::new(reader)))
+        }
+    }
+
+    fn get_entry(&self, id: u32) -> Result<&ArkOffsetEntry, ArkReadError> {
+        self
             .entries
             .iter()
             .find(|e| e.id == id)
-            .expect("Invalid id");
+            .ok_or(ArkReadError::EntryNotFound { id })
+    }
 
+    fn open_entry_file(&self, entry: &ArkOffsetEntry) -> Result<Take<File>, ArkReadError> {
         // Open from main ark or ark part
         let file_path = if self.version >= 3 && self.version <= 10 {
-            &self.part_paths[entry.part as usize]
+            self.part_paths
+                .get(entry.part as usize)
+                .ok_or(ArkReadError::ArkPartNotFound { part: entry.part })?
         } else {
             &self.path
         };
 
-        // TODO: Support reading from non-first ark part?
-        let mut file = std::fs::File::open(file_path)?;
-        file.seek(SeekFrom::Start(entry.offset))?;
+        let mut file = File::open(file_path)
+            .map_err(|_| ArkReadError::CantOpenArk)?;
 
-        let mut buffer = vec![0u8; entry.size];
-        file.read_exact(&mut buffer)?;
-        Ok(buffer)
+        file.seek(SeekFrom::Start(entry.offset))
+            .map_err(|_| ArkReadError::CantReadEntry { path: entry.path.to_owned() })?;
+
+        Ok(file.take(entry.size as u64))
     }
-}
\ No newline at end of file
+}
+
+fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&[0x1F, 0x8B])
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+    use crate::ark::ArkBuilder;
+    use flate2::Compression;
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use std::io::Write;
+
+    fn create_compressed_ark(name: &str, data: &[u8]) -> Ark {
+        let mut gzip_encoder = GzEncoder::new(Vec::new(), Compression::default());
+        gzip_encoder.write_all(data).unwrap();
+
+        let mut deflate_encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        deflate_encoder.write_all(data).unwrap();
+
+        let hdr_path = std::env::temp_dir()
+            .join("pikaxe_tests")
+            .join(name)
+            .join("main.hdr");
+
+        let mut ark = ArkBuilder::new(5)
+            .and_data("deflate.dta", deflate_encoder.finish().unwrap())
+            .and_data("gzip.dta", gzip_encoder.finish().unwrap())
+            .and_data("raw.dta", data.to_vec())
+            .build(&hdr_path)
+            .unwrap();
+
+        // Mark as compressed
+        for entry in ark.entries.iter_mut().filter(|e| e.path != "raw.dta") {
+            entry.inflated_size = data.len();
+        }
+
+        ark
+    }
+
+    fn get_entry_id(ark: &Ark, path: &str) -> u32 {
+        ark.entries.iter().find(|e| e.path == path).unwrap().id
+    }
+
+    #[rstest]
+    #[case("deflate.dta")]
+    #[case("gzip.dta")]
+    #[case("raw.dta")]
+    fn get_stream_inflates_entry(#[case] path: &str) {
+        let data = b"(song (name \"Song\") (artist \"Artist\") (name \"Song\"))".repeat(8);
+        let ark = create_compressed_ark("get_stream_inflates_entry", &data);
+        let id = get_entry_id(&ark, path);
+
+        assert_eq!(data, ark.get_stream(id).unwrap());
+
+        let mut streamed_data = Vec::new();
+        ark.get_reader(id).unwrap().read_to_end(&mut streamed_data).unwrap();
+
+        assert_eq!(data, streamed_data);
+    }
+
+    #[rstest]
+    fn get_raw_stream_skips_inflate() {
+        let data = b"(a b c d e f)".repeat(16);
+        let ark = create_compressed_ark("get_raw_stream_skips_inflate", &data);
+        let entry = ark.entries.iter().find(|e| e.path == "gzip.dta").unwrap();
+
+        let raw_data = ark.get_raw_stream(entry.id).unwrap();
+
+        assert_eq!(entry.size, raw_data.len());
+        assert!(is_gzip(&raw_data));
+    }
+
+    #[rstest]
+    fn get_stream_invalid_id() {
+        let ark = create_compressed_ark("get_stream_invalid_id", b"(a)");
+
+        assert!(matches!(ark.get_stream(100), Err(ArkReadError::EntryNotFound { id: 100 })));
+        assert!(matches!(ark.get_reader(100), Err(ArkReadError::EntryNotFound { id: 100 })));
+    }
+
+    #[rstest]
+    fn get_stream_bad_inflated_size() {
+        let data = b"(a b c)".repeat(16);
+        let mut ark = create_compressed_ark("get_stream_bad_inflated_size", &data);
+        let id = get_entry_id(&ark, "deflate.dta");
+
+        ark.entries.iter_mut().find(|e| e.id == id).unwrap().inflated_size += 1;
+
+        assert!(matches!(ark.get_stream(id), Err(ArkReadError::CantInflateEntry { .. })));
+    }
+}
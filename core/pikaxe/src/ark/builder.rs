@@ -0,0 +1,743 @@
+use crate::ark::*;
+use crate::io::*;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_PART_SIZE: u64 = 0x4000_0000; // 1GB
+
+#[derive(Debug)]
+pub enum ArkEntrySource {
+    File(PathBuf),
+    Data(Vec<u8>),
+}
+
+#[derive(Debug)]
+pub struct ArkBuilderEntry {
+    pub path: String,
+    pub source: ArkEntrySource,
+}
+
+impl ArkBuilderEntry {
+    pub(crate) fn get_size(&self) -> Result<u64, ArkWriteError> {
+        match &self.source {
+            ArkEntrySource::File(file_path) => std::fs::metadata(file_path)
+                .map(|m| m.len())
+                .map_err(|_| ArkWriteError::CantReadEntry {
+                    path: self.path.to_owned()
+                }),
+            ArkEntrySource::Data(data) => Ok(data.len() as u64),
+        }
+    }
+
+    pub(crate) fn write_data<T: Write>(&self, writer: &mut T) -> Result<u64, ArkWriteError> {
+        match &self.source {
+            ArkEntrySource::File(file_path) => {
+                let file = File::open(file_path)
+                    .map_err(|_| ArkWriteError::CantReadEntry {
+                        path: self.path.to_owned()
+                    })?;
+
+                // Stream file contents so large files aren't fully buffered
+                let mut reader = BufReader::new(file);
+                std::io::copy(&mut reader, writer)
+                    .map_err(|_| ArkWriteError::CantWriteArk)
+            },
+            ArkEntrySource::Data(data) => {
+                writer.write_all(data)
+                    .map_err(|_| ArkWriteError::CantWriteArk)?;
+
+                Ok(data.len() as u64)
+            }
+        }
+    }
+}
+
+/// Entries to write to archive (shared by ark and package builders)
+#[derive(Debug, Default)]
+pub struct BuilderEntries {
+    entries: Vec<ArkBuilderEntry>,
+}
+
+impl BuilderEntries {
+    pub fn new() -> BuilderEntries {
+        BuilderEntries::default()
+    }
+
+    /// Adds all files in directory using relative path with forward slashes as entry path.
+    /// Returns none if directory can't be read.
+    pub fn from_dir(dir_path: &Path) -> Option<BuilderEntries> {
+        let mut entries = BuilderEntries::new();
+        let files = dir_path.get_all_files().ok()?;
+
+        for file_path in files {
+            let entry_path = file_path
+                .strip_prefix(dir_path)
+                .unwrap_or(&file_path)
+                .to_str()
+                .unwrap_or_default()
+                .replace('\\', "/");
+
+            entries.push_file(entry_path, file_path);
+        }
+
+        Some(entries)
+    }
+
+    pub fn push_file<S: Into<String>, T: Into<PathBuf>>(&mut self, entry_path: S, file_path: T) {
+        self.entries.push(ArkBuilderEntry {
+            path: entry_path.into(),
+            source: ArkEntrySource::File(file_path.into()),
+        });
+    }
+
+    pub fn push_data<S: Into<String>>(&mut self, entry_path: S, data: Vec<u8>) {
+        self.entries.push(ArkBuilderEntry {
+            path: entry_path.into(),
+            source: ArkEntrySource::Data(data),
+        });
+    }
+
+    pub fn get_entries(&self) -> &Vec<ArkBuilderEntry> {
+        &self.entries
+    }
+
+    pub fn get_entries_mut(&mut self) -> &mut Vec<ArkBuilderEntry> {
+        &mut self.entries
+    }
+}
+
+#[derive(Debug)]
+pub struct ArkBuilder {
+    version: i32,
+    encryption: ArkEncryption,
+    part_size: u64,
+    entries: BuilderEntries,
+}
+
+impl ArkBuilder {
+    pub fn new(version: i32) -> ArkBuilder {
+        ArkBuilder {
+            version,
+            encryption: ArkEncryption::None,
+            part_size: DEFAULT_PART_SIZE,
+            entries: BuilderEntries::new(),
+        }
+    }
+
+    pub fn from_dir<T: AsRef<Path>>(dir_path: T, version: i32) -> Result<ArkBuilder, ArkWriteError> {
+        let dir_path = dir_path.as_ref();
+
+        Ok(ArkBuilder {
+            entries: BuilderEntries::from_dir(dir_path)
+                .ok_or_else(|| ArkWriteError::CantReadEntry {
+                    path: dir_path.to_str().unwrap_or_default().to_owned()
+                })?,
+            ..ArkBuilder::new(version)
+        })
+    }
+
+    pub fn with_encryption(mut self, encryption: ArkEncryption) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    pub fn with_part_size(mut self, part_size: u64) -> Self {
+        self.part_size = part_size;
+        self
+    }
+
+    pub fn and_file<S: Into<String>, T: Into<PathBuf>>(mut self, ark_path: S, file_path: T) -> Self {
+        self.entries.push_file(ark_path, file_path);
+        self
+    }
+
+    pub fn and_data<S: Into<String>>(mut self, ark_path: S, data: Vec<u8>) -> Self {
+        self.entries.push_data(ark_path, data);
+        self
+    }
+
+    pub fn get_entries(&self) -> &Vec<ArkBuilderEntry> {
+        self.entries.get_entries()
+    }
+
+    /// Writes ark to output path. For versions 3-10 the path is the .hdr file and
+    /// parts are written next to it as <name>_<n>.ark. Amp arks are a single .ark file.
+    pub fn build<T: AsRef<Path>>(mut self, output_path: T) -> Result<Ark, ArkWriteError> {
+        let output_path = output_path.as_ref();
+
+        // Entries are written in sorted order
+        self.entries.get_entries_mut().sort_by(|a, b| a.path.cmp(&b.path));
+
+        match self.version {
+            1 | 2 => self.build_amp_ark(output_path),
+            3 | 5 => self.build_hdr_ark(output_path),
+            _ => Err(ArkWriteError::ArkVersionNotSupported {
+                version: self.version
+            })
+        }
+    }
+
+    fn build_hdr_ark(&self, hdr_path: &Path) -> Result<Ark, ArkWriteError> {
+        let mut ark = Ark {
+            version: self.version,
+            encryption: self.encryption,
+            path: hdr_path.to_owned(),
+            ..Default::default()
+        };
+
+        let mut part_sizes = Vec::new();
+        let mut part_writer: Option<BufWriter<File>> = None;
+        let mut part_offset = 0u64;
+
+        for (id, entry) in self.entries.get_entries().iter().enumerate() {
+            let size = entry.get_size()?;
+
+            if size > u32::MAX as u64 {
+                return Err(ArkWriteError::EntryTooLarge {
+                    path: entry.path.to_owned(),
+                    version: self.version
+                });
+            }
+
+            // Start new part if entry doesn't fit
+            if part_writer.is_none() || (part_offset > 0 && (part_offset + size) > self.part_size) {
+                if let Some(mut writer) = part_writer.take() {
+                    writer.flush().map_err(|_| ArkWriteError::CantWriteArk)?;
+                    part_sizes.push(part_offset);
+                }
+
+                let part_path = get_part_path(hdr_path, ark.part_paths.len());
+                let file = create_new_file(&part_path)
+                    .map_err(|_| ArkWriteError::CantWriteArk)?;
+
+                ark.part_paths.push(part_path);
+                part_writer = Some(BufWriter::new(file));
+                part_offset = 0;
+            }
+
+            let writer = part_writer.as_mut().unwrap();
+            let written_size = entry.write_data(writer)?;
+
+            ark.entries.push(ArkOffsetEntry {
+                id: id as u32,
+                path: entry.path.to_owned(),
+                offset: part_offset,
+                part: (ark.part_paths.len() - 1) as u32,
+                size: written_size as usize,
+                inflated_size: 0,
+            });
+
+            part_offset += written_size;
+        }
+
+        if let Some(mut writer) = part_writer.take() {
+            writer.flush().map_err(|_| ArkWriteError::CantWriteArk)?;
+            part_sizes.push(part_offset);
+        }
+
+        if self.version == 3 && part_sizes.iter().sum::<u64>() > u32::MAX as u64 {
+            // Offsets are stored as u32 values
+            return Err(ArkWriteError::EntryTooLarge {
+                path: ark.entries.last().map(|e| e.path.to_owned()).unwrap_or_default(),
+                version: self.version
+            });
+        }
+
+        // Write hdr
+        create_missing_dirs(hdr_path)
+            .map_err(|_| ArkWriteError::CantWriteArk)?;
+        ark.part_sizes = part_sizes;
+        ark.save_header(&ark.part_sizes)?;
+
+        ark.sort_entries_by_name();
+        Ok(ark)
+    }
+
+    fn build_amp_ark(&self, ark_path: &Path) -> Result<Ark, ArkWriteError> {
+        let mut ark = Ark {
+            version: self.version,
+            encryption: ArkEncryption::None,
+            path: ark_path.to_owned(),
+            ..Default::default()
+        };
+
+        for (id, entry) in self.entries.get_entries().iter().enumerate() {
+            ark.entries.push(ArkOffsetEntry {
+                id: id as u32,
+                path: entry.path.to_owned(),
+                offset: 0,
+                part: 0,
+                size: entry.get_size()? as usize,
+                inflated_size: 0,
+            });
+        }
+
+        // Data is placed directly after header
+        let mut offset = ark.calc_amp_header_size() as u64;
+        for entry in ark.entries.iter_mut() {
+            entry.offset = offset;
+            offset += entry.size as u64;
+        }
+
+        let mut header_data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut header_data);
+        ark.write_amp_header(&mut stream)?;
+
+        let file = create_new_file(ark_path)
+            .map_err(|_| ArkWriteError::CantWriteArk)?;
+        let mut writer = BufWriter::new(file);
+
+        writer.write_all(&header_data)
+            .map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        for (entry, ark_entry) in self.entries.get_entries().iter().zip(ark.entries.iter()) {
+            let written_size = entry.write_data(&mut writer)?;
+
+            if written_size != ark_entry.size as u64 {
+                // File changed while writing
+                return Err(ArkWriteError::CantReadEntry {
+                    path: entry.path.to_owned()
+                });
+            }
+        }
+
+        writer.flush().map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        ark.sort_entries_by_name();
+        Ok(ark)
+    }
+}
+
+impl Ark {
+    /// Appends new or replacement entries to a new ark part and rewrites only the hdr.
+    /// Existing entries keep their ids and added entries are given the next available ids.
+    ///
+    /// Only v3 and v5 arks can be patched as other versions store part names, checksums or hashes
+    /// in hdr that aren't updated yet. Other versions return `PatchVersionNotSupported`.
+    pub fn patch(&mut self, entries: Vec<ArkBuilderEntry>) -> Result<(), ArkWriteError> {
+        if !matches!(self.version, 3 | 5) {
+            return Err(ArkWriteError::PatchVersionNotSupported {
+                version: self.version
+            });
+        }
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        // Use part sizes from hdr so unrelated or padded files in ark directory don't shift offsets
+        let part_count = self.part_sizes.len();
+        if self.part_paths.len() < part_count {
+            return Err(ArkWriteError::CantWriteArk);
+        }
+
+        let new_part_start = self.part_sizes.iter().sum::<u64>();
+        let mut part_offset = 0u64;
+        let mut patched_entries = Vec::new();
+
+        // Check sizes before anything is written
+        for entry in entries.iter() {
+            let size = entry.get_size()?;
+
+            if size > u32::MAX as u64 || (self.version == 3 && (new_part_start + part_offset + size) > u32::MAX as u64) {
+                // Offsets are stored as u32 values in v3
+                return Err(ArkWriteError::EntryTooLarge {
+                    path: entry.path.to_owned(),
+                    version: self.version
+                });
+            }
+
+            patched_entries.push((entry.path.as_str(), new_part_start + part_offset, size));
+            part_offset += size;
+        }
+
+        // Write entries to new part
+        let part_path = get_part_path(&self.path, part_count);
+        let file = create_new_file(&part_path)
+            .map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        let mut writer = BufWriter::new(file);
+
+        for (entry, (_, _, size)) in entries.iter().zip(patched_entries.iter()) {
+            let written_size = entry.write_data(&mut writer)?;
+
+            if written_size != *size {
+                // File changed while writing
+                return Err(ArkWriteError::CantReadEntry {
+                    path: entry.path.to_owned()
+                });
+            }
+        }
+
+        writer.flush().map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        self.part_paths.truncate(part_count);
+        self.part_paths.push(part_path);
+        self.part_sizes.push(part_offset);
+
+        // Update offsets
+        let part_size_ranges = create_part_size_ranges(&self.part_sizes);
+        let mut next_id = self.entries
+            .iter()
+            .map(|e| e.id + 1)
+            .max()
+            .unwrap_or_default();
+
+        for (path, offset, size) in patched_entries {
+            let (part, offset) = get_ark_part_and_offset(offset, &part_size_ranges)
+                .map_err(|_| ArkWriteError::CantWriteArk)?;
+
+            match self.entries.iter_mut().find(|e| e.path.eq(path)) {
+                Some(entry) => {
+                    // Replace existing entry
+                    entry.part = part;
+                    entry.offset = offset;
+                    entry.size = size as usize;
+                    entry.inflated_size = 0;
+                },
+                None => {
+                    self.entries.push(ArkOffsetEntry {
+                        id: next_id,
+                        path: path.to_owned(),
+                        offset,
+                        part,
+                        size: size as usize,
+                        inflated_size: 0,
+                    });
+
+                    next_id += 1;
+                }
+            }
+        }
+
+        self.sort_entries_by_name();
+        self.save_header(&self.part_sizes)
+    }
+}
+
+fn get_part_path(hdr_path: &Path, part: usize) -> PathBuf {
+    let file_stem = hdr_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("main");
+
+    hdr_path.with_file_name(format!("{file_stem}_{part}.ark"))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+
+    fn get_test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join("pikaxe_tests")
+            .join(name);
+
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+
+        dir
+    }
+
+    fn create_test_builder(version: i32) -> ArkBuilder {
+        ArkBuilder::new(version)
+            .and_data("songs/song1/song1.mid", vec![1u8; 100])
+            .and_data("config/gen/rb.dtb", vec![2u8; 50])
+            .and_data("songs/song1/gen/song1.milo_xbox", vec![3u8; 200])
+            .and_data("empty.dta", Vec::new())
+            .and_data("root.dta", vec![4u8; 10])
+    }
+
+    #[rstest]
+    #[case(3, "main.hdr")]
+    #[case(5, "main.hdr")]
+    #[case(1, "main.ark")]
+    #[case(2, "main.ark")]
+    fn build_and_read_ark(#[case] version: i32, #[case] file_name: &str) {
+        let out_dir = get_test_dir(&format!("build_and_read_ark_v{version}"));
+        let out_path = out_dir.join("gen").join(file_name);
+
+        let built_ark = create_test_builder(version)
+            .with_part_size(256)
+            .build(&out_path)
+            .unwrap();
+
+        let ark = Ark::from_path(&out_path).unwrap();
+
+        assert_eq!(version, ark.version);
+        assert_eq!(5, ark.entries.len());
+
+        for (built_entry, entry) in built_ark.entries.iter().zip(ark.entries.iter()) {
+            assert_eq!(built_entry.id, entry.id);
+            assert_eq!(built_entry.path, entry.path);
+            assert_eq!(built_entry.part, entry.part);
+            assert_eq!(built_entry.offset, entry.offset);
+            assert_eq!(built_entry.size, entry.size);
+        }
+
+        let root_entry = ark.entries.iter().find(|e| e.path == "root.dta").unwrap();
+        assert_eq!(vec![4u8; 10], ark.get_stream(root_entry.id).unwrap());
+
+        let milo_entry = ark.entries.iter().find(|e| e.path == "songs/song1/gen/song1.milo_xbox").unwrap();
+        assert!(milo_entry.is_gen_file());
+        assert_eq!(vec![3u8; 200], ark.get_stream(milo_entry.id).unwrap());
+    }
+
+    #[rstest]
+    fn build_ark_splits_parts() {
+        let out_dir = get_test_dir("build_ark_splits_parts");
+        let hdr_path = out_dir.join("gen").join("main.hdr");
+
+        let ark = create_test_builder(5)
+            .with_part_size(256)
+            .build(&hdr_path)
+            .unwrap();
+
+        // Sorted sizes are 50, 0, 10, 200, 100 so only first three fit in first part
+        assert_eq!(3, ark.part_paths.len());
+        assert_eq!(hdr_path.with_file_name("main_0.ark"), ark.part_paths[0]);
+        assert_eq!(hdr_path.with_file_name("main_1.ark"), ark.part_paths[1]);
+        assert_eq!(hdr_path.with_file_name("main_2.ark"), ark.part_paths[2]);
+
+        let milo_entry = ark.entries.iter().find(|e| e.path == "songs/song1/gen/song1.milo_xbox").unwrap();
+        assert_eq!(1, milo_entry.part);
+        assert_eq!(0, milo_entry.offset);
+    }
+
+    #[rstest]
+    fn build_ark_from_dir() {
+        let in_dir = get_test_dir("build_ark_from_dir_input");
+        let out_dir = get_test_dir("build_ark_from_dir_output");
+
+        std::fs::create_dir_all(in_dir.join("config")).unwrap();
+        std::fs::write(in_dir.join("config").join("rb.dta"), b"(test 1)").unwrap();
+        std::fs::write(in_dir.join("main.dta"), b"(main)").unwrap();
+
+        let hdr_path = out_dir.join("main.hdr");
+        ArkBuilder::from_dir(&in_dir, 3)
+            .unwrap()
+            .build(&hdr_path)
+            .unwrap();
+
+        let ark = Ark::from_path(&hdr_path).unwrap();
+        let paths = ark.entries.iter().map(|e| e.path.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(vec!["config/rb.dta", "main.dta"], paths);
+        assert_eq!(b"(test 1)".to_vec(), ark.get_stream(ark.entries[0].id).unwrap());
+    }
+
+    #[rstest]
+    #[case(3, ArkEncryption::ClassicEncryption(0x2A58_C391))]
+    #[case(5, ArkEncryption::ClassicEncryption(0x1C5E_7F0B))]
+    #[case(5, ArkEncryption::NewEncryption(-0x3D2B_8E15))]
+    fn build_encrypted_ark(#[case] version: i32, #[case] encryption: ArkEncryption) {
+        let out_dir = get_test_dir(&format!("build_encrypted_ark_v{version}_{}", encryption.get_key().unwrap()));
+        let hdr_path = out_dir.join("gen").join("main.hdr");
+
+        create_test_builder(version)
+            .with_encryption(encryption)
+            .build(&hdr_path)
+            .unwrap();
+
+        // Key should be written unencrypted at start of hdr
+        let hdr_data = std::fs::read(&hdr_path).unwrap();
+        assert_eq!(encryption.get_key().unwrap().to_le_bytes(), hdr_data[..4]);
+
+        let ark = Ark::from_path(&hdr_path).unwrap();
+        assert_eq!(version, ark.version);
+        assert_eq!(encryption, ark.encryption);
+        assert_eq!(5, ark.entries.len());
+
+        let root_entry = ark.entries.iter().find(|e| e.path == "root.dta").unwrap();
+        assert_eq!(vec![4u8; 10], ark.get_stream(root_entry.id).unwrap());
+    }
+
+    #[rstest]
+    #[case(ArkEncryption::ClassicEncryption(-5))]
+    #[case(ArkEncryption::ClassicEncryption(5))] // Conflicts with version
+    #[case(ArkEncryption::NewEncryption(5))]
+    fn build_encrypted_ark_invalid_key(#[case] encryption: ArkEncryption) {
+        let out_dir = get_test_dir(&format!("build_encrypted_ark_invalid_key_{}", encryption.get_key().unwrap()));
+
+        let result = create_test_builder(5)
+            .with_encryption(encryption)
+            .build(out_dir.join("main.hdr"));
+
+        assert!(matches!(result, Err(ArkWriteError::InvalidEncryptionKey { .. })));
+    }
+
+    #[rstest]
+    #[case(3)]
+    #[case(5)]
+    fn patch_ark(#[case] version: i32) {
+        let out_dir = get_test_dir(&format!("patch_ark_v{version}"));
+        let hdr_path = out_dir.join("gen").join("main.hdr");
+
+        let built_ark = create_test_builder(version)
+            .build(&hdr_path)
+            .unwrap();
+
+        let mut ark = Ark::from_path(&hdr_path).unwrap();
+        ark.patch(vec![
+            ArkBuilderEntry {
+                path: String::from("root.dta"),
+                source: ArkEntrySource::Data(vec![5u8; 20]),
+            },
+            ArkBuilderEntry {
+                path: String::from("songs/song2/song2.mid"),
+                source: ArkEntrySource::Data(vec![6u8; 30]),
+            },
+        ]).unwrap();
+
+        let ark = Ark::from_path(&hdr_path).unwrap();
+        assert_eq!(2, ark.part_paths.len());
+        assert_eq!(6, ark.entries.len());
+
+        // Ids should be unchanged for existing entries
+        for built_entry in built_ark.entries.iter() {
+            let entry = ark.entries.iter().find(|e| e.path == built_entry.path).unwrap();
+            assert_eq!(built_entry.id, entry.id);
+        }
+
+        let root_entry = ark.entries.iter().find(|e| e.path == "root.dta").unwrap();
+        assert_eq!(1, root_entry.part);
+        assert_eq!(vec![5u8; 20], ark.get_stream(root_entry.id).unwrap());
+
+        let new_entry = ark.entries.iter().find(|e| e.path == "songs/song2/song2.mid").unwrap();
+        assert_eq!(5, new_entry.id);
+        assert_eq!(vec![6u8; 30], ark.get_stream(new_entry.id).unwrap());
+
+        let mid_entry = ark.entries.iter().find(|e| e.path == "songs/song1/song1.mid").unwrap();
+        assert_eq!(0, mid_entry.part);
+        assert_eq!(vec![1u8; 100], ark.get_stream(mid_entry.id).unwrap());
+    }
+
+    #[rstest]
+    fn patch_ark_ignores_extra_part_files() {
+        let out_dir = get_test_dir("patch_ark_ignores_extra_part_files");
+        let hdr_path = out_dir.join("gen").join("main.hdr");
+
+        create_test_builder(3)
+            .build(&hdr_path)
+            .unwrap();
+
+        // Padding + unrelated ark file shouldn't change part offsets
+        let mut part_data = std::fs::read(hdr_path.with_file_name("main_0.ark")).unwrap();
+        part_data.extend_from_slice(&[0u8; 0x40]);
+        std::fs::write(hdr_path.with_file_name("main_0.ark"), part_data).unwrap();
+        std::fs::write(hdr_path.with_file_name("main_5.ark"), [0u8; 7]).unwrap();
+
+        let mut ark = Ark::from_path(&hdr_path).unwrap();
+        assert_eq!(vec![360], ark.part_sizes);
+
+        ark.patch(vec![
+            ArkBuilderEntry {
+                path: String::from("root.dta"),
+                source: ArkEntrySource::Data(vec![5u8; 20]),
+            },
+        ]).unwrap();
+
+        let ark = Ark::from_path(&hdr_path).unwrap();
+        assert_eq!(vec![360, 20], ark.part_sizes);
+        assert_eq!(hdr_path.with_file_name("main_1.ark"), ark.part_paths[1]);
+
+        let root_entry = ark.entries.iter().find(|e| e.path == "root.dta").unwrap();
+        assert_eq!((1, 0), (root_entry.part, root_entry.offset));
+        assert_eq!(vec![5u8; 20], ark.get_stream(root_entry.id).unwrap());
+
+        let milo_entry = ark.entries.iter().find(|e| e.path == "songs/song1/gen/song1.milo_xbox").unwrap();
+        assert_eq!(vec![3u8; 200], ark.get_stream(milo_entry.id).unwrap());
+    }
+
+    #[rstest]
+    fn patch_ark_with_many_parts() {
+        let out_dir = get_test_dir("patch_ark_with_many_parts");
+        let hdr_path = out_dir.join("gen").join("main.hdr");
+
+        // One entry per part (main_0.ark to main_10.ark)
+        let builder = (0..11u8)
+            .fold(ArkBuilder::new(3).with_part_size(16), |b, i| b.and_data(format!("file_{i:02}.dta"), vec![i; 16]));
+        builder.build(&hdr_path).unwrap();
+
+        let mut ark = Ark::from_path(&hdr_path).unwrap();
+        assert_eq!(11, ark.part_paths.len());
+        assert_eq!(hdr_path.with_file_name("main_2.ark"), ark.part_paths[2]);
+        assert_eq!(hdr_path.with_file_name("main_10.ark"), ark.part_paths[10]);
+
+        ark.patch(vec![
+            ArkBuilderEntry {
+                path: String::from("file_03.dta"),
+                source: ArkEntrySource::Data(vec![0xFFu8; 4]),
+            },
+        ]).unwrap();
+
+        let ark = Ark::from_path(&hdr_path).unwrap();
+        assert_eq!(hdr_path.with_file_name("main_11.ark"), ark.part_paths[11]);
+
+        for entry in ark.entries.iter() {
+            let expected = match entry.path.as_str() {
+                "file_03.dta" => vec![0xFFu8; 4],
+                _ => vec![entry.id as u8; 16],
+            };
+
+            assert_eq!(expected, ark.get_stream(entry.id).unwrap(), "{}", entry.path);
+        }
+    }
+
+    #[rstest]
+    #[case(4)]
+    #[case(10)]
+    fn patch_ark_version_not_supported(#[case] version: i32) {
+        let mut ark = Ark {
+            version,
+            ..Default::default()
+        };
+
+        let result = ark.patch(vec![
+            ArkBuilderEntry {
+                path: String::from("root.dta"),
+                source: ArkEntrySource::Data(vec![5u8; 20]),
+            },
+        ]);
+
+        assert!(matches!(result, Err(ArkWriteError::PatchVersionNotSupported { version: v }) if v == version));
+    }
+
+    #[rstest]
+    fn patch_ark_offset_too_large() {
+        let out_dir = get_test_dir("patch_ark_offset_too_large");
+        let hdr_path = out_dir.join("main.hdr");
+
+        let mut ark = Ark {
+            version: 3,
+            path: hdr_path.to_owned(),
+            part_paths: vec![hdr_path.with_file_name("main_0.ark")],
+            part_sizes: vec![u32::MAX as u64 - 10],
+            ..Default::default()
+        };
+
+        let result = ark.patch(vec![
+            ArkBuilderEntry {
+                path: String::from("root.dta"),
+                source: ArkEntrySource::Data(vec![5u8; 20]),
+            },
+        ]);
+
+        assert!(matches!(result, Err(ArkWriteError::EntryTooLarge { version: 3, .. })));
+        assert!(!hdr_path.with_file_name("main_1.ark").exists());
+        assert_eq!(1, ark.part_sizes.len());
+    }
+
+    #[rstest]
+    fn build_ark_version_not_supported() {
+        let out_dir = get_test_dir("build_ark_version_not_supported");
+
+        let result = create_test_builder(11)
+            .build(out_dir.join("main.hdr"));
+
+        assert!(matches!(result, Err(ArkWriteError::ArkVersionNotSupported { version: 11 })));
+    }
+}
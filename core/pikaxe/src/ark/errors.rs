@@ -10,6 +10,58 @@ pub enum ArkReadError {
     ArkVersionNotSupported {
         version: i32
     },
+    #[error("Frequency ark (magic 0x{major:08X}) version of {minor} not supported")]
+    FreqArkVersionNotSupported {
+        major: i32,
+        minor: i32
+    },
     #[error("HDR file is larger than 20mb")] // Honestly should never happen
     HdrTooBig,
-}
\ No newline at end of file
+    #[error("Entry with id {id} not found")]
+    EntryNotFound {
+        id: u32
+    },
+    #[error("Ark part {part} not found")]
+    ArkPartNotFound {
+        part: u32
+    },
+    #[error("Can't read data for entry \"{path}\"")]
+    CantReadEntry {
+        path: String
+    },
+    #[error("Can't inflate data for entry \"{path}\"")]
+    CantInflateEntry {
+        path: String
+    },
+    #[error("Entry offset 0x{offset:X} is outside of all ark parts")]
+    EntryOffsetOutOfRange {
+        offset: u64
+    },
+}
+
+#[derive(Debug, ThisError)]
+pub enum ArkWriteError {
+    #[error("Can't write ark file")]
+    CantWriteArk,
+    #[error("Can't read data for entry \"{path}\"")]
+    CantReadEntry {
+        path: String
+    },
+    #[error("Writing ark version of {version} not supported")]
+    ArkVersionNotSupported {
+        version: i32
+    },
+    #[error("Patching ark version of {version} not supported (only versions 3 and 5)")]
+    PatchVersionNotSupported {
+        version: i32
+    },
+    #[error("Key of {key} can't be used for ark encryption")]
+    InvalidEncryptionKey {
+        key: i32
+    },
+    #[error("Entry \"{path}\" is too large for ark version {version}")]
+    EntryTooLarge {
+        path: String,
+        version: i32
+    },
+}
@@ -1,8 +1,8 @@
 use crate::ark::*;
 use crate::io::*;
 use std::collections::HashMap;
-use std::io::Read;
-use std::path::Path;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 #[cfg(feature = "python")] use pyo3::prelude::*;
 
 const MAX_HDR_SIZE: u64 = 20 * 0x100000; // 20MB
@@ -12,6 +12,10 @@ impl Ark {
     pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Ark, ArkReadError> {
         let path = path.as_ref();
 
+        if !path.is_file() {
+            return Err(ArkReadError::CantOpenArk);
+        }
+
         // Check if extension is .ark
         let is_ark = path
             .extension()
@@ -20,10 +24,31 @@ impl Ark {
             .eq_ignore_ascii_case("ark");
 
         if is_ark {
-            let (version_major, _version_minor) = peek_ark_version(path)?;
+            let (version_major, version_minor) = peek_ark_version(path)?;
 
             if version_major == FREQ_ARK_VERSION {
-                todo!("Arks from frequency is not supported")
+                let version_minor = version_minor.unwrap_or_default();
+
+                if version_minor != 2 {
+                    return Err(ArkReadError::FreqArkVersionNotSupported {
+                        major: version_major,
+                        minor: version_minor
+                    });
+                }
+
+                let mut ark = Ark {
+                    version: version_major,
+                    encryption: ArkEncryption::None,
+                    path: path.to_owned(),
+                    ..Default::default()
+                };
+
+                let mut stream = FileStream::from_path_as_read_open(path)
+                    .map_err(|_| ArkReadError::ArkNotSupported)?;
+                let mut reader = BinaryStream::from_stream(&mut stream);
+
+                ark.parse_freq_ark(&mut reader)?;
+                return Ok(ark);
             } else if ![1, 2].contains(&version_major) {
                 return Err(ArkReadError::ArkVersionNotSupported {
                     version: version_major
@@ -48,12 +73,12 @@ impl Ark {
 
         let mut hdr_data = read_to_bytes(path);
         let mut version = get_version(&hdr_data[0..4]);
-        let mut crypt_key = None;
+        let mut encryption = ArkEncryption::None;
 
         if !version_is_supported(version) {
             // Decrypt hdr (use version as key)
-            crypt_dtb_style(&mut hdr_data[4..], version, None);
-            crypt_key = Some(version);
+            encryption = ArkEncryption::from_key(version);
+            encryption.crypt(&mut hdr_data[4..]);
 
             version = get_version(&hdr_data[4..8]);
 
@@ -67,10 +92,7 @@ impl Ark {
 
         let mut ark = Ark {
             version,
-            encryption: match crypt_key {
-                Some(key) => ArkEncryption::ClassicEncryption(key),
-                None => ArkEncryption::None,
-            },
+            encryption,
             path: path.to_owned(),
             part_paths: {
                 let dir_path = path.parent().unwrap();
@@ -84,7 +106,8 @@ impl Ark {
                         .is_some_and(|e| e.ends_with(".ARK") || e.ends_with(".ark")))
                     .collect::<Vec<_>>();
 
-                ark_parts.sort_by(|a, b| a.cmp(b));
+                // Sort by part number so main_10.ark comes after main_2.ark
+                ark_parts.sort_by_cached_key(|p| (get_part_index(p), p.to_owned()));
                 ark_parts
             },
             ..Default::default()
@@ -99,6 +122,87 @@ impl Ark {
         Ok(ark)
     }
 
+    /// Parses archive from Frequency. Everything is stored in a single ark file.
+    ///
+    /// Layout:
+    /// - Magic `ARK\0` + minor version (2)
+    /// - File entry offset + count
+    /// - Folder entry offset + count
+    /// - String table offset + count
+    /// - Total header size
+    /// - Block size
+    ///
+    /// File entries (20 bytes): file name offset (relative to string table),
+    /// folder index (i16), offset in block (u16), block index, size, inflated size.
+    ///
+    /// Folder entries (8 bytes): name hash, name offset (relative to string table).
+    fn parse_freq_ark(&mut self, reader: &mut BinaryStream) -> Result<(), ArkReadError> {
+        reader.seek(SeekFrom::Start(8)).map_err(|_| ArkReadError::ArkNotSupported)?; // Skip magic + version
+
+        let mut header = [0u32; 8];
+        for h in header.iter_mut() {
+            *h = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)?;
+        }
+
+        let [
+            file_entry_offset,
+            file_entry_count,
+            folder_entry_offset,
+            folder_entry_count,
+            string_table_offset,
+            _string_count,
+            _total_header_size,
+            block_size
+        ] = header;
+
+        // Read folder names
+        reader.seek(SeekFrom::Start(folder_entry_offset as u64)).map_err(|_| ArkReadError::ArkNotSupported)?;
+        let folder_name_offsets = (0..folder_entry_count)
+            .map(|_| {
+                reader.read_uint32()?; // Name hash
+                reader.read_uint32()
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ArkReadError::ArkNotSupported)?;
+
+        let folder_names = folder_name_offsets
+            .into_iter()
+            .map(|o| read_string_at(reader, string_table_offset as u64 + o as u64))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Read file entries
+        for id in 0..file_entry_count {
+            reader.seek(SeekFrom::Start(file_entry_offset as u64 + (id as u64 * 20))).map_err(|_| ArkReadError::ArkNotSupported)?;
+
+            let file_name_offset = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)?;
+            let folder_idx = reader.read_int16().map_err(|_| ArkReadError::ArkNotSupported)?;
+            let block_offset = reader.read_uint16().map_err(|_| ArkReadError::ArkNotSupported)?;
+            let block = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)?;
+            let size = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)? as usize;
+            let inflated_size = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)? as usize;
+
+            let file_name = read_string_at(reader, string_table_offset as u64 + file_name_offset as u64)?;
+
+            let path = match folder_names.get(folder_idx as usize) {
+                Some(folder_name) if folder_idx >= 0 => create_full_path(folder_name, &file_name),
+                _ => file_name,
+            };
+
+            self.entries.push(ArkOffsetEntry {
+                id,
+                path,
+                offset: (block as u64 * block_size as u64) + block_offset as u64,
+                part: 0,
+                size,
+                inflated_size,
+            });
+        }
+
+        self.sort_entries_by_name();
+
+        Ok(())
+    }
+
     fn parse_amp_ark(&mut self) -> Result<(), ArkReadError> {
         let mut stream = FileStream::from_path_as_read_open(&self.path)
             .map_err(|_| ArkReadError::ArkNotSupported)?;
@@ -181,6 +285,11 @@ impl Ark {
         Ok(())
     }
 
+    /// Parses hdr for ark versions 3-10. Layouts are as follows:
+    ///  v3-v4  : part sizes, string blob + indices, file entries (32-bit offsets)
+    ///  v5     : part file names added, file entries use 64-bit offsets
+    ///  v6-v7  : 16-byte hash after version, part checksums after part file names
+    ///  v9-v10 : file entries store full path instead of string indices, followed by file name hash table
     fn parse_header(&mut self, hdr: &[u8]) -> Result<(), ArkReadError> {
         let mut stream = MemoryStream::from_slice_as_read(hdr);
         let mut reader = BinaryStream::from_stream(&mut stream);
@@ -189,37 +298,78 @@ impl Ark {
         reader.seek(SeekFrom::Current(4))
             .map_err(|_| ArkReadError::ArkNotSupported)?;
 
-        let _part_count = reader.read_uint32()
+        if self.version >= 6 {
+            // Skip hash
+            reader.seek(SeekFrom::Current(16))
+                .map_err(|_| ArkReadError::ArkNotSupported)?;
+        }
+
+        let part_count = reader.read_uint32()
             .map_err(|_| ArkReadError::ArkNotSupported)?;
 
-        // TODO: Verify both counts match
         let part_size_count = reader.read_uint32()
             .map_err(|_| ArkReadError::ArkNotSupported)?;
 
-        let mut part_size_ranges = vec![(0u64, 0u64); part_size_count as usize];
-        let mut part_start = 0u64;
+        if part_count != part_size_count {
+            return Err(ArkReadError::ArkNotSupported);
+        }
+
+        let mut part_sizes = vec![0u64; part_size_count as usize];
 
         // Read part sizes
-        for p in part_size_ranges.iter_mut() {
-            let size = reader.read_uint32()
+        for size in part_sizes.iter_mut() {
+            *size = reader.read_uint32()
                 .map_err(|_| ArkReadError::ArkNotSupported)? as u64;
-
-            *p = (part_start, part_start + size);
-            part_start += size;
         }
 
+        let part_size_ranges = create_part_size_ranges(&part_sizes);
+        self.part_sizes = part_sizes;
+
         if self.version >= 5 {
-            // TODO: Verify count matches here too
             let part_name_count = reader.read_uint32()
                 .map_err(|_| ArkReadError::ArkNotSupported)?;
 
-            // Skip part file names
-            for _ in 0..part_name_count {
-                reader.read_prefixed_string()
+            if part_name_count != part_count {
+                return Err(ArkReadError::ArkNotSupported);
+            }
+
+            // Read part file names
+            let mut part_names = vec![String::new(); part_name_count as usize];
+
+            for name in part_names.iter_mut() {
+                *name = reader.read_prefixed_string()
+                    .map_err(|_| ArkReadError::ArkNotSupported)?;
+            }
+
+            self.resolve_part_paths(&part_names);
+        }
+
+        if self.version >= 6 {
+            let checksum_count = reader.read_uint32()
+                .map_err(|_| ArkReadError::ArkNotSupported)?;
+
+            if checksum_count != part_count {
+                return Err(ArkReadError::ArkNotSupported);
+            }
+
+            // Read part checksums
+            self.part_checksums = vec![0u32; checksum_count as usize];
+
+            for checksum in self.part_checksums.iter_mut() {
+                *checksum = reader.read_uint32()
                     .map_err(|_| ArkReadError::ArkNotSupported)?;
             }
         }
 
+        if self.version >= 9 {
+            // Read file entries + file name hashes
+            self.parse_file_path_entries(&mut reader, part_size_ranges.as_slice())?;
+            self.file_name_hashes = parse_file_name_hashes(&mut reader, self.entries.len())?;
+
+            self.sort_entries_by_name();
+            return Ok(());
+        }
+
         // Read string blob
         let strings = parse_string_blob(&mut reader)?;
 
@@ -249,10 +399,11 @@ impl Ark {
             let size = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)? as usize;
             let inflated_size = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)? as usize;
 
-            let file_name = &strings[&string_indices[file_name_idx]];
-            let dir_path = &strings[&string_indices[dir_path_idx]];
+            // String indices can be a sparse hash table so check that strings exist
+            let file_name = get_indexed_string(strings, string_indices, file_name_idx)?;
+            let dir_path = get_indexed_string(strings, string_indices, dir_path_idx)?;
 
-            let (part, offset) = get_ark_part_and_offset(offset, part_sizes);
+            let (part, offset) = get_ark_part_and_offset(offset, part_sizes)?;
 
             self.entries.push(ArkOffsetEntry {
                 id,
@@ -267,9 +418,290 @@ impl Ark {
         Ok(())
     }
 
-    fn sort_entries_by_name(&mut self) {
+    fn parse_file_path_entries(&mut self, reader: &mut BinaryStream, part_sizes: &[(u64, u64)]) -> Result<(), ArkReadError> {
+        let entry_count = reader.read_uint32()
+            .map_err(|_| ArkReadError::ArkNotSupported)?;
+
+        for id in 0..entry_count {
+            let offset = reader.read_uint64().map_err(|_| ArkReadError::ArkNotSupported)?;
+            let path = reader.read_prefixed_string().map_err(|_| ArkReadError::ArkNotSupported)?;
+            let _flags = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)?;
+            let size = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)? as usize;
+            let inflated_size = reader.read_uint32().map_err(|_| ArkReadError::ArkNotSupported)? as usize;
+
+            let (part, offset) = get_ark_part_and_offset(offset, part_sizes)?;
+
+            self.entries.push(ArkOffsetEntry {
+                id,
+                path,
+                offset,
+                part,
+                size,
+                inflated_size,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn resolve_part_paths(&mut self, part_names: &[String]) {
+        // Names are usually relative to parent of hdr directory (ex: "gen/main_0.ark")
+        let Some(hdr_dir) = self.path.parent() else {
+            return;
+        };
+
+        let part_paths = part_names
+            .iter()
+            .map(|name| {
+                let name_path = Path::new(name);
+
+                [hdr_dir.parent().map(|p| p.join(name_path)), name_path.file_name().map(|f| hdr_dir.join(f))]
+                    .into_iter()
+                    .flatten()
+                    .find(|p| p.is_file())
+            })
+            .collect::<Option<Vec<_>>>();
+
+        // Only use if all parts are found, otherwise keep searched paths
+        if let Some(part_paths) = part_paths {
+            self.part_paths = part_paths;
+        }
+    }
+
+    pub(crate) fn sort_entries_by_name(&mut self) {
         self.entries.sort_by(|a, b| a.path.cmp(&b.path));
     }
+
+    pub(crate) fn write_header(&self, stream: &mut dyn Stream, part_sizes: &[u64]) -> Result<(), ArkWriteError> {
+        let mut writer = BinaryStream::from_stream(stream);
+
+        let entries = self.get_entries_by_id();
+        let string_table = ArkStringTable::from_entries(&entries);
+
+        writer.write_int32(self.version).map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        // Write part sizes (count is written twice)
+        writer.write_uint32(part_sizes.len() as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+        writer.write_uint32(part_sizes.len() as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        for size in part_sizes.iter() {
+            writer.write_uint32(*size as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+        }
+
+        if self.version >= 5 {
+            // Write part file names
+            writer.write_uint32(self.part_paths.len() as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+
+            for part_path in self.part_paths.iter() {
+                let part_name = get_part_name(&self.path, part_path);
+                writer.write_prefixed_string(&part_name).map_err(|_| ArkWriteError::CantWriteArk)?;
+            }
+        }
+
+        // Write string blob + indices
+        string_table.write_blob(&mut writer)?;
+        string_table.write_indices(&mut writer)?;
+
+        // Write file entries
+        writer.write_uint32(entries.len() as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        let part_size_ranges = create_part_size_ranges(part_sizes);
+
+        for entry in entries {
+            let (part_start, _) = part_size_ranges[entry.part as usize];
+
+            let offset = part_start + entry.offset;
+            let (dir_path, file_name) = split_full_path(&entry.path);
+
+            match self.version {
+                3 | 4 => {
+                    if offset > u32::MAX as u64 {
+                        return Err(ArkWriteError::EntryTooLarge {
+                            path: entry.path.to_owned(),
+                            version: self.version
+                        });
+                    }
+
+                    writer.write_uint32(offset as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+                },
+                _ => writer.write_uint64(offset).map_err(|_| ArkWriteError::CantWriteArk)?
+            };
+
+            writer.write_uint32(string_table.get_index(file_name)).map_err(|_| ArkWriteError::CantWriteArk)?;
+            writer.write_uint32(string_table.get_index(dir_path)).map_err(|_| ArkWriteError::CantWriteArk)?;
+            writer.write_uint32(entry.size as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+            writer.write_uint32(entry.inflated_size as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn save_header(&self, part_sizes: &[u64]) -> Result<(), ArkWriteError> {
+        let mut hdr_data = Vec::new();
+
+        // Encrypted hdr is prefixed with key
+        if let Some(key) = self.encryption.get_key() {
+            if !self.encryption.is_key_valid() || version_is_supported(key) {
+                return Err(ArkWriteError::InvalidEncryptionKey {
+                    key
+                });
+            }
+
+            hdr_data.extend_from_slice(&key.to_le_bytes());
+        }
+
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut hdr_data);
+        stream.seek(SeekFrom::End(0))
+            .map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        self.write_header(&mut stream, part_sizes)?;
+
+        if self.encryption.get_key().is_some() {
+            self.encryption.crypt(&mut hdr_data[4..]);
+        }
+
+        let mut hdr_file = std::fs::File::create(&self.path)
+            .map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        hdr_file.write_all(&hdr_data)
+            .map_err(|_| ArkWriteError::CantWriteArk)
+    }
+
+    pub(crate) fn calc_amp_header_size(&self) -> usize {
+        let entries = self.get_entries_by_id();
+        let string_table = ArkStringTable::from_entries(&entries);
+
+        let strings_size = if self.version == 1 {
+            string_table
+                .strings
+                .iter()
+                .map(|s| s.len() + 4)
+                .sum::<usize>()
+        } else {
+            string_table.blob.len() + 4 + (string_table.offsets.len() * 4)
+        };
+
+        8 + (20 * entries.len()) + 4 + strings_size
+    }
+
+    pub(crate) fn write_amp_header(&self, stream: &mut dyn Stream) -> Result<(), ArkWriteError> {
+        let mut writer = BinaryStream::from_stream(stream);
+
+        let entries = self.get_entries_by_id();
+        let string_table = ArkStringTable::from_entries(&entries);
+
+        writer.write_int32(self.version).map_err(|_| ArkWriteError::CantWriteArk)?;
+        writer.write_uint32(entries.len() as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        // Write entries
+        for entry in entries.iter() {
+            let (dir_path, file_name) = split_full_path(&entry.path);
+
+            if entry.offset > u32::MAX as u64 {
+                return Err(ArkWriteError::EntryTooLarge {
+                    path: entry.path.to_owned(),
+                    version: self.version
+                });
+            }
+
+            if self.version != 1 {
+                writer.write_uint32(entry.offset as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+            }
+
+            writer.write_uint32(string_table.get_index(file_name)).map_err(|_| ArkWriteError::CantWriteArk)?;
+            writer.write_uint32(string_table.get_index(dir_path)).map_err(|_| ArkWriteError::CantWriteArk)?;
+
+            if self.version == 1 {
+                writer.write_uint32(entry.offset as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+            }
+
+            writer.write_uint32(entry.size as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+            writer.write_uint32(entry.inflated_size as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+        }
+
+        // Write strings
+        if self.version == 1 {
+            // Write size-prefixed strings (OPM amp demo)
+            writer.write_uint32(string_table.strings.len() as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+
+            for s in string_table.strings.iter() {
+                writer.write_prefixed_string(s).map_err(|_| ArkWriteError::CantWriteArk)?;
+            }
+        } else {
+            string_table.write_blob(&mut writer)?;
+            string_table.write_indices(&mut writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_entries_by_id(&self) -> Vec<&ArkOffsetEntry> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|e| e.id);
+        entries
+    }
+}
+
+#[derive(Default)]
+struct ArkStringTable {
+    strings: Vec<String>,
+    blob: Vec<u8>,
+    offsets: Vec<u32>,
+    lookup: HashMap<String, u32>,
+}
+
+impl ArkStringTable {
+    fn from_entries(entries: &[&ArkOffsetEntry]) -> ArkStringTable {
+        let mut table = ArkStringTable::default();
+
+        for entry in entries.iter() {
+            let (dir_path, file_name) = split_full_path(&entry.path);
+
+            table.add(file_name);
+            table.add(dir_path);
+        }
+
+        table
+    }
+
+    fn add(&mut self, s: &str) -> u32 {
+        if let Some(idx) = self.lookup.get(s) {
+            return *idx;
+        }
+
+        let idx = self.strings.len() as u32;
+
+        // Append to blob as null-terminated string
+        self.offsets.push(self.blob.len() as u32);
+        self.blob.extend_from_slice(s.as_bytes());
+        self.blob.push(0);
+
+        self.strings.push(s.to_owned());
+        self.lookup.insert(s.to_owned(), idx);
+
+        idx
+    }
+
+    fn get_index(&self, s: &str) -> u32 {
+        self.lookup[s]
+    }
+
+    fn write_blob(&self, writer: &mut BinaryStream) -> Result<(), ArkWriteError> {
+        writer.write_uint32(self.blob.len() as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+        writer.write_bytes(&self.blob).map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        Ok(())
+    }
+
+    fn write_indices(&self, writer: &mut BinaryStream) -> Result<(), ArkWriteError> {
+        writer.write_uint32(self.offsets.len() as u32).map_err(|_| ArkWriteError::CantWriteArk)?;
+
+        for offset in self.offsets.iter() {
+            writer.write_uint32(*offset).map_err(|_| ArkWriteError::CantWriteArk)?;
+        }
+
+        Ok(())
+    }
 }
 
 fn get_version(data: &[u8]) -> i32 {
@@ -281,7 +713,7 @@ fn get_version(data: &[u8]) -> i32 {
 
 fn version_is_supported(version: i32) -> bool {
     match version {
-        3 | 5 => true,
+        3 | 4 | 5 | 6 | 7 | 9 | 10 => true,
         _ => false
     }
 }
@@ -306,6 +738,32 @@ fn parse_string_blob(reader: &mut BinaryStream) -> Result<HashMap<u32, String>,
     Ok(strings)
 }
 
+fn parse_file_name_hashes(reader: &mut BinaryStream, entry_count: usize) -> Result<Vec<u32>, ArkReadError> {
+    let hash_count = reader.read_uint32()
+        .map_err(|_| ArkReadError::ArkNotSupported)?;
+
+    // Hash table should have at least one slot per entry
+    if (hash_count as usize) < entry_count {
+        return Err(ArkReadError::ArkNotSupported);
+    }
+
+    let mut hashes = vec![0; hash_count as usize];
+
+    for hash in hashes.iter_mut() {
+        *hash = reader.read_uint32()
+            .map_err(|_| ArkReadError::ArkNotSupported)?;
+    }
+
+    Ok(hashes)
+}
+
+fn get_indexed_string<'a>(strings: &'a HashMap<u32, String>, string_indices: &[u32], idx: usize) -> Result<&'a String, ArkReadError> {
+    string_indices
+        .get(idx)
+        .and_then(|si| strings.get(si))
+        .ok_or(ArkReadError::ArkNotSupported)
+}
+
 fn parse_string_indices(reader: &mut BinaryStream) -> Result<Vec<u32>, ArkReadError> {
     let indices_count = reader.read_uint32()
         .map_err(|_| ArkReadError::ArkNotSupported)?;
@@ -328,13 +786,69 @@ fn create_full_path(dir_path: &String, file_name: &String) -> String {
     format!("{}/{}", dir_path, file_name)
 }
 
-fn get_ark_part_and_offset(offset: u64, part_size_ranges: &[(u64, u64)]) -> (u32, u64) {
+fn split_full_path(path: &str) -> (&str, &str) {
+    match path.rsplit_once('/') {
+        Some((dir_path, file_name)) => (dir_path, file_name),
+        None => ("", path)
+    }
+}
+
+fn get_part_name(hdr_path: &Path, part_path: &Path) -> String {
+    // Part names are relative to parent of hdr directory (ex: "gen/main_0.ark")
+    let part_name = hdr_path
+        .parent()
+        .and_then(|p| p.parent())
+        .and_then(|p| part_path.strip_prefix(p).ok())
+        .unwrap_or(part_path);
+
+    part_name
+        .to_str()
+        .unwrap_or_default()
+        .replace('\\', "/")
+}
+
+fn get_part_index(part_path: &Path) -> u32 {
+    // Number after last underscore of file name (ex: "main_10.ark" -> 10)
+    part_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(|s| s.rsplit_once('_'))
+        .and_then(|(_, n)| n.parse().ok())
+        .unwrap_or(u32::MAX)
+}
+
+pub(crate) fn create_part_size_ranges(part_sizes: &[u64]) -> Vec<(u64, u64)> {
+    let mut part_start = 0u64;
+
+    part_sizes
+        .iter()
+        .map(|size| {
+            let range = (part_start, part_start + size);
+            part_start += size;
+            range
+        })
+        .collect()
+}
+
+pub(crate) fn get_ark_part_and_offset(offset: u64, part_size_ranges: &[(u64, u64)]) -> Result<(u32, u64), ArkReadError> {
     part_size_ranges
         .iter()
         .enumerate()
         .find(|(_, (start, end))| &offset >= start && &offset < end)
-        .map(|(i, (start, _))| (i as u32, &offset - start))
-        .unwrap()
+        .or_else(|| part_size_ranges // Empty files can be placed at very end of part
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, (start, end))| &offset >= start && offset == *end))
+        .map(|(i, (start, _))| (i as u32, offset - start))
+        .ok_or(ArkReadError::EntryOffsetOutOfRange {
+            offset
+        })
+}
+
+fn read_string_at(reader: &mut BinaryStream, offset: u64) -> Result<String, ArkReadError> {
+    reader.seek(SeekFrom::Start(offset)).map_err(|_| ArkReadError::ArkNotSupported)?;
+    reader.read_null_terminated_string().map_err(|_| ArkReadError::ArkNotSupported)
 }
 
 fn peek_ark_version(path: &Path) -> Result<(i32, Option<i32>), ArkReadError> {
@@ -364,4 +878,383 @@ fn peek_ark_version(path: &Path) -> Result<(i32, Option<i32>), ArkReadError> {
     };
 
     Ok((v1, v2))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+
+    // (dir path, file name, global offset, size)
+    const TEST_ENTRIES: [(&str, &str, u64, u32); 3] = [
+        ("config", "rb.dta", 0, 16),
+        ("songs/gen", "song.milo_xbox", 16, 32),
+        ("", "main.dta", 48, 64),
+    ];
+
+    const TEST_PART_SIZES: [u32; 2] = [48, 64];
+    const TEST_CHECKSUMS: [u32; 2] = [0x1234_5678, 0x9ABC_DEF0];
+
+    fn create_synthetic_hdr(version: i32, part_count: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        let mut writer = BinaryStream::from_stream(&mut stream);
+
+        writer.write_int32(version).unwrap();
+
+        if version >= 6 {
+            writer.write_bytes(&[0xAB; 16]).unwrap();
+        }
+
+        writer.write_uint32(part_count).unwrap();
+        writer.write_uint32(TEST_PART_SIZES.len() as u32).unwrap();
+        for size in TEST_PART_SIZES {
+            writer.write_uint32(size).unwrap();
+        }
+
+        if version >= 5 {
+            writer.write_uint32(2).unwrap();
+            writer.write_prefixed_string("gen/main_0.ark").unwrap();
+            writer.write_prefixed_string("gen/main_1.ark").unwrap();
+        }
+
+        if version >= 6 {
+            writer.write_uint32(TEST_CHECKSUMS.len() as u32).unwrap();
+            for checksum in TEST_CHECKSUMS {
+                writer.write_uint32(checksum).unwrap();
+            }
+        }
+
+        if version >= 9 {
+            writer.write_uint32(TEST_ENTRIES.len() as u32).unwrap();
+            for (dir_path, file_name, offset, size) in TEST_ENTRIES {
+                writer.write_uint64(offset).unwrap();
+                writer.write_prefixed_string(&create_full_path(&dir_path.to_owned(), &file_name.to_owned())).unwrap();
+                writer.write_uint32(0).unwrap(); // Flags
+                writer.write_uint32(size).unwrap();
+                writer.write_uint32(0).unwrap();
+            }
+
+            // File name hashes (sparse)
+            writer.write_uint32(5).unwrap();
+            for hash in [0x0, 0x2B1C_55A0, 0x0, 0x7E3F_0011, 0x1D2C_3B4A] {
+                writer.write_uint32(hash).unwrap();
+            }
+
+            return data;
+        }
+
+        // String blob
+        let blob = b"\0config\0rb.dta\0songs/gen\0song.milo_xbox\0main.dta\0";
+        writer.write_uint32(blob.len() as u32).unwrap();
+        writer.write_bytes(blob).unwrap();
+
+        // String indices (sparse hash table with empty slots)
+        let indices = [0u32, 1, 0, 8, 15, 0, 25, 40];
+        writer.write_uint32(indices.len() as u32).unwrap();
+        for idx in indices {
+            writer.write_uint32(idx).unwrap();
+        }
+
+        // (file name index, dir path index)
+        let entry_indices = [(3, 1), (6, 4), (7, 0)];
+
+        writer.write_uint32(TEST_ENTRIES.len() as u32).unwrap();
+        for ((_, _, offset, size), (file_idx, dir_idx)) in TEST_ENTRIES.iter().zip(entry_indices) {
+            match version {
+                3 | 4 => writer.write_uint32(*offset as u32).unwrap(),
+                _ => writer.write_uint64(*offset).unwrap(),
+            };
+
+            writer.write_uint32(file_idx).unwrap();
+            writer.write_uint32(dir_idx).unwrap();
+            writer.write_uint32(*size).unwrap();
+            writer.write_uint32(0).unwrap();
+        }
+
+        data
+    }
+
+    #[rstest]
+    #[case("gen/main_0.ark", 0)]
+    #[case("gen/main_2.ark", 2)]
+    #[case("gen/main_10.ark", 10)]
+    #[case("gen/MAIN_3.ARK", 3)]
+    #[case("gen/patch.ark", u32::MAX)]
+    fn get_part_index_test(#[case] path: &str, #[case] expected: u32) {
+        assert_eq!(expected, get_part_index(Path::new(path)));
+    }
+
+    #[rstest]
+    #[case(3)]
+    #[case(4)]
+    #[case(5)]
+    #[case(6)]
+    #[case(7)]
+    #[case(9)]
+    #[case(10)]
+    fn parse_synthetic_header(#[case] version: i32) {
+        let hdr = create_synthetic_hdr(version, 2);
+
+        let mut ark = Ark {
+            version,
+            ..Default::default()
+        };
+
+        ark.parse_header(&hdr).unwrap();
+
+        // Entries are sorted by name
+        let entries = ark.entries
+            .iter()
+            .map(|e| (e.id, e.path.as_str(), e.part, e.offset, e.size))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![
+            (0, "config/rb.dta", 0, 0, 16),
+            (2, "main.dta", 1, 0, 64),
+            (1, "songs/gen/song.milo_xbox", 0, 16, 32),
+        ], entries);
+
+        if version >= 6 {
+            assert_eq!(TEST_CHECKSUMS.to_vec(), ark.part_checksums);
+        } else {
+            assert!(ark.part_checksums.is_empty());
+        }
+    }
+
+    #[rstest]
+    #[case(3)]
+    #[case(5)]
+    #[case(6)]
+    #[case(9)]
+    fn parse_synthetic_header_part_count_mismatch(#[case] version: i32) {
+        let hdr = create_synthetic_hdr(version, 3);
+
+        let mut ark = Ark {
+            version,
+            ..Default::default()
+        };
+
+        assert!(matches!(ark.parse_header(&hdr), Err(ArkReadError::ArkNotSupported)));
+    }
+
+    // Hand-written v3 hdr (single part, string blob + indices, u32 offsets)
+    fn get_v3_hdr_bytes(second_offset: u8) -> Vec<u8> {
+        [
+            &[0x03, 0x00, 0x00, 0x00][..], // Version
+            &[0x01, 0x00, 0x00, 0x00], // Part count
+            &[0x01, 0x00, 0x00, 0x00], // Part size count
+            &[0x20, 0x00, 0x00, 0x00], // Part sizes
+            &[0x18, 0x00, 0x00, 0x00], // String blob size
+            b"\0config\0rb.dta\0main.dta\0",
+            &[0x04, 0x00, 0x00, 0x00], // String index count
+            &[0x01, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x0F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            &[0x02, 0x00, 0x00, 0x00], // Entry count
+            // Offset, file name index, dir path index, size, inflated size
+            &[0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            &[second_offset, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        ].concat()
+    }
+
+    // Hand-written v10 hdr (two parts, full entry paths, u64 offsets + hash table)
+    fn get_v10_hdr_bytes() -> Vec<u8> {
+        [
+            &[0x0A, 0x00, 0x00, 0x00][..], // Version
+            &[0xAB; 16], // Hash
+            &[0x02, 0x00, 0x00, 0x00], // Part count
+            &[0x02, 0x00, 0x00, 0x00], // Part size count
+            &[0x10, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00], // Part sizes
+            &[0x02, 0x00, 0x00, 0x00], // Part name count
+            &[0x0E, 0x00, 0x00, 0x00], b"gen/main_0.ark",
+            &[0x0E, 0x00, 0x00, 0x00], b"gen/main_1.ark",
+            &[0x02, 0x00, 0x00, 0x00], // Part checksum count
+            &[0x78, 0x56, 0x34, 0x12, 0xF0, 0xDE, 0xBC, 0x9A],
+            &[0x02, 0x00, 0x00, 0x00], // Entry count
+            // Offset, path, flags, size, inflated size
+            &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            &[0x0D, 0x00, 0x00, 0x00], b"config/rb.dta",
+            &[0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            &[0x18, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+            &[0x08, 0x00, 0x00, 0x00], b"main.dta",
+            &[0x00, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00],
+            &[0x03, 0x00, 0x00, 0x00], // Hash count
+            &[0x00, 0x00, 0x00, 0x00, 0xEF, 0xBE, 0xAD, 0xDE, 0x78, 0x56, 0x34, 0x12],
+        ].concat()
+    }
+
+    #[rstest]
+    fn parse_v3_header_bytes() {
+        let mut ark = Ark {
+            version: 3,
+            ..Default::default()
+        };
+
+        ark.parse_header(&get_v3_hdr_bytes(0x10)).unwrap();
+
+        let entries = ark.entries
+            .iter()
+            .map(|e| (e.id, e.path.as_str(), e.part, e.offset, e.size))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![
+            (0, "config/rb.dta", 0, 0, 16),
+            (1, "main.dta", 0, 16, 16),
+        ], entries);
+        assert_eq!(vec![0x20], ark.part_sizes);
+    }
+
+    #[rstest]
+    fn parse_v3_header_bytes_offset_out_of_range() {
+        let mut ark = Ark {
+            version: 3,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            ark.parse_header(&get_v3_hdr_bytes(0x40)),
+            Err(ArkReadError::EntryOffsetOutOfRange { offset: 0x40 })
+        ));
+    }
+
+    #[rstest]
+    fn parse_v10_header_bytes() {
+        let mut ark = Ark {
+            version: 10,
+            ..Default::default()
+        };
+
+        ark.parse_header(&get_v10_hdr_bytes()).unwrap();
+
+        let entries = ark.entries
+            .iter()
+            .map(|e| (e.id, e.path.as_str(), e.part, e.offset, e.size, e.inflated_size))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![
+            (0, "config/rb.dta", 0, 0, 16, 0),
+            (1, "main.dta", 1, 8, 8, 32),
+        ], entries);
+        assert_eq!(vec![0x10, 0x20], ark.part_sizes);
+        assert_eq!(vec![0x1234_5678, 0x9ABC_DEF0], ark.part_checksums);
+        assert_eq!(vec![0x0, 0xDEAD_BEEF, 0x1234_5678], ark.file_name_hashes);
+    }
+
+    fn create_synthetic_freq_ark(version_minor: u32) -> Vec<u8> {
+        const BLOCK_SIZE: u32 = 0x800;
+
+        let mut data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        let mut writer = BinaryStream::from_stream(&mut stream);
+
+        // (folder idx, file name offset, data)
+        let files: [(i16, u32, &[u8]); 3] = [
+            (1, 0, b"(rb)"),
+            (-1, 7, b"main"),
+            (0, 16, b"(song1 (name \"Song\"))"),
+        ];
+        let folders = [(0x1111_1111u32, 25u32), (0x2222_2222, 31)];
+        let strings = b"rb.dta\0main.dta\0song.dta\0songs\0config\0";
+
+        let file_entry_offset = 0x28;
+        let folder_entry_offset = file_entry_offset + (files.len() as u32 * 20);
+        let string_table_offset = folder_entry_offset + (folders.len() as u32 * 8);
+        let header_size = string_table_offset + strings.len() as u32;
+
+        writer.write_int32(FREQ_ARK_VERSION).unwrap();
+        writer.write_uint32(version_minor).unwrap();
+        writer.write_uint32(file_entry_offset).unwrap();
+        writer.write_uint32(files.len() as u32).unwrap();
+        writer.write_uint32(folder_entry_offset).unwrap();
+        writer.write_uint32(folders.len() as u32).unwrap();
+        writer.write_uint32(string_table_offset).unwrap();
+        writer.write_uint32(5).unwrap();
+        writer.write_uint32(header_size).unwrap();
+        writer.write_uint32(BLOCK_SIZE).unwrap();
+
+        // File data starts at first block (each file offset by 0x10 within its block)
+        for (i, (folder_idx, name_offset, file_data)) in files.iter().enumerate() {
+            writer.write_uint32(*name_offset).unwrap();
+            writer.write_int16(*folder_idx).unwrap();
+            writer.write_uint16(0x10).unwrap();
+            writer.write_uint32(i as u32 + 1).unwrap();
+            writer.write_uint32(file_data.len() as u32).unwrap();
+            writer.write_uint32(0).unwrap();
+        }
+
+        for (hash, name_offset) in folders {
+            writer.write_uint32(hash).unwrap();
+            writer.write_uint32(name_offset).unwrap();
+        }
+
+        writer.write_bytes(strings).unwrap();
+
+        for (i, (_, _, file_data)) in files.iter().enumerate() {
+            let offset = ((i + 1) * BLOCK_SIZE as usize) + 0x10;
+
+            data.resize(offset, 0);
+            data.extend_from_slice(file_data);
+        }
+
+        data
+    }
+
+    fn write_test_file(name: &str, data: &[u8]) -> PathBuf {
+        let dir_path = std::env::temp_dir().join("pikaxe_tests").join(name);
+        std::fs::create_dir_all(&dir_path).unwrap();
+
+        let file_path = dir_path.join("main.ark");
+        std::fs::write(&file_path, data).unwrap();
+
+        file_path
+    }
+
+    #[rstest]
+    fn read_freq_ark() {
+        let ark_path = write_test_file("read_freq_ark", &create_synthetic_freq_ark(2));
+        let ark = Ark::from_path(&ark_path).unwrap();
+
+        assert_eq!(FREQ_ARK_VERSION, ark.version);
+        assert_eq!(ArkEncryption::None, ark.encryption);
+
+        let entries = ark.entries
+            .iter()
+            .map(|e| (e.id, e.path.as_str(), e.part, e.offset, e.size))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![
+            (0, "config/rb.dta", 0, 0x810, 4),
+            (1, "main.dta", 0, 0x1010, 4),
+            (2, "songs/song.dta", 0, 0x1810, 21),
+        ], entries);
+
+        assert_eq!(b"(rb)".to_vec(), ark.get_stream(0).unwrap());
+        assert_eq!(b"main".to_vec(), ark.get_stream(1).unwrap());
+        assert_eq!(b"(song1 (name \"Song\"))".to_vec(), ark.get_stream(2).unwrap());
+    }
+
+    #[rstest]
+    fn read_freq_ark_version_not_supported() {
+        let ark_path = write_test_file("read_freq_ark_version_not_supported", &create_synthetic_freq_ark(3));
+
+        assert!(matches!(
+            Ark::from_path(&ark_path),
+            Err(ArkReadError::FreqArkVersionNotSupported { major: FREQ_ARK_VERSION, minor: 3 })
+        ));
+    }
+
+    #[rstest]
+    #[case(1, false)]
+    #[case(2, false)]
+    #[case(3, true)]
+    #[case(4, true)]
+    #[case(5, true)]
+    #[case(6, true)]
+    #[case(7, true)]
+    #[case(8, false)]
+    #[case(9, true)]
+    #[case(10, true)]
+    #[case(11, false)]
+    fn version_is_supported_test(#[case] version: i32, #[case] expected: bool) {
+        assert_eq!(expected, version_is_supported(version));
+    }
+}
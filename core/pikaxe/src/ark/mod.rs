@@ -1,7 +1,9 @@
 mod ark;
+mod builder;
 mod errors;
 mod io;
 
 pub use self::ark::*;
+pub use self::builder::*;
 pub use self::errors::*;
 pub use self::io::*;
\ No newline at end of file
@@ -0,0 +1,559 @@
+use crate::{ErrorContext, GamePreset, Platform, SystemInfo};
+use crate::scene::{Object, ObjectDir, PackedObject};
+use crate::texture::{Bitmap, convert_cmp_to_dxt1, convert_dxt1_to_cmp, DXGI_Encoding, Image, swap_image_bytes, TPLEncoding};
+use std::collections::HashMap;
+use thiserror::Error as ThisError;
+
+/// Types with platform specific data that can't be copied between platforms as is
+const PLATFORM_SPECIFIC_TYPES: [&str; 2] = ["CubeTex", "Tex"];
+
+#[derive(Debug, ThisError)]
+pub enum ConvertError {
+    #[error("Can't convert bitmap with encoding {encoding} from {from:?} to {to:?}")]
+    UnsupportedBitmap {
+        encoding: u32,
+        from: Platform,
+        to: Platform
+    },
+    #[error("Directory entry of \"{name}\" can't be converted to different version or endian (written as is)")]
+    DirectoryEntry {
+        name: String
+    },
+    #[error("Can't write {object_type} version {expected} used by {preset} (writes version {version})")]
+    UnsupportedObjectVersion {
+        object_type: String,
+        version: u32,
+        expected: u32,
+        preset: &'static str
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct ConvertSettings {
+    /// Game preset of target system. Objects are checked against versions of preset when known, not remapped:
+    /// writers use fixed versions, so entries are only copied if source already has preset version and reported otherwise
+    pub preset: Option<&'static GamePreset>,
+    /// Remove entries that can't be converted instead of keeping them as is (still for source system)
+    pub remove_failed: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ConvertReport {
+    /// Entries unpacked and packed again for target system
+    pub converted: Vec<String>,
+    /// Entries that couldn't be unpacked but were copied as is (same version + endian)
+    pub copied: Vec<String>,
+    /// Entries that couldn't be converted
+    pub failed: Vec<String>,
+    /// Errors of entries that couldn't be converted (+ directory entries written as is)
+    pub errors: Vec<crate::Error>,
+}
+
+enum ConvertedEntry {
+    Converted(PackedObject),
+    Copied(PackedObject),
+}
+
+/// Converts entries of directory (and inline sub directories) from one system to another.
+/// Entries are packed for target system. Entries that can't be converted are reported and kept as is
+/// (or removed if `remove_failed` is set), so directory shouldn't be saved for target system if any failed.
+pub fn convert_object_dir(obj_dir: &mut ObjectDir, from: &SystemInfo, to: &SystemInfo) -> ConvertReport {
+    convert_object_dir_with_settings(obj_dir, from, to, &ConvertSettings::default())
+}
+
+pub fn convert_object_dir_with_settings(obj_dir: &mut ObjectDir, from: &SystemInfo, to: &SystemInfo, settings: &ConvertSettings) -> ConvertReport {
+    let mut report = ConvertReport::default();
+    convert_directory(obj_dir, from, to, settings, &mut report);
+
+    report
+}
+
+fn convert_directory(obj_dir: &mut ObjectDir, from: &SystemInfo, to: &SystemInfo, settings: &ConvertSettings, report: &mut ConvertReport) {
+    let ObjectDir::ObjectDir(dir) = obj_dir;
+    let same_format = from.endian == to.endian && from.version == to.version;
+
+    if !same_format && dir.dir_entry.is_some() {
+        report.errors.push(ConvertError::DirectoryEntry { name: dir.name.to_owned() }.into());
+    }
+
+    // Original data is for source system
+    let mut original_data = std::mem::take(&mut dir.original_data);
+    let original_info = dir.original_info.take();
+
+    let mut failed = Vec::new();
+
+    for entry in dir.entries.iter_mut() {
+        let name = entry.get_name().to_owned();
+
+        match convert_entry(entry, &original_data, from, to, settings) {
+            Ok(ConvertedEntry::Converted(packed)) => {
+                report.converted.push(name);
+                *entry = Object::Packed(packed);
+            },
+            Ok(ConvertedEntry::Copied(packed)) => {
+                report.copied.push(name);
+                *entry = Object::Packed(packed);
+            },
+            Err(error) => {
+                // Keep original data for entry left as is
+                if let Some(data) = original_data.remove(&name).filter(|_| !settings.remove_failed) {
+                    dir.original_data.insert(name.to_owned(), data);
+                    dir.original_info = original_info;
+                }
+
+                failed.push(name);
+                report.errors.push(error);
+            },
+        }
+    }
+
+    if settings.remove_failed {
+        dir.entries.retain(|e| !failed.iter().any(|f| f.eq(e.get_name())));
+    }
+
+    report.failed.extend(failed);
+
+    for sub_dir in dir.sub_dirs.iter_mut() {
+        convert_directory(sub_dir, from, to, settings, report);
+    }
+}
+
+/// Checks if data of object can be used as is for other system (same version + endian, and same platform for platform specific types)
+pub(crate) fn can_copy_data(object_type: &str, from: &SystemInfo, to: &SystemInfo) -> bool {
+    from.endian == to.endian
+        && from.version == to.version
+        && (from.platform == to.platform || !PLATFORM_SPECIFIC_TYPES.contains(&object_type))
+}
+
+fn convert_entry(entry: &Object, original_data: &HashMap<String, Vec<u8>>, from: &SystemInfo, to: &SystemInfo, settings: &ConvertSettings) -> Result<ConvertedEntry, crate::Error> {
+    let can_copy = can_copy_data(entry.get_type(), from, to);
+    let context = ErrorContext::from_entry(entry.get_name(), entry.get_type());
+
+    // Converted from packed data for source system so entry isn't changed if it can't be converted
+    let source = match entry {
+        Object::Packed(packed) => packed.to_owned(),
+        obj => match (obj.try_pack(from), original_data.get(obj.get_name())) {
+            (Ok(packed), _) => packed,
+            (Err(_), Some(data)) => PackedObject {
+                name: obj.get_name().to_owned(),
+                object_type: obj.get_type().to_owned(),
+                data: data.to_owned()
+            },
+            (Err(error), None) => return Err(error),
+        }
+    };
+
+    let source = Object::Packed(source);
+    let unpacked = source.try_unpack(from);
+
+    let Object::Packed(source) = source else {
+        unreachable!()
+    };
+
+    let mut obj = match unpacked {
+        Ok(Some(obj)) => obj,
+        Ok(None) | Err(_) if can_copy => return Ok(ConvertedEntry::Copied(source)),
+        Ok(None) => {
+            return Err(crate::Error::UnsupportedObjectType { object_type: source.object_type }.with_context(context));
+        },
+        Err(error) => return Err(error),
+    };
+    match &mut obj {
+        Object::Tex(tex) => {
+            if let Some(bitmap) = tex.bitmap.as_mut() {
+                *bitmap = convert_bitmap(bitmap, from, to)
+                    .map_err(|e| e.with_context(context.to_owned()))?;
+
+                tex.bpp = bitmap.bpp as u32;
+            }
+        },
+        Object::CubeTex(cube) => {
+            let faces = [&mut cube.right, &mut cube.left, &mut cube.top, &mut cube.bottom, &mut cube.front, &mut cube.back];
+
+            for bitmap in faces.into_iter().flatten() {
+                let converted = convert_bitmap(bitmap, from, to)
+                    .map_err(|e| e.with_context(context.to_owned()))?;
+
+                // Encoding is also stored in cube properties
+                if converted.encoding != bitmap.encoding {
+                    let error: crate::Error = ConvertError::UnsupportedBitmap {
+                        encoding: bitmap.encoding,
+                        from: from.platform,
+                        to: to.platform
+                    }.into();
+
+                    return Err(error.with_context(context));
+                }
+
+                *bitmap = converted;
+            }
+        },
+        _ => {}
+    }
+
+    let packed = match obj.try_pack(to) {
+        Ok(packed) => packed,
+        Err(_) if can_copy => return Ok(ConvertedEntry::Copied(source)),
+        Err(error) => return Err(error),
+    };
+
+    // Writers don't support other versions yet, so only check against version of target game if known
+    if let Some((preset, expected)) = settings.preset.and_then(|p| p.get_object_version(&packed.object_type).map(|v| (p, v))) {
+        let version = packed.get_version(to.endian).unwrap_or_default();
+
+        if version != expected {
+            if can_copy && source.get_version(from.endian) == Some(expected) {
+                return Ok(ConvertedEntry::Copied(source));
+            }
+
+            let error: crate::Error = ConvertError::UnsupportedObjectVersion {
+                object_type: packed.object_type,
+                version,
+                expected,
+                preset: preset.id
+            }.into();
+
+            return Err(error.with_context(context));
+        }
+    }
+
+    // Make sure written version can be read for target system
+    let packed = Object::Packed(packed);
+    packed.try_unpack(to)?;
+
+    let Object::Packed(packed) = packed else {
+        unreachable!()
+    };
+
+    Ok(ConvertedEntry::Converted(packed))
+}
+
+/// Converts bitmap to encoding used by target platform. DXT1 + CMP encodings are converted
+/// losslessly, other encodings are decoded and encoded again (without mip maps).
+pub fn convert_bitmap(bitmap: &Bitmap, from: &SystemInfo, to: &SystemInfo) -> Result<Bitmap, crate::Error> {
+    const DXT1: u32 = DXGI_Encoding::DXGI_FORMAT_BC1_UNORM as u32;
+    const CMP: u32 = TPLEncoding::CMP as u32;
+
+    let converted = match (from.platform, to.platform, bitmap.encoding) {
+        (from_platform, to_platform, _) if from_platform == to_platform => Some(bitmap.clone()),
+        (Platform::X360, Platform::PS3, 8 | 24 | 32) | (Platform::PS3, Platform::X360, 8 | 24 | 32) => {
+            // 360 blocks only differ by using big endian 16-bit values
+            let mut converted = bitmap.clone();
+            swap_image_bytes(&mut converted.raw_data);
+
+            Some(converted)
+        },
+        (Platform::X360 | Platform::PS3, Platform::Wii, DXT1) => {
+            let is_360 = from.platform == Platform::X360;
+            convert_bitmap_levels(bitmap, CMP, |dx_img, tpl_img, width| convert_dxt1_to_cmp(dx_img, tpl_img, width, is_360))
+        },
+        (Platform::Wii, Platform::X360 | Platform::PS3, CMP | 584) => {
+            let is_360 = to.platform == Platform::X360;
+            convert_bitmap_levels(bitmap, DXT1, |tpl_img, dx_img, width| convert_cmp_to_dxt1(tpl_img, dx_img, width, is_360))
+        },
+        _ => None
+    };
+
+    if let Some(converted) = converted {
+        return Ok(converted);
+    }
+
+    let unsupported = || ConvertError::UnsupportedBitmap {
+        encoding: bitmap.encoding,
+        from: from.platform,
+        to: to.platform
+    };
+
+    // Only decode encodings that are implemented (some decoders panic instead of returning error)
+    if !can_decode_bitmap(bitmap, from) || !matches!(to.platform, Platform::PS3 | Platform::X360 | Platform::Wii) {
+        return Err(unsupported().into());
+    }
+
+    // Encode first level again
+    let rgba = bitmap.unpack_rgba(from)
+        .map_err(|_| unsupported())?;
+
+    Ok(Bitmap::from_image(Image::FromRGBA {
+        rgba: &rgba,
+        width: bitmap.width,
+        height: bitmap.height,
+        mips: 0
+    }, to))
+}
+
+/// Checks if encoding of bitmap can be decoded for platform and data is large enough for every level
+fn can_decode_bitmap(bitmap: &Bitmap, info: &SystemInfo) -> bool {
+    bitmap.width > 0
+        && bitmap.height > 0
+        && bitmap.calc_data_size(info).is_some_and(|size| bitmap.raw_data.len() >= size)
+}
+
+/// Converts each 4bpp level of bitmap (levels that aren't multiple of 8x8 tiles are dropped)
+fn convert_bitmap_levels(bitmap: &Bitmap, encoding: u32, convert: impl Fn(&[u8], &mut [u8], u32)) -> Option<Bitmap> {
+    let mut raw_data = Vec::with_capacity(bitmap.raw_data.len());
+    let mut mip_maps = None;
+
+    let (mut width, mut height) = (bitmap.width as usize, bitmap.height as usize);
+    let mut offset = 0;
+
+    for level in 0..=bitmap.mip_maps {
+        let size = (width * height) / 2;
+
+        if width % 8 != 0 || height % 8 != 0 || (offset + size) > bitmap.raw_data.len() {
+            break;
+        }
+
+        let start = raw_data.len();
+        raw_data.resize(start + size, 0);
+        convert(&bitmap.raw_data[offset..(offset + size)], &mut raw_data[start..], width as u32);
+
+        mip_maps = Some(level);
+        offset += size;
+        width >>= 1;
+        height >>= 1;
+    }
+
+    Some(Bitmap {
+        bpp: 4,
+        encoding,
+        mip_maps: mip_maps?,
+
+        width: bitmap.width,
+        height: bitmap.height,
+        bpl: bitmap.width / 2,
+
+        raw_data
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+    use crate::io::IOEndian;
+    use crate::scene::{CamObject, MatObject, ObjectDirBase, Tex};
+
+    fn get_info(platform: Platform) -> SystemInfo {
+        SystemInfo {
+            version: 25,
+            platform,
+            endian: IOEndian::Big,
+        }
+    }
+
+    fn create_dxt1_bitmap(width: u16, height: u16) -> Bitmap {
+        // Arbitrary block data so each block is different
+        let raw_data = (0..((width as usize * height as usize) / 2))
+            .map(|i| ((i * 37) % 256) as u8)
+            .collect();
+
+        Bitmap {
+            bpp: 4,
+            encoding: DXGI_Encoding::DXGI_FORMAT_BC1_UNORM as u32,
+            mip_maps: 0,
+
+            width,
+            height,
+            bpl: width / 2,
+
+            raw_data
+        }
+    }
+
+    #[rstest]
+    #[case(Platform::X360)]
+    #[case(Platform::Wii)]
+    fn convert_dxt1_bitmap_round_trip(#[case] platform: Platform) {
+        let ps3_info = get_info(Platform::PS3);
+        let other_info = get_info(platform);
+
+        let bitmap = create_dxt1_bitmap(32, 16);
+        let converted = convert_bitmap(&bitmap, &ps3_info, &other_info).unwrap();
+        let restored = convert_bitmap(&converted, &other_info, &ps3_info).unwrap();
+
+        assert_ne!(bitmap.raw_data, converted.raw_data);
+        assert_eq!(bitmap.raw_data, restored.raw_data);
+        assert_eq!(bitmap.encoding, restored.encoding);
+
+        // Decodes to same image
+        assert_eq!(bitmap.unpack_rgba(&ps3_info).unwrap(), converted.unpack_rgba(&other_info).unwrap());
+    }
+
+    #[rstest]
+    fn convert_dxt1_bitmap_to_cmp_drops_small_mips() {
+        let mut bitmap = create_dxt1_bitmap(16, 16);
+        let small_mips = [0xAAu8; (8 * 8 + 4 * 4) / 2];
+
+        bitmap.raw_data.extend_from_slice(&small_mips);
+        bitmap.mip_maps = 2;
+
+        let converted = convert_bitmap(&bitmap, &get_info(Platform::PS3), &get_info(Platform::Wii)).unwrap();
+
+        assert_eq!(72, converted.encoding);
+        assert_eq!(1, converted.mip_maps);
+        assert_eq!((16 * 16 + 8 * 8) / 2, converted.raw_data.len());
+    }
+
+    #[rstest]
+    #[case(Platform::Wii, 64, 32, 16 * 16 * 4)] // RGBA8 (decoding not implemented)
+    #[case(Platform::Wii, 72, 4, 16)] // Missing data
+    #[case(Platform::X360, 12, 4, 16 * 16 / 2)] // Unknown encoding
+    fn convert_bitmap_unsupported(#[case] platform: Platform, #[case] encoding: u32, #[case] bpp: u8, #[case] data_size: usize) {
+        let bitmap = Bitmap {
+            bpp,
+            encoding,
+            mip_maps: 0,
+
+            width: 16,
+            height: 16,
+            bpl: 16,
+
+            raw_data: vec![0u8; data_size]
+        };
+
+        let to_platform = match platform {
+            Platform::Wii => Platform::X360,
+            _ => Platform::Wii
+        };
+
+        let result = convert_bitmap(&bitmap, &get_info(platform), &get_info(to_platform));
+
+        assert!(matches!(
+            result,
+            Err(crate::Error::Convert(ConvertError::UnsupportedBitmap { encoding: e, .. })) if e == encoding
+        ));
+    }
+
+    #[rstest]
+    fn convert_object_dir_with_unsupported_bitmap() {
+        let from = get_info(Platform::Wii);
+        let to = get_info(Platform::X360);
+
+        let mut tex = Tex::new();
+        tex.name = String::from("rgba.tex");
+        tex.bitmap = Some(Bitmap {
+            bpp: 32,
+            encoding: TPLEncoding::RGBA8 as u32,
+            mip_maps: 0,
+
+            width: 8,
+            height: 8,
+            bpl: 32,
+
+            raw_data: vec![0xFFu8; 8 * 8 * 4]
+        });
+
+        let mut obj_dir = ObjectDir::ObjectDir(ObjectDirBase {
+            entries: vec![Object::Tex(tex)],
+            ..ObjectDirBase::new()
+        });
+
+        let report = convert_object_dir(&mut obj_dir, &from, &to);
+
+        assert!(report.converted.is_empty());
+        assert_eq!(1, report.errors.len());
+        assert!(matches!(
+            report.errors[0].get_inner(),
+            crate::Error::Convert(ConvertError::UnsupportedBitmap { encoding: 64, .. })
+        ));
+    }
+
+    #[rstest]
+    fn convert_object_dir_to_other_platform() {
+        let from = get_info(Platform::X360);
+        let to = SystemInfo {
+            platform: Platform::PS3,
+            ..from
+        };
+
+        let mut tex = Tex::new();
+        tex.name = String::from("floor.tex");
+        tex.bitmap = Some(convert_bitmap(&create_dxt1_bitmap(16, 16), &to, &from).unwrap());
+
+        let mut obj_dir = ObjectDir::ObjectDir(ObjectDirBase {
+            entries: vec![
+                Object::Tex(tex),
+                Object::Packed(PackedObject {
+                    name: String::from("crowd.fx"),
+                    object_type: String::from("UnknownFx"),
+                    data: vec![1, 2, 3, 4]
+                }),
+            ],
+            ..ObjectDirBase::new()
+        });
+
+        let report = convert_object_dir(&mut obj_dir, &from, &to);
+
+        assert_eq!(vec!["floor.tex"], report.converted);
+        assert_eq!(vec!["crowd.fx"], report.copied);
+        assert!(report.errors.is_empty());
+
+        let Some(Object::Tex(converted_tex)) = obj_dir.get_entries()[0].unpack(&to) else {
+            panic!("Tex not converted");
+        };
+
+        assert_eq!(
+            create_dxt1_bitmap(16, 16).raw_data,
+            converted_tex.bitmap.unwrap().raw_data
+        );
+
+        // Unknown entries can't be converted to other versions
+        let report = convert_object_dir(&mut obj_dir, &to, &SystemInfo { version: 24, ..to });
+
+        assert_eq!(1, report.errors.len());
+        assert!(matches!(report.errors[0].get_inner(), crate::Error::UnsupportedObjectType { .. }));
+        assert_eq!(vec!["floor.tex"], report.converted);
+
+        // Entry is kept as is
+        assert_eq!(vec!["crowd.fx"], report.failed);
+        assert_eq!(2, obj_dir.get_entries().len());
+        assert!(matches!(&obj_dir.get_entries()[1], Object::Packed(p) if p.name == "crowd.fx" && p.data == vec![1, 2, 3, 4]));
+
+        // Or removed
+        let settings = ConvertSettings {
+            remove_failed: true,
+            ..Default::default()
+        };
+        let report = convert_object_dir_with_settings(&mut obj_dir, &SystemInfo { version: 24, ..to }, &to, &settings);
+
+        assert_eq!(vec!["crowd.fx"], report.failed);
+        assert_eq!(1, obj_dir.get_entries().len());
+        assert_eq!("floor.tex", obj_dir.get_entries()[0].get_name());
+    }
+
+    #[rstest]
+    fn convert_object_dir_with_preset_object_versions() {
+        let from = get_info(Platform::X360);
+        let preset = GamePreset::find("gh2").unwrap();
+        let to = preset.get_system_info(None);
+
+        let mut obj_dir = ObjectDir::ObjectDir(ObjectDirBase {
+            entries: vec![
+                Object::Cam(CamObject {
+                    name: String::from("main.cam"),
+                    ..Default::default()
+                }),
+                Object::Mat(MatObject {
+                    name: String::from("floor.mat"),
+                    ..Default::default()
+                }),
+            ],
+            ..ObjectDirBase::new()
+        });
+
+        let settings = ConvertSettings {
+            preset: Some(preset),
+            ..Default::default()
+        };
+        let report = convert_object_dir_with_settings(&mut obj_dir, &from, &to, &settings);
+
+        // Cam is written with same version as game, Mat isn't
+        assert_eq!(vec!["main.cam"], report.converted);
+        assert_eq!(1, report.errors.len());
+        assert!(matches!(
+            report.errors[0].get_inner(),
+            crate::Error::Convert(ConvertError::UnsupportedObjectVersion { version: 28, expected: 27, .. })
+        ));
+        assert!(matches!(&obj_dir.get_entries()[1], Object::Mat(mat) if mat.name == "floor.mat"));
+    }
+}
@@ -2,6 +2,65 @@ use thiserror::Error as ThisError;
 
 #[derive(Debug, ThisError)]
 pub enum ParseDTAError {
-    #[error("Unknown DTA parse error")]
-    UnknownDTAParseError,
-}
\ No newline at end of file
+    #[error("Unclosed '{open}' starting at line {line}, column {column}")]
+    UnclosedArray {
+        open: char,
+        line: usize,
+        column: usize
+    },
+    #[error("Unexpected '{close}' at line {line}, column {column}")]
+    UnexpectedClose {
+        close: char,
+        line: usize,
+        column: usize
+    },
+    #[error("Unclosed string starting at line {line}, column {column}")]
+    UnclosedString {
+        line: usize,
+        column: usize
+    },
+    #[error("Unclosed quoted symbol starting at line {line}, column {column}")]
+    UnclosedSymbol {
+        line: usize,
+        column: usize
+    },
+    #[error("Unclosed comment starting at line {line}, column {column}")]
+    UnclosedComment {
+        line: usize,
+        column: usize
+    },
+    #[error("Invalid token \"{token}\" at line {line}, column {column}")]
+    InvalidToken {
+        token: String,
+        line: usize,
+        column: usize
+    },
+    #[error("Unknown directive \"{directive}\" at line {line}, column {column}")]
+    UnknownDirective {
+        directive: String,
+        line: usize,
+        column: usize
+    },
+    #[error("Missing name for \"{directive}\" at line {line}, column {column}")]
+    MissingDirectiveName {
+        directive: String,
+        line: usize,
+        column: usize
+    },
+}
+
+impl ParseDTAError {
+    /// Gets line and column of error (both start at 1)
+    pub fn get_position(&self) -> (usize, usize) {
+        match self {
+            ParseDTAError::UnclosedArray { line, column, .. }
+                | ParseDTAError::UnexpectedClose { line, column, .. }
+                | ParseDTAError::UnclosedString { line, column }
+                | ParseDTAError::UnclosedSymbol { line, column }
+                | ParseDTAError::UnclosedComment { line, column }
+                | ParseDTAError::InvalidToken { line, column, .. }
+                | ParseDTAError::UnknownDirective { line, column, .. }
+                | ParseDTAError::MissingDirectiveName { line, column, .. } => (*line, *column),
+        }
+    }
+}
@@ -15,8 +15,30 @@ pub enum DtaLoadError {
     UnknownVersion {
         version: u32
     },
+    #[error("Expected null terminator after string at offset 0x{offset:X}")]
+    MissingStringTerminator {
+        offset: u64
+    },
 }
 
+#[derive(Debug, ThisError)]
+pub enum DtaSaveError {
+    #[error("Node type {node_type:#02X} can't be written to Amplitude dtb")]
+    UnsupportedAmplitudeNode {
+        node_type: u32
+    },
+}
+
+const AMP_DTB_VERSION: u8 = 2;
+
+/// Binary layouts of dtb data
+///
+/// - Milo: `u16` node count + `u32` line number per array
+/// - Amplitude: version byte + original file names, then `u16` node count + `u32` line number + `u32` id
+///   and node types packed in 2-bits per array
+/// - Forge: `u32` node count + `u32` line number + `u32` id per array, null terminated strings.
+///   Unverified: hasn't been checked against dtb files from Forge games or a public spec yet, so loading
+///   rejects strings without null terminator instead of reading misaligned data.
 #[derive(Debug)]
 pub enum DataArrayIOSettings {
     Milo,
@@ -25,19 +47,51 @@ pub enum DataArrayIOSettings {
 }
 
 impl DataArray {
-    pub fn load(&mut self, _stream: &mut Box<BinaryStream>) -> Result<(), Box<dyn Error>> {
-        todo!()
+    /// Loads single node (milo dtb)
+    pub fn load(&mut self, stream: &mut Box<BinaryStream>) -> Result<(), Box<dyn Error>> {
+        *self = load_node(stream, &mut Vec::new(), &mut Vec::new(), load_array_with_ids, load_string)?;
+        Ok(())
     }
 }
 
 impl RootData {
     pub fn save(&self, stream: &mut Box<BinaryStream>) -> Result<(), Box<dyn Error>> {
-        let has_data = !self.data.is_empty();
+        self.save_with_settings(stream, DataArrayIOSettings::Milo)
+    }
 
-        // Save data
-        stream.write_boolean(has_data)?;
-        if has_data {
-            save_array(&self.data, stream, &mut 0)?;
+    /// Saves dtb using line numbers + array ids from parser or loaded dtb (order of arrays is used if missing)
+    pub fn save_with_settings(&self, stream: &mut Box<BinaryStream>, settings: DataArrayIOSettings) -> Result<(), Box<dyn Error>> {
+        let mut ids = ArrayIds::from_root(self);
+
+        match settings {
+            DataArrayIOSettings::Milo => {
+                let has_data = !self.data.is_empty();
+
+                // Save data
+                stream.write_boolean(has_data)?;
+                if has_data {
+                    save_array_with_ids(&self.data, stream, &mut ids)?;
+                }
+            },
+            DataArrayIOSettings::Forge => {
+                let has_data = !self.data.is_empty();
+
+                stream.write_boolean(has_data)?;
+                if has_data {
+                    save_array_forge(&self.data, stream, &mut ids)?;
+                }
+            },
+            DataArrayIOSettings::Amplitude => {
+                stream.write_uint8(AMP_DTB_VERSION)?;
+
+                // Write original file names
+                stream.write_uint32(self.file_names.len() as u32)?;
+                for name in self.file_names.iter() {
+                    stream.write_prefixed_string(name)?;
+                }
+
+                save_array_amp(&self.data, stream, &mut ids)?;
+            }
         }
 
         Ok(())
@@ -45,12 +99,12 @@ impl RootData {
 
     pub fn load(&mut self, stream: &mut Box<BinaryStream>) -> Result<(), Box<dyn Error>> {
         // Clear data
-        self.data.clear();
+        self.clear();
 
         // Read data
         let has_data = stream.read_boolean()?;
         if has_data {
-            self.data = load_array(stream)?;
+            self.data = load_array_with_ids(stream, &mut self.line_numbers, &mut self.array_ids)?;
         };
 
         Ok(())
@@ -61,51 +115,167 @@ impl RootData {
             DataArrayIOSettings::Milo => {
                 return self.load(stream);
             },
-            DataArrayIOSettings::Forge => todo!("Can't read Forge dtb"),
+            DataArrayIOSettings::Forge => {
+                // Clear data
+                self.clear();
+
+                // Read data
+                let has_data = stream.read_boolean()?;
+                if has_data {
+                    self.data = load_array_forge(stream, &mut self.line_numbers, &mut self.array_ids)?;
+                }
+
+                Ok(())
+            },
             DataArrayIOSettings::Amplitude => {
                 // Clear data
-                self.data.clear();
+                self.clear();
 
                 // Read data
                 let data_version = stream.read_uint8()?;
 
-                if data_version != 2 {
+                if data_version != AMP_DTB_VERSION {
                     return Err(Box::new(DtaLoadError::UnknownVersion {
                         version: data_version as u32
                     }));
                 }
 
-                // Read original file names (ignore for now)
+                // Read original file names
                 let name_count = stream.read_uint32()?;
                 for _ in 0..name_count {
-                    stream.read_prefixed_string()?;
+                    self.file_names.push(stream.read_prefixed_string()?);
                 }
 
-                self.data = load_array_amp(stream)?;
+                self.data = load_array_amp(stream, &mut self.line_numbers, &mut self.array_ids)?;
                 Ok(())
             }
         }
     }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.line_numbers.clear();
+        self.array_ids.clear();
+        self.file_names.clear();
+    }
+}
+
+/// Line number + id to write for each array (in order arrays are written)
+struct ArrayIds<'a> {
+    line_numbers: &'a [u32],
+    array_ids: &'a [u32],
+    index: u32,
+}
+
+impl<'a> ArrayIds<'a> {
+    fn from_root(root: &'a RootData) -> ArrayIds<'a> {
+        ArrayIds {
+            line_numbers: &root.line_numbers,
+            array_ids: &root.array_ids,
+            index: 0
+        }
+    }
+
+    fn from_index(index: u32) -> ArrayIds<'a> {
+        ArrayIds {
+            line_numbers: &[],
+            array_ids: &[],
+            index
+        }
+    }
+
+    fn next(&mut self) -> (u32, u32) {
+        let i = self.index as usize;
+
+        let line_number = self.line_numbers.get(i).copied().unwrap_or(self.index);
+        let id = self.array_ids.get(i).copied().unwrap_or(self.index);
+
+        self.index += 1;
+        (line_number, id)
+    }
 }
 
 pub(crate) fn save_array(data: &Vec<DataArray>, stream: &mut Box<BinaryStream>, id: &mut u32) -> Result<(), Box<dyn Error>> {
+    let mut ids = ArrayIds::from_index(*id);
+    save_array_with_ids(data, stream, &mut ids)?;
+
+    *id = ids.index;
+    Ok(())
+}
+
+fn save_array_with_ids(data: &[DataArray], stream: &mut Box<BinaryStream>, ids: &mut ArrayIds) -> Result<(), Box<dyn Error>> {
+    // Id is actually line # in dta
+    let (line_number, _) = ids.next();
+
+    stream.write_uint16(data.len() as u16)?;
+    stream.write_uint32(line_number)?;
+
+    for node in data {
+        save_node(node, stream, ids, save_array_with_ids, save_string)?;
+    }
+
+    Ok(())
+}
+
+fn save_array_forge(data: &[DataArray], stream: &mut Box<BinaryStream>, ids: &mut ArrayIds) -> Result<(), Box<dyn Error>> {
+    let (line_number, id) = ids.next();
+
+    stream.write_uint32(data.len() as u32)?;
+    stream.write_uint32(line_number)?;
+    stream.write_uint32(id)?;
+
+    for node in data {
+        save_node(node, stream, ids, save_array_forge, save_string_forge)?;
+    }
+
+    Ok(())
+}
+
+fn save_array_amp(data: &[DataArray], stream: &mut Box<BinaryStream>, ids: &mut ArrayIds) -> Result<(), Box<dyn Error>> {
+    let (line_number, id) = ids.next();
+
     stream.write_uint16(data.len() as u16)?;
-    stream.write_uint32(*id)?;
+    stream.write_uint32(line_number)?;
+    stream.write_uint32(id)?;
 
-    // Update id (actually line # in dta)
-    *id += 1;
+    // Types are packed in 2-bits, so 16 types per 32-bit word
+    let mut types = vec![0u32; data.len().div_ceil(16)];
+
+    for (i, node) in data.iter().enumerate() {
+        let typ = match node {
+            DataArray::Integer(_) => 0x00,
+            DataArray::Symbol(_) | DataArray::String(_) => 0x01,
+            DataArray::Float(_) => 0x02,
+            DataArray::Array(_) => 0x03,
+            _ => return Err(Box::new(DtaSaveError::UnsupportedAmplitudeNode {
+                node_type: node.get_enum_value()
+            }))
+        };
+
+        types[i / 16] |= typ << ((i % 16) * 2);
+    }
+
+    for typ in types {
+        stream.write_uint32(typ)?;
+    }
 
     for node in data {
-        save_node(node, stream, id)?;
+        match node {
+            DataArray::Integer(int) => stream.write_int32(*int)?,
+            DataArray::Symbol(str) | DataArray::String(str) => save_string(str, stream)?,
+            DataArray::Float(f) => stream.write_float32(*f)?,
+            DataArray::Array(arr) => save_array_amp(arr, stream, ids)?,
+            _ => unreachable!("Node types are checked above"),
+        }
     }
 
     Ok(())
 }
 
-pub(crate) fn load_array_amp(stream: &mut Box<BinaryStream>) -> Result<Vec<DataArray>, Box<dyn Error>> {
+pub(crate) fn load_array_amp(stream: &mut Box<BinaryStream>, line_numbers: &mut Vec<u32>, array_ids: &mut Vec<u32>) -> Result<Vec<DataArray>, Box<dyn Error>> {
     let count = stream.read_uint16()? as usize;
-    let _id_1 = stream.read_uint32()?;
-    let _id_2 = stream.read_uint32()?;
+    line_numbers.push(stream.read_uint32()?);
+    array_ids.push(stream.read_uint32()?);
 
     // Types are packed in 2-bits, so 16 types per 32-bit word
     let mut type_count = count / 16;
@@ -127,26 +297,50 @@ pub(crate) fn load_array_amp(stream: &mut Box<BinaryStream>) -> Result<Vec<DataA
         let rem = i % 16;
         let typ = (types[div] >> (rem * 2)) & 0x03;
 
-        nodes.push(load_node_amp(stream, typ)?);
+        nodes.push(load_node_amp(stream, typ, line_numbers, array_ids)?);
     }
 
     Ok(nodes)
 }
 
 pub(crate) fn load_array(stream: &mut Box<BinaryStream>) -> Result<Vec<DataArray>, Box<dyn Error>> {
+    load_array_with_ids(stream, &mut Vec::new(), &mut Vec::new())
+}
+
+fn load_array_with_ids(stream: &mut Box<BinaryStream>, line_numbers: &mut Vec<u32>, array_ids: &mut Vec<u32>) -> Result<Vec<DataArray>, Box<dyn Error>> {
+    // Milo dtb doesn't have secondary ids
     let count = stream.read_uint16()? as usize;
-    let _id = stream.read_uint32()?;
+    line_numbers.push(stream.read_uint32()?);
 
     let mut nodes = Vec::new();
 
     for _ in 0..count {
-        nodes.push(load_node(stream)?);
+        nodes.push(load_node(stream, line_numbers, array_ids, load_array_with_ids, load_string)?);
     }
 
     Ok(nodes)
 }
 
-fn save_node(data: &DataArray, writer: &mut Box<BinaryStream>, id: &mut u32) -> Result<(), Box<dyn Error>> {
+fn load_array_forge(stream: &mut Box<BinaryStream>, line_numbers: &mut Vec<u32>, array_ids: &mut Vec<u32>) -> Result<Vec<DataArray>, Box<dyn Error>> {
+    let count = stream.read_uint32()? as usize;
+    line_numbers.push(stream.read_uint32()?);
+    array_ids.push(stream.read_uint32()?);
+
+    let mut nodes = Vec::new();
+
+    for _ in 0..count {
+        nodes.push(load_node(stream, line_numbers, array_ids, load_array_forge, load_string_forge)?);
+    }
+
+    Ok(nodes)
+}
+
+type SaveArrayFn = fn(&[DataArray], &mut Box<BinaryStream>, &mut ArrayIds) -> Result<(), Box<dyn Error>>;
+type SaveStringFn = fn(&DataString, &mut Box<BinaryStream>) -> Result<(), Box<dyn Error>>;
+type LoadArrayFn = fn(&mut Box<BinaryStream>, &mut Vec<u32>, &mut Vec<u32>) -> Result<Vec<DataArray>, Box<dyn Error>>;
+type LoadStringFn = fn(&mut Box<BinaryStream>) -> Result<DataString, Box<dyn Error>>;
+
+fn save_node(data: &DataArray, writer: &mut Box<BinaryStream>, ids: &mut ArrayIds, save_array: SaveArrayFn, save_string: SaveStringFn) -> Result<(), Box<dyn Error>> {
     let node_enum = data.get_enum_value();
     writer.write_uint32(node_enum)?;
 
@@ -154,19 +348,17 @@ fn save_node(data: &DataArray, writer: &mut Box<BinaryStream>, id: &mut u32) ->
         DataArray::Integer(int) => writer.write_int32(*int)?,
         DataArray::Float(f) => writer.write_float32(*f)?,
         DataArray::Variable(str) => save_string(str, writer)?,
-        /*DataArray::Func(str) => {
-            save_string(str, writer)?;
-        },*/
+        DataArray::Func(str) => save_string(str, writer)?,
         DataArray::Object(str) => save_string(str, writer)?,
         DataArray::Symbol(str) => save_string(str, writer)?,
         DataArray::KDataUnhandled => writer.write_int32(0)?,
         DataArray::IfDef(str) => save_string(str, writer)?,
         DataArray::Else => writer.write_int32(0)?,
         DataArray::EndIf => writer.write_int32(0)?,
-        DataArray::Array(arr) => save_array(arr, writer, id)?,
-        DataArray::Command(arr) => save_array(arr, writer, id)?,
+        DataArray::Array(arr) => save_array(arr, writer, ids)?,
+        DataArray::Command(arr) => save_array(arr, writer, ids)?,
         DataArray::String(str) => save_string(str, writer)?,
-        DataArray::Property(arr) => save_array(arr, writer, id)?,
+        DataArray::Property(arr) => save_array(arr, writer, ids)?,
         DataArray::Define(str) => save_string(str, writer)?,
         DataArray::Include(str) => save_string(str, writer)?,
         DataArray::Merge(str) => save_string(str, writer)?,
@@ -177,14 +369,14 @@ fn save_node(data: &DataArray, writer: &mut Box<BinaryStream>, id: &mut u32) ->
     Ok(())
 }
 
-fn load_node(stream: &mut Box<BinaryStream>) -> Result<DataArray, Box<dyn Error>> {
+fn load_node(stream: &mut Box<BinaryStream>, line_numbers: &mut Vec<u32>, array_ids: &mut Vec<u32>, load_array: LoadArrayFn, load_string: LoadStringFn) -> Result<DataArray, Box<dyn Error>> {
     let node_type = stream.read_uint32()?;
 
     let node = match node_type {
         0x00 => DataArray::Integer(stream.read_int32()?),
         0x01 => DataArray::Float(stream.read_float32()?),
         0x02 => DataArray::Variable(load_string(stream)?),
-        0x03 => todo!(), // TODO: Add func support
+        0x03 => DataArray::Func(load_string(stream)?),
         0x04 => DataArray::Object(load_string(stream)?),
         0x05 => DataArray::Symbol(load_string(stream)?),
         0x06 => {
@@ -200,10 +392,10 @@ fn load_node(stream: &mut Box<BinaryStream>) -> Result<DataArray, Box<dyn Error>
             stream.seek(SeekFrom::Current(4))?;
             DataArray::EndIf
         },
-        0x10 => DataArray::Array(load_array(stream)?),
-        0x11 => DataArray::Command(load_array(stream)?),
+        0x10 => DataArray::Array(load_array(stream, line_numbers, array_ids)?),
+        0x11 => DataArray::Command(load_array(stream, line_numbers, array_ids)?),
         0x12 => DataArray::String(load_string(stream)?),
-        0x13 => DataArray::Property(load_array(stream)?),
+        0x13 => DataArray::Property(load_array(stream, line_numbers, array_ids)?),
         0x20 => DataArray::Define(load_string(stream)?),
         0x21 => DataArray::Include(load_string(stream)?),
         0x22 => DataArray::Merge(load_string(stream)?),
@@ -221,12 +413,12 @@ fn load_node(stream: &mut Box<BinaryStream>) -> Result<DataArray, Box<dyn Error>
     Ok(node)
 }
 
-fn load_node_amp(stream: &mut Box<BinaryStream>, node_type: u32) -> Result<DataArray, Box<dyn Error>> {
+fn load_node_amp(stream: &mut Box<BinaryStream>, node_type: u32, line_numbers: &mut Vec<u32>, array_ids: &mut Vec<u32>) -> Result<DataArray, Box<dyn Error>> {
     let node = match node_type {
         0x00 => DataArray::Integer(stream.read_int32()?),
         0x01 => DataArray::Symbol(load_string(stream)?),
         0x02 => DataArray::Float(stream.read_float32()?),
-        0x03 => DataArray::Array(load_array_amp(stream)?),
+        0x03 => DataArray::Array(load_array_amp(stream, line_numbers, array_ids)?),
         _ => unreachable!("Shouldn't be reached. Node type of \"{node_type}\" is invalid"),
     };
 
@@ -242,7 +434,248 @@ fn save_string(str: &DataString, stream: &mut Box<BinaryStream>) -> Result<(), B
     Ok(())
 }
 
+/// Same as milo but with null terminator after string (not included in length)
+fn save_string_forge(str: &DataString, stream: &mut Box<BinaryStream>) -> Result<(), Box<dyn Error>> {
+    save_string(str, stream)?;
+    stream.write_uint8(0)?;
+
+    Ok(())
+}
+
 fn load_string(stream: &mut Box<BinaryStream>) -> Result<DataString, Box<dyn Error>> {
     let length = stream.read_uint32()? as usize;
     Ok(stream.read_bytes(length)?.into())
-}
\ No newline at end of file
+}
+
+fn load_string_forge(stream: &mut Box<BinaryStream>) -> Result<DataString, Box<dyn Error>> {
+    let str = load_string(stream)?;
+
+    let offset = stream.pos();
+    if stream.read_uint8()? != 0 {
+        return Err(Box::new(DtaLoadError::MissingStringTerminator {
+            offset
+        }));
+    }
+
+    Ok(str)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+    use crate::io::MemoryStream;
+
+    fn load_root(data: &[u8], settings: DataArrayIOSettings) -> RootData {
+        let mut stream = MemoryStream::from_slice_as_read(data);
+        let mut reader = Box::new(BinaryStream::from_stream(&mut stream));
+
+        let mut root = RootData::new();
+        root.load_with_settings(&mut reader, settings).unwrap();
+        root
+    }
+
+    fn save_root(root: &RootData, settings: DataArrayIOSettings) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        let mut writer = Box::new(BinaryStream::from_stream(&mut stream));
+
+        root.save_with_settings(&mut writer, settings).unwrap();
+        data
+    }
+
+    #[rstest]
+    fn milo_dtb_round_trip() {
+        let dtb: &[u8] = &[
+            0x01, // Has data
+            0x02, 0x00, 0x05, 0x00, 0x00, 0x00, // Root (2 nodes, line 5)
+            0x05, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, b'n', b'a', b'm', b'e', // Symbol
+            0x10, 0x00, 0x00, 0x00, 0x01, 0x00, 0x09, 0x00, 0x00, 0x00, // Array (1 node, line 9)
+            0x00, 0x00, 0x00, 0x00, 0x2A, 0x00, 0x00, 0x00, // Integer
+        ];
+
+        let root = load_root(dtb, DataArrayIOSettings::Milo);
+
+        assert_eq!(vec![
+            DataArray::Symbol("name".into()),
+            DataArray::Array(vec![DataArray::Integer(42)]),
+        ], root.data);
+        assert_eq!(vec![5, 9], root.line_numbers);
+
+        assert_eq!(dtb, save_root(&root, DataArrayIOSettings::Milo).as_slice());
+    }
+
+    #[rstest]
+    fn amp_dtb_round_trip() {
+        let dtb: &[u8] = &[
+            0x02, // Version
+            0x01, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, b's', b'o', b'n', b'g', b's', b'.', b'd', b't', b'a', // File names
+            0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, // Root (2 nodes, line 1, id 7)
+            0x0D, 0x00, 0x00, 0x00, // Types (symbol, array)
+            0x04, 0x00, 0x00, 0x00, b'n', b'a', b'm', b'e', // Symbol
+            0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, // Array (1 node, line 2, id 8)
+            0x02, 0x00, 0x00, 0x00, // Types (float)
+            0x00, 0x00, 0x80, 0x3F, // Float
+        ];
+
+        let root = load_root(dtb, DataArrayIOSettings::Amplitude);
+
+        assert_eq!(vec![
+            DataArray::Symbol("name".into()),
+            DataArray::Array(vec![DataArray::Float(1.0)]),
+        ], root.data);
+        assert_eq!(vec![1, 2], root.line_numbers);
+        assert_eq!(vec![7, 8], root.array_ids);
+        assert_eq!(vec![String::from("songs.dta")], root.file_names);
+
+        assert_eq!(dtb, save_root(&root, DataArrayIOSettings::Amplitude).as_slice());
+    }
+
+    #[rstest]
+    #[case(&[0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF], DataArray::Integer(-1))]
+    #[case(&[0x03, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, b'p', b'r', b'i', b'n', b't'], DataArray::Func("print".into()))]
+    #[case(&[0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], DataArray::Else)]
+    #[case(&[0x11, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, b'f'], DataArray::Command(vec![DataArray::Func("f".into())]))]
+    fn load_data_array(#[case] data: &[u8], #[case] expected: DataArray) {
+        let mut stream = MemoryStream::from_slice_as_read(data);
+        let mut reader = Box::new(BinaryStream::from_stream(&mut stream));
+
+        let mut node = DataArray::default();
+        node.load(&mut reader).unwrap();
+
+        assert_eq!(expected, node);
+    }
+
+    #[rstest]
+    fn milo_dtb_with_func_round_trip() {
+        let dtb: &[u8] = &[
+            0x01, // Has data
+            0x01, 0x00, 0x01, 0x00, 0x00, 0x00, // Root (1 node, line 1)
+            0x11, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, // Command (2 nodes, line 2)
+            0x03, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, b'p', b'r', b'i', b'n', b't', // Func
+            0x12, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, b'h', b'i', // String
+        ];
+
+        let root = load_root(dtb, DataArrayIOSettings::Milo);
+
+        assert_eq!(vec![
+            DataArray::Command(vec![DataArray::Func("print".into()), DataArray::String("hi".into())]),
+        ], root.data);
+
+        assert_eq!(dtb, save_root(&root, DataArrayIOSettings::Milo).as_slice());
+    }
+
+    #[rstest]
+    fn forge_dtb_round_trip() {
+        let dtb: &[u8] = &[
+            0x01, // Has data
+            0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, // Root (3 nodes, line 1, id 3)
+            0x05, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, b'n', b'a', b'm', b'e', 0x00, // Symbol
+            0x12, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, b'h', b'i', 0x00, // String
+            0x10, 0x00, 0x00, 0x00, // Array
+            0x02, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, // (2 nodes, line 4, id 4)
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3F, // Float
+            0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // kDataUnhandled
+        ];
+
+        let root = load_root(dtb, DataArrayIOSettings::Forge);
+
+        assert_eq!(vec![
+            DataArray::Symbol("name".into()),
+            DataArray::String("hi".into()),
+            DataArray::Array(vec![DataArray::Float(0.5), DataArray::KDataUnhandled]),
+        ], root.data);
+        assert_eq!(vec![1, 4], root.line_numbers);
+        assert_eq!(vec![3, 4], root.array_ids);
+
+        assert_eq!(dtb, save_root(&root, DataArrayIOSettings::Forge).as_slice());
+    }
+
+    #[rstest]
+    fn forge_dtb_load_nested() {
+        // Hand-built, line numbers and ids aren't sequential like the writer's
+        let dtb: &[u8] = &[
+            0x01, // Has data
+            0x02, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, // Root (2 nodes, line 12, id 32)
+            0x13, 0x00, 0x00, 0x00, // Property
+            0x01, 0x00, 0x00, 0x00, 0x0F, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, // (1 node, line 15, id 7)
+            0x05, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, b'f', b'o', b'o', 0x00, // Symbol
+            0x11, 0x00, 0x00, 0x00, // Command
+            0x03, 0x00, 0x00, 0x00, 0x0F, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, // (3 nodes, line 15, id 5)
+            0x03, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, b's', b'e', b't', 0x00, // Func
+            0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, b'x', 0x00, // Variable
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Object (empty)
+        ];
+
+        let root = load_root(dtb, DataArrayIOSettings::Forge);
+
+        assert_eq!(vec![
+            DataArray::Property(vec![DataArray::Symbol("foo".into())]),
+            DataArray::Command(vec![
+                DataArray::Func("set".into()),
+                DataArray::Variable("x".into()),
+                DataArray::Object("".into()),
+            ]),
+        ], root.data);
+        assert_eq!(vec![12, 15, 15], root.line_numbers);
+        assert_eq!(vec![32, 7, 5], root.array_ids);
+    }
+
+    #[rstest]
+    fn forge_dtb_missing_string_terminator() {
+        // Milo layout read as Forge
+        let dtb: &[u8] = &[
+            0x01, // Has data
+            0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, // Root (1 node, line 1, id 1)
+            0x05, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, b'h', b'i', // Symbol (no terminator)
+            0x12, 0x00, 0x00, 0x00,
+        ];
+
+        let mut stream = MemoryStream::from_slice_as_read(dtb);
+        let mut reader = Box::new(BinaryStream::from_stream(&mut stream));
+        let result = RootData::new().load_with_settings(&mut reader, DataArrayIOSettings::Forge);
+
+        let error = result.unwrap_err();
+        assert!(matches!(error.downcast_ref::<DtaLoadError>(), Some(DtaLoadError::MissingStringTerminator { offset: 0x17 })));
+    }
+
+    #[rstest]
+    fn forge_dtb_empty() {
+        let dtb: &[u8] = &[0x00];
+
+        let root = load_root(dtb, DataArrayIOSettings::Forge);
+        assert!(root.data.is_empty());
+
+        assert_eq!(dtb, save_root(&root, DataArrayIOSettings::Forge).as_slice());
+    }
+
+    #[rstest]
+    fn save_parsed_dta_uses_line_numbers() {
+        let root = parse_dta(b"(a\n\n   (b 1))").unwrap();
+
+        assert_eq!(vec![
+            0x01, // Has data
+            0x01, 0x00, 0x01, 0x00, 0x00, 0x00, // Root (line 1)
+            0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, // (a ...) (line 1)
+            0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, b'a',
+            0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03, 0x00, 0x00, 0x00, // (b 1) (line 3)
+            0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, b'b',
+            0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        ], save_root(&root, DataArrayIOSettings::Milo));
+    }
+
+    #[rstest]
+    fn save_amp_dtb_with_unsupported_node() {
+        let root = RootData {
+            data: vec![DataArray::Variable("var".into())],
+            ..Default::default()
+        };
+
+        let mut data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        let mut writer = Box::new(BinaryStream::from_stream(&mut stream));
+
+        let error = root.save_with_settings(&mut writer, DataArrayIOSettings::Amplitude).unwrap_err();
+        assert!(matches!(error.downcast_ref::<DtaSaveError>(), Some(DtaSaveError::UnsupportedAmplitudeNode { node_type: 0x02 })));
+    }
+}
@@ -4,7 +4,7 @@ mod parser;
 
 pub use errors::*;
 pub use io::*;
-use parser::*;
+pub use parser::parse_dta;
 
 const CHAR_NEWLINE: u8 = b'\n';
 const CHAR_SPACE: u8 = b' ';
@@ -76,7 +76,13 @@ pub enum DataArray {
     Integer(i32),
     Float(f32),
     Variable(DataString),
-    //Func() ???
+    /// Name of inline function
+    ///
+    /// Printed as bare name so parsing printed dta back gives `Symbol`
+    Func(DataString),
+    /// Name of object
+    ///
+    /// Printed as bare name so parsing printed dta back gives `Symbol`
     Object(DataString),
     Symbol(DataString),
     KDataUnhandled,
@@ -123,7 +129,7 @@ impl DataArray {
             DataArray::Integer(_)     => 0x00,
             DataArray::Float(_)       => 0x01,
             DataArray::Variable(_)    => 0x02,
-            //DataArray::Func(_)        => 0x03,
+            DataArray::Func(_)        => 0x03,
             DataArray::Object(_)      => 0x04,
             DataArray::Symbol(_)      => 0x05,
             DataArray::KDataUnhandled => 0x06,
@@ -172,6 +178,18 @@ impl DataArray {
         None
     }
 
+    /// Counts func and object nodes (including nested ones). These are printed as bare names
+    /// so node type is lost when printed dta is parsed again.
+    pub fn count_lossy_print_nodes(&self) -> usize {
+        match self {
+            DataArray::Func(_) | DataArray::Object(_) => 1,
+            DataArray::Array(arr)
+                | DataArray::Command(arr)
+                | DataArray::Property(arr) => arr.iter().map(|n| n.count_lossy_print_nodes()).sum(),
+            _ => 0
+        }
+    }
+
     pub fn as_float(&self) -> Option<f32> {
         match self {
             DataArray::Float(f) => Some(*f),
@@ -191,6 +209,7 @@ impl DataArray {
     pub fn as_string(&self) -> Option<&DataString> {
         match self {
             DataArray::Variable(s)
+                | DataArray::Func(s)
                 | DataArray::Object(s)
                 | DataArray::Symbol(s)
                 | DataArray::IfDef(s)
@@ -224,9 +243,13 @@ impl DataArray {
                 stream.write_all(b"$")?;
                 stream.write_all(&v.data)?;
             },
-            //DataArray::Func(_)        => 0x03,
-            DataArray::Object(_o) => {
-                todo!("Support object dta serialization")
+            DataArray::Func(f) => {
+                // No dta syntax for func nodes so written as bare name (re-parsed as symbol)
+                stream.write_all(&f.data)?;
+            },
+            DataArray::Object(o) => {
+                // No dta syntax for object nodes so written as bare name (re-parsed as symbol)
+                stream.write_all(&o.data)?;
             },
             DataArray::Symbol(s) => {
                 if s.data.iter().any(|c| c.eq(&CHAR_SPACE)) {
@@ -320,6 +343,12 @@ impl DataArray {
 #[derive(Debug, Default)]
 pub struct RootData {
     pub data: Vec<DataArray>,
+    /// Line numbers of arrays (root first, then nested arrays in order they start). Written as array ids in dtb.
+    pub line_numbers: Vec<u32>,
+    /// Secondary ids of arrays in same order as line numbers (Amplitude/Forge dtb only)
+    pub array_ids: Vec<u32>,
+    /// Original dta file names (Amplitude dtb only)
+    pub file_names: Vec<String>,
 }
 
 impl RootData {
@@ -327,6 +356,14 @@ impl RootData {
         RootData::default()
     }
 
+    /// Counts func and object nodes which can't be printed without losing node type
+    pub fn count_lossy_print_nodes(&self) -> usize {
+        self.data
+            .iter()
+            .map(|n| n.count_lossy_print_nodes())
+            .sum()
+    }
+
     pub fn print<T: std::io::Write>(&self, stream: &mut T) -> Result<(), std::io::Error> {
         self.print_with_format(stream, DTAFormat::default())
     }
@@ -381,7 +418,8 @@ mod tests {
     #[case(DataArray::Float(0.38), b"0.38")]
     #[case(DataArray::Float(-0.45), b"-0.45")]
     #[case(DataArray::Variable("test".into()), b"$test")]
-    // TODO: Test case for func + object
+    #[case(DataArray::Func("print".into()), b"print")]
+    #[case(DataArray::Object("main.cam".into()), b"main.cam")]
     #[case(DataArray::Symbol("test".into()), b"test")]
     #[case(DataArray::Symbol("lol look at these spaces".into()), b"\"lol look at these spaces\"")]
     #[case(DataArray::KDataUnhandled, b"kDataUnhandled")]
@@ -412,4 +450,28 @@ mod tests {
         //assert_eq!(expected, buffer.buffer());
         assert_eq!(expected_str, buffer_str);
     }
-}
\ No newline at end of file
+
+    #[rstest]
+    fn print_func_and_object_parse_as_symbols() {
+        let mut root = RootData::new();
+        root.data.push(DataArray::Command(vec![
+            DataArray::Func("set".into()),
+            DataArray::Object("main.cam".into()),
+        ]));
+
+        let mut data = Vec::new();
+        root.print(&mut data).unwrap();
+
+        let parsed = parse_dta(&data).unwrap();
+
+        // Func and object node types are lost when printed
+        assert_eq!(2, root.count_lossy_print_nodes());
+        assert_eq!(0, parsed.count_lossy_print_nodes());
+        assert_eq!(vec![
+            DataArray::Command(vec![
+                DataArray::Symbol("set".into()),
+                DataArray::Symbol("main.cam".into()),
+            ]),
+        ], parsed.data);
+    }
+}
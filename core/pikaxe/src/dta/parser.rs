@@ -1,40 +1,30 @@
 use nom::*;
-use nom::branch::{alt, permutation};
-use nom::bytes::complete::{is_not, tag, take, take_till, take_till1, take_while, take_while1};
-use nom::character::{is_alphanumeric, is_digit, is_hex_digit};
-use nom::character::complete::{alpha1, alphanumeric0, alphanumeric1, digit1, hex_digit1, one_of};
-use nom::combinator::{all_consuming, map, map_parser, map_res, not, opt, recognize};
-use nom::error::{context, Error};
-use nom::multi::{many0, separated_list0};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while, take_while1};
+use nom::character::is_alphanumeric;
+use nom::character::complete::{alpha1, digit1, hex_digit1};
+use nom::combinator::{all_consuming, map, map_res, opt, recognize};
 use nom::number::complete::recognize_float;
-use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::sequence::{delimited, pair, preceded};
 use super::{DataArray, ParseDTAError, RootData, DataString};
-use std::num::{IntErrorKind, ParseIntError};
 
 const WS_CHARACTERS: &[u8] = b" \t\r\n\x0c";
-const SPACE_CHARACTERS: &[u8] = b" \t";
-const NEWLINE_CHARACTERS: &[u8] = b"\r\n";
-const DIGIT_CHARACTERS_10: &[u8] = b"0123456789";
-const DIGIT_CHARACTERS_16: &[u8] = b"0123456789AaBbCcDdEeFf";
+const TOKEN_END_CHARACTERS: &[u8] = b"()[]{};\"";
 
-const OPEN_BRACKET: &[u8] = b"(";
-const CLOSE_BRACKET:&[u8] = b")";
+const QUOTE_CHARACTER: u8 = b'"';
+const SYMBOL_QUOTE_CHARACTER: u8 = b'\'';
+const COMMENT_CHARACTER: u8 = b';';
 
-const QUOTE_CHARACTER: &[u8] = b"\"";
-const COMMENT_CHARACTER: &[u8] = b";";
-
-pub struct ParsedSong<'a> {
-    pub start: usize,
-    pub size: usize,
-    pub data: &'a [u8],
-}
+const BLOCK_COMMENT_START: &[u8] = b"/*";
+const BLOCK_COMMENT_END: &[u8] = b"*/";
 
 pub struct DTAParser<'a> {
     pub depth: usize,
     pub line_number: usize,
     pub char_index: usize,
-    pub data_array: Vec<(DataArray, usize)>, // Includes char start index
-    pub remaining: &'a [u8]
+    pub line_numbers: Vec<u32>, // Lines of arrays in order they start
+    pub remaining: &'a [u8],
+    line_start: usize,
 }
 
 impl<'a> DTAParser<'a> {
@@ -43,215 +33,222 @@ impl<'a> DTAParser<'a> {
             depth: 0,
             line_number: 0,
             char_index: 0,
-            data_array: Vec::new(),
-            remaining: data
+            line_numbers: Vec::new(),
+            remaining: data,
+            line_start: 0,
         }
     }
 
-    pub fn parse(mut self) {
-        self.parse_array();
-    }
+    pub fn parse(mut self) -> Result<RootData, ParseDTAError> {
+        // Root array starts on first line
+        self.line_numbers.push(1);
+        let data = self.parse_array(None)?;
 
-    fn parse_array(&mut self) -> Vec<(DataArray, usize)> {
-        let data = Vec::new();
+        Ok(RootData {
+            data,
+            line_numbers: self.line_numbers,
+            ..Default::default()
+        })
+    }
 
-        self.consume_whitespace();
-        // TODO: Consume comments
+    /// Parses nodes until closing character (or end of data for root)
+    fn parse_array(&mut self, open: Option<(u8, usize, usize)>) -> Result<Vec<DataArray>, ParseDTAError> {
+        let mut data = Vec::new();
+
+        loop {
+            self.consume_whitespace_and_comments()?;
+
+            let Some(&c) = self.remaining.first() else {
+                return match open {
+                    Some((open, line, column)) => Err(ParseDTAError::UnclosedArray {
+                        open: open as char,
+                        line,
+                        column
+                    }),
+                    None => Ok(data)
+                };
+            };
+
+            let (line, column) = self.get_position();
+
+            let node = match c {
+                b'(' | b'[' | b'{' => {
+                    self.advance(1);
+                    self.line_numbers.push(line as u32);
+
+                    self.depth += 1;
+                    let items = self.parse_array(Some((c, line, column)))?;
+                    self.depth -= 1;
+
+                    match c {
+                        b'(' => DataArray::Array(items),
+                        b'[' => DataArray::Property(items),
+                        _ => DataArray::Command(items),
+                    }
+                },
+                b')' | b']' | b'}' => {
+                    let is_match = matches!((open, c), (Some((b'(', ..)), b')') | (Some((b'[', ..)), b']') | (Some((b'{', ..)), b'}'));
+
+                    if !is_match {
+                        return Err(ParseDTAError::UnexpectedClose {
+                            close: c as char,
+                            line,
+                            column
+                        });
+                    }
+
+                    self.advance(1);
+                    return Ok(data);
+                },
+                QUOTE_CHARACTER => {
+                    let text = self.take_quoted(QUOTE_CHARACTER)
+                        .ok_or(ParseDTAError::UnclosedString { line, column })?;
 
-        data
-    }
+                    DataArray::String(DataString::from_vec(text.to_vec()))
+                },
+                SYMBOL_QUOTE_CHARACTER => {
+                    let text = self.take_quoted(SYMBOL_QUOTE_CHARACTER)
+                        .ok_or(ParseDTAError::UnclosedSymbol { line, column })?;
 
-    fn consume_whitespace(&mut self) {
-        let mut chars_moved = 0;
-        let mut lines_moved = 0;
-
-        for c in self.remaining.iter() {
-            match c {
-                b'\n' => {
-                    chars_moved += 1;
-                    lines_moved += 1;
+                    DataArray::Symbol(DataString::from_vec(text.to_vec()))
                 },
-                c if c.is_ascii_whitespace() => {
-                    chars_moved += 1;
-                }
+                b'#' => self.parse_directive()?,
                 _ => {
-                    break;
+                    let token = self.take_token();
+
+                    match parse_value(token) {
+                        // Hex number too large for 32 bits shouldn't be parsed as symbol
+                        Ok((_, node)) if !(matches!(node, DataArray::Symbol(_)) && is_hex_token(token)) => node,
+                        _ if token.starts_with(b"$") || is_hex_token(token) => {
+                            return Err(ParseDTAError::InvalidToken {
+                                token: String::from_utf8_lossy(token).into_owned(),
+                                line,
+                                column
+                            });
+                        },
+                        // Anything else is symbol (operators, etc.)
+                        _ => DataArray::Symbol(DataString::from_vec(token.to_vec())),
+                    }
                 }
-            }
-        }
+            };
 
-        // Update self
-        self.char_index += chars_moved;
-        self.line_number += lines_moved;
-        self.remaining = &self.remaining[chars_moved..];
+            data.push(node);
+        }
     }
-}
 
-/*pub(crate) fn parse_dta_str<'a>(text: &'a [u8]) -> Vec<DataArray> {
-    //let mut parse_info = 
+    fn parse_directive(&mut self) -> Result<DataArray, ParseDTAError> {
+        let (line, column) = self.get_position();
+        let directive = self.take_token();
+
+        let node: fn(DataString) -> DataArray = match directive {
+            b"#else" => return Ok(DataArray::Else),
+            b"#endif" => return Ok(DataArray::EndIf),
+            b"#autorun" => return Ok(DataArray::Autorun),
+            b"#define" => DataArray::Define,
+            b"#include" => DataArray::Include,
+            b"#merge" => DataArray::Merge,
+            b"#ifdef" => DataArray::IfDef,
+            b"#ifndef" => DataArray::IfNDef,
+            b"#undef" => DataArray::Undef,
+            _ => return Err(ParseDTAError::UnknownDirective {
+                directive: String::from_utf8_lossy(directive).into_owned(),
+                line,
+                column
+            })
+        };
 
-    Vec::new()
-}*/
-
-fn take_ws<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    take_while(move |c| WS_CHARACTERS.contains(&c))(text)
-}
-
-fn take_newline<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    take_while(move |c| NEWLINE_CHARACTERS.contains(&c))(text)
-}
-
-fn take_ws_or_comment<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    let mut ws_result = take_ws(text);
-
-    while let Ok((text, _)) = ws_result {
-        let comment_result = take_comment(text);
-
-        if comment_result.is_err() {
-            break;
+        self.consume_whitespace();
+        let name = self.take_token();
+
+        if name.is_empty() {
+            return Err(ParseDTAError::MissingDirectiveName {
+                directive: String::from_utf8_lossy(directive).into_owned(),
+                line,
+                column
+            });
         }
 
-        let (t1, _) = comment_result.unwrap();
-        ws_result = take_ws(t1);
+        Ok(node(DataString::from_vec(name.to_vec())))
     }
 
-    ws_result
+    /// Takes text until whitespace, comment, string or array character
+    fn take_token(&mut self) -> &'a [u8] {
+        let size = self.remaining
+            .iter()
+            .position(|c| WS_CHARACTERS.contains(c) || TOKEN_END_CHARACTERS.contains(c))
+            .unwrap_or(self.remaining.len());
 
-    //alt((take_ws, take_comment))(text)
-}
+        let token = &self.remaining[..size];
+        self.advance(size);
 
-fn take_until_newline<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    take_till(move |c| NEWLINE_CHARACTERS.contains(&c))(text)
-}
+        token
+    }
 
-fn take_comment<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    context(
-        "comment",
-        preceded(
-            tag(COMMENT_CHARACTER),
-            preceded(take_until_newline, take_newline)
-        )
-    )(text)
-}
+    /// Takes text between quote characters (can span multiple lines)
+    fn take_quoted(&mut self, quote: u8) -> Option<&'a [u8]> {
+        let size = self.remaining
+            .iter()
+            .skip(1)
+            .position(|c| quote.eq(c))?;
 
-fn take_section<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    delimited(
-        preceded(take_ws, tag(OPEN_BRACKET)),
-        tag(b"test"), // alt
-        preceded(take_ws, tag(CLOSE_BRACKET)),
-    )(text)
-}
+        let text = &self.remaining[1..(size + 1)];
+        self.advance(size + 2);
 
-fn take_quoted_string<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    // TODO: Better support escaped characters?
-    context(
-        "string",
-        delimited(
-            tag(QUOTE_CHARACTER),
-            take_till(move |c| QUOTE_CHARACTER.contains(&c)),
-            tag(QUOTE_CHARACTER),
-        )
-    )(text)
-}
-
-fn take_node<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    let (mut r1, _) = take_ws_or_comment(text)?; // Whitespace
+        Some(text)
+    }
 
-    // Read open bracket
-    let open_bracket_res: IResult<&'a [u8], &'a [u8]> = tag(OPEN_BRACKET)(r1);
-    if open_bracket_res.is_ok() {
-        // Nested data...
-        r1 = &r1[1..];
+    fn consume_whitespace_and_comments(&mut self) -> Result<(), ParseDTAError> {
+        loop {
+            self.consume_whitespace();
 
-        let (nested_r1, _) = take_node(r1)?;
-        r1 = nested_r1;
-    }
+            if self.remaining.first() == Some(&COMMENT_CHARACTER) {
+                let size = self.remaining
+                    .iter()
+                    .position(|c| c.eq(&b'\n'))
+                    .unwrap_or(self.remaining.len());
 
-    // Read until close bracket
-    let mut close_bracket_res: IResult<&'a [u8], &'a [u8]>;
+                self.advance(size);
+            } else if self.remaining.starts_with(BLOCK_COMMENT_START) {
+                let (line, column) = self.get_position();
 
-    loop {
-        close_bracket_res = tag(CLOSE_BRACKET)(r1);
+                let size = self.remaining
+                    .windows(BLOCK_COMMENT_END.len())
+                    .position(|w| w.eq(BLOCK_COMMENT_END))
+                    .ok_or(ParseDTAError::UnclosedComment { line, column })?;
 
-        if r1.is_empty() {
-            break;
-        } else if close_bracket_res.is_ok() {
-            r1 = &r1[1..];
-            break;
+                self.advance(size + BLOCK_COMMENT_END.len());
+            } else {
+                return Ok(());
+            }
         }
+    }
 
+    fn consume_whitespace(&mut self) {
+        let size = self.remaining
+            .iter()
+            .position(|c| !c.is_ascii_whitespace())
+            .unwrap_or(self.remaining.len());
 
+        self.advance(size);
     }
 
-    /*while let Ok((nested_r1, _)) = close_bracket_res {
-        /*let comment_result = take_comment(text);
+    fn advance(&mut self, size: usize) {
+        for c in self.remaining[..size].iter() {
+            self.char_index += 1;
 
-        if comment_result.is_err() {
-            break;
+            if c.eq(&b'\n') {
+                self.line_number += 1;
+                self.line_start = self.char_index;
+            }
         }
 
-        let (t1, _) = comment_result.unwrap();
-        ws_result = take_ws(t1);*/
-    }*/
-
-    /*let tt: IResult<&'a [u8], &'a [u8]> = alt((
-        map(take_quoted_string, |_| (r1, r2))
-    ))(r1);*/
-
-    //return Ok((r1, r2));
-
-    //return tt;
-
-    preceded(
-        take_ws_or_comment,
-        // string
-        // int
-        // float
-        // node
-        delimited(
-            tag(OPEN_BRACKET),
-            preceded(
-                take_ws_or_comment,
-                take_while(|c| !CLOSE_BRACKET.contains(&c))
-            ),
-            preceded(
-                take_ws_or_comment,
-                tag(CLOSE_BRACKET)
-            )
-        )
-    )(text)
-}
-
-fn take_root_node<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    preceded(
-        take_ws_or_comment,
-        // string
-        // int
-        // float
-        // node
-        delimited(
-            tag(OPEN_BRACKET),
-            preceded(
-                take_ws_or_comment,
-                take_while(|c| !CLOSE_BRACKET.contains(&c))
-            ),
-            preceded(
-                take_ws_or_comment,
-                tag(CLOSE_BRACKET)
-            )
-        )
-    )(text)
-}
+        self.remaining = &self.remaining[size..];
+    }
 
-fn parse_string<'a>(text: &'a [u8]) -> IResult<&'a [u8], DataArray> {
-    delimited(
-        tag(b"\""),
-        map(
-            take_while(|c: u8| c.ne(&b"\""[0])),
-            |s: &'a [u8]| DataArray::String(DataString::from_vec(s.to_owned()))
-        ),
-        tag(b"\"")
-    )(text)
+    /// Gets current line and column (both start at 1)
+    fn get_position(&self) -> (usize, usize) {
+        (self.line_number + 1, self.char_index - self.line_start + 1)
+    }
 }
 
 fn parse_symbol_name<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
@@ -288,8 +285,8 @@ fn parse_int<'a>(text: &'a [u8]) -> IResult<&'a [u8], DataArray> {
                 alt((tag("0x"), tag("0X"))),
                 all_consuming(hex_digit1),
             ),
-            |num: &'a [u8]| i32::from_str_radix(std::str::from_utf8(num).unwrap(), 16) // Shouldn't fail
-                .map(|n| DataArray::Integer(n))
+            |num: &'a [u8]| u32::from_str_radix(std::str::from_utf8(num).unwrap(), 16) // Fails if larger than 32 bits
+                .map(|n| DataArray::Integer(n as i32)) // Values above i32::MAX wrap (e.g. 0xFFFFFFFF = -1)
         ),
         // Base 10
         map_res(
@@ -306,6 +303,14 @@ fn parse_int<'a>(text: &'a [u8]) -> IResult<&'a [u8], DataArray> {
     ))(text)
 }
 
+/// Checks if token is hex number (used to reject values larger than 32 bits instead of parsing as symbol)
+fn is_hex_token(token: &[u8]) -> bool {
+    match token {
+        [b'0', b'x' | b'X', digits @ ..] => !digits.is_empty() && digits.iter().all(|c| c.is_ascii_hexdigit()),
+        _ => false
+    }
+}
+
 fn parse_float<'a>(text: &'a [u8]) -> IResult<&'a [u8], DataArray> {
     map_res(
         all_consuming(recognize_float),
@@ -330,13 +335,12 @@ fn parse_var_name<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
 }
 
 fn parse_variable<'a>(text: &'a [u8]) -> IResult<&'a [u8], DataArray> {
+    // Name is stored without $ (same as dtb)
     map(
         all_consuming(
-            recognize(
-                pair(
-                    tag("$"),
-                    parse_var_name
-                )
+            preceded(
+                tag("$"),
+                parse_var_name
             )
         ),
         |data: &'a [u8]| DataArray::Variable(DataString::from_vec(data.to_vec()))
@@ -350,141 +354,25 @@ fn parse_kdata_unhandled<'a>(text: &'a [u8]) -> IResult<&'a [u8], DataArray> {
     )(text)
 }
 
-/*fn map_int(text: &[u8]) -> Result<DataArray, ParseIntError> {
-    let num = match text {
-        // Base 16
-        [b'0', b'X' | b'x', hd @ ..] if hd.iter().all(|c| is_hex_digit(*c))
-            => i32::from_str_radix(std::str::from_utf8(hd).unwrap(), 16),
-        // Base 10
-        d if d.iter().enumerate().all(|(i, c)| is_digit(*c) || i == 0 && c.eq(&b'-'))
-            => std::str::from_utf8(d).unwrap().parse::<i32>(),
-        _ => Err(ParseIntError {
-            kind: IntErrorKind::InvalidDigit
-        })
-    };
-    
-    num.map(|n| DataArray::Integer(n))
-}*/
-
-fn take_until_ws_comment_array1<'a>(text: &'a [u8]) -> IResult<&'a [u8], &'a [u8]> {
-    take_till1(
-        |c: u8| WS_CHARACTERS.contains(&c)
-            || b"()[]{};".contains(&c)
-    )(text)
-}
-
-fn parse_node<'a>(text: &'a [u8]) -> IResult<&'a [u8], DataArray> {
-    preceded(
-        take_ws_or_comment,
-        alt((
-            map_parser(
-                take_until_ws_comment_array1,
-                alt((
-                    // Specific keywords...
-                    parse_kdata_unhandled,
-
-                    // Int
-                    parse_int,
-                    // Float
-                    parse_float,
-                    // Variable
-                    parse_variable,
-                    // Symbol
-                    parse_symbol,
-                ))
-            ),
-            // String
-            parse_string,
-            // Array
-            delimited(
-                tag(OPEN_BRACKET),
-                map(
-                    parse_data_array,
-                    |items: Vec<DataArray>| DataArray::Array(items)
-                ),
-                preceded(
-                    take_ws_or_comment,
-                    tag(CLOSE_BRACKET)
-                )
-            ),
-            // Property
-            delimited(
-                tag("["),
-                map(
-                    parse_data_array,
-                    |items: Vec<DataArray>| DataArray::Property(items)
-                ),
-                preceded(
-                    take_ws_or_comment,
-                    tag("]")
-                )
-            ),
-            // Command
-            delimited(
-                tag("{"),
-                map(
-                    parse_data_array,
-                    |items: Vec<DataArray>| DataArray::Command(items)
-                ),
-                preceded(
-                    take_ws_or_comment,
-                    tag("}")
-                )
-            )
-        ))
-    )(text)
-}
-
-fn parse_data_array<'a>(text: &'a [u8]) -> IResult<&'a [u8], Vec<DataArray>> {
-    many0(
-        parse_node
-    )(text)
-
-    /*preceded(
-        take_ws_or_comment,
-        // string
-        // int
-        // float
-        // node
-        delimited(
-            tag(OPEN_BRACKET),
-            preceded(
-                take_ws_or_comment,
-
-                take_while(|c| !CLOSE_BRACKET.contains(&c))
-            ),
-            preceded(
-                take_ws_or_comment,
-                tag(CLOSE_BRACKET)
-            )
-        )
-    )(text)*/
+/// Parses single token as value node
+fn parse_value(text: &[u8]) -> IResult<&[u8], DataArray> {
+    alt((
+        // Specific keywords...
+        parse_kdata_unhandled,
+
+        // Int
+        parse_int,
+        // Float
+        parse_float,
+        // Variable
+        parse_variable,
+        // Symbol
+        parse_symbol,
+    ))(text)
 }
 
-pub fn parse_dta<'a>(dta: &'a[u8]) -> Result<Vec<ParsedSong>, ParseDTAError> {
-    let (r1, r2) = take_root_node(dta)
-        .map_err(|_| ParseDTAError::UnknownDTAParseError)?;
-
-    let r1_str = std::str::from_utf8(r1).unwrap();
-    let r2_str = std::str::from_utf8(r2).unwrap();
-
-    println!("r1: {r1_str}");
-    println!("r2: {r2_str}");
-
-    let (_, items) = parse_data_array(dta).unwrap();
-
-    println!("\n\n{}", String::from_utf8(dta.to_owned()).unwrap());
-    println!("{items:#?}");
-
-    //let result = take_section(dta).map_err(|_| ParseDTAError::UnknownDTAParseError)?;
-
-    /*let result = map(
-        many0(take_section),
-        |songs| songs
-    )(dta)
-        .map_err(|_| ParseDTAError::UnknownDTAParseError)?;*/
-
-    Ok(Vec::new())
+pub fn parse_dta(dta: &[u8]) -> Result<RootData, ParseDTAError> {
+    DTAParser::new(dta).parse()
 }
 
 #[cfg(test)]
@@ -492,27 +380,6 @@ mod tests {
     use rstest::*;
     use super::*;
 
-    //const DTA_PATH: Option<&str> = option_env!("PIKAXE_TEST_DTA_PATH");
-
-    /*#[rstest]
-    #[case(b";whatever \n (wildhoneypie)\n(temporarysecretary\n   (name \"Temporary Secretary\")\n)", 2)] // b"(wildhoneypie)\n(temporarysecretary\n   (name \"Temporary Secretary\")\n)")
-    fn parse_dta_test<const N: usize>(#[case] dta: &[u8; N], #[case] expected_count: usize) {
-        //let dta_path = DTA_PATH;
-        /*match DTA_PATH {
-            Some(dta_path) => println!("DTA path is \"{dta_path}\""),
-            None => println!("DTA path not found!"),
-        };*/
-
-        //let dta_str = std::str::from_utf8(dta).unwrap();
-
-        let result = parse_dta(dta);
-        assert!(result.is_ok());
-
-        if let Ok(songs) = result {
-            assert_eq!(expected_count, songs.len());
-        }
-    }*/
-
     #[rstest]
     #[case(b"", None)]
     #[case(b"   ", None)]
@@ -523,38 +390,103 @@ mod tests {
     #[case(b"-100", Some(DataArray::Integer(-100)))]
     #[case(b"0xff", Some(DataArray::Integer(0xFF)))]
     #[case(b"0xFF", Some(DataArray::Integer(0xFF)))]
+    #[case(b"0xFFFFFFFF", Some(DataArray::Integer(-1)))]
+    #[case(b"0x100000000", None)]
     #[case(b"\"Test\"", Some(DataArray::String(DataString::from_string("Test"))))]
     #[case(b"\'Test\'", Some(DataArray::Symbol(DataString::from_string("Test"))))]
+    #[case(b"\'Test symbol\'", Some(DataArray::Symbol(DataString::from_string("Test symbol"))))]
     #[case(b"Test", Some(DataArray::Symbol(DataString::from_string("Test"))))]
-    #[case(b"$test", Some(DataArray::Variable(DataString::from_string("$test"))))]
-    #[case(b"$test_song", Some(DataArray::Variable(DataString::from_string("$test_song"))))]
-    #[case(b"$p9director", Some(DataArray::Variable(DataString::from_string("$p9director"))))]
-    #[case(b"$p9director_1985", Some(DataArray::Variable(DataString::from_string("$p9director_1985"))))]
+    #[case(b"==", Some(DataArray::Symbol(DataString::from_string("=="))))]
+    #[case(b"$test", Some(DataArray::Variable(DataString::from_string("test"))))]
+    #[case(b"$test_song", Some(DataArray::Variable(DataString::from_string("test_song"))))]
+    #[case(b"$p9director", Some(DataArray::Variable(DataString::from_string("p9director"))))]
+    #[case(b"$p9director_1985", Some(DataArray::Variable(DataString::from_string("p9director_1985"))))]
     #[case(b"$", None)]
     #[case(b"$0", None)]
     #[case(b"$01234", None)]
     #[case(b"$0abc", None)]
     #[case(b"kDataUnhandled", Some(DataArray::KDataUnhandled))]
+    #[case(b"#define kSongs", Some(DataArray::Define(DataString::from_string("kSongs"))))]
+    #[case(b"#include ../config/macros.dta", Some(DataArray::Include(DataString::from_string("../config/macros.dta"))))]
+    #[case(b"#merge songs.dta", Some(DataArray::Merge(DataString::from_string("songs.dta"))))]
+    #[case(b"#ifdef HX_XBOX", Some(DataArray::IfDef(DataString::from_string("HX_XBOX"))))]
+    #[case(b"#ifndef _SHIP", Some(DataArray::IfNDef(DataString::from_string("_SHIP"))))]
+    #[case(b"#else", Some(DataArray::Else))]
+    #[case(b"#endif", Some(DataArray::EndIf))]
+    #[case(b"#autorun", Some(DataArray::Autorun))]
+    #[case(b"#undef kSongs", Some(DataArray::Undef(DataString::from_string("kSongs"))))]
     fn parse_node_test<const N: usize>(#[case] data: &[u8; N], #[case] expected: Option<DataArray>) {
-        let result = parse_node(data)
-            .map(|(_, arr)| arr)
-            .ok();
-
-        // TODO: Verify expected exception too?
+        let result = parse_dta(data)
+            .ok()
+            .and_then(|root| root.data.into_iter().next());
 
         assert_eq!(expected, result);
     }
 
     #[rstest]
     #[case(b"(year 2002)", DataArray::Array(vec![DataArray::Symbol(DataString::from_string("year")), DataArray::Integer(2002) ]))]
+    #[case(b"{set $x 5}", DataArray::Command(vec![DataArray::Symbol(DataString::from_string("set")), DataArray::Variable(DataString::from_string("x")), DataArray::Integer(5) ]))]
+    #[case(b"[volume]", DataArray::Property(vec![DataArray::Symbol(DataString::from_string("volume")) ]))]
+    #[case(b"(name \"Doctor Worm\"; Comment\n)", DataArray::Array(vec![DataArray::Symbol(DataString::from_string("name")), DataArray::String(DataString::from_string("Doctor Worm")) ]))]
     fn parse_node_array_test<const N: usize>(#[case] data: &[u8; N], #[case] expected: DataArray) {
-        let result = parse_node(data)
-            .map(|(_, arr)| arr)
+        let result = parse_dta(data)
+            .map(|mut root| root.data.remove(0))
             .unwrap();
 
         assert_eq!(expected, result);
     }
 
+    #[rstest]
+    fn parse_dta_test() {
+        let dta = b"; Songs\n#define kVersion (3)\n(temporarysecretary\n   (name \"Temporary\nSecretary\")\n   /* Block\n comment */\n   (song {if_else $ship [volume] kDataUnhandled})\n)\n";
+        let root = parse_dta(dta).unwrap();
+
+        assert_eq!(
+            vec![
+                DataArray::Define(DataString::from_string("kVersion")),
+                DataArray::Array(vec![DataArray::Integer(3)]),
+                DataArray::Array(vec![
+                    DataArray::Symbol(DataString::from_string("temporarysecretary")),
+                    DataArray::Array(vec![
+                        DataArray::Symbol(DataString::from_string("name")),
+                        DataArray::String(DataString::from_string("Temporary\nSecretary")),
+                    ]),
+                    DataArray::Array(vec![
+                        DataArray::Symbol(DataString::from_string("song")),
+                        DataArray::Command(vec![
+                            DataArray::Symbol(DataString::from_string("if_else")),
+                            DataArray::Variable(DataString::from_string("ship")),
+                            DataArray::Property(vec![DataArray::Symbol(DataString::from_string("volume"))]),
+                            DataArray::KDataUnhandled,
+                        ]),
+                    ]),
+                ]),
+            ],
+            root.data
+        );
+
+        // Root + arrays in order they start
+        assert_eq!(vec![1, 2, 3, 4, 8, 8, 8], root.line_numbers);
+    }
+
+    #[rstest]
+    #[case(b"(song\n   (name \"Test\")", (1, 1), "Unclosed '(' starting at line 1, column 1")]
+    #[case(b"(song\n   (name \"Test\"]\n)", (2, 16), "Unexpected ']' at line 2, column 16")]
+    #[case(b"(song)\n)", (2, 1), "Unexpected ')' at line 2, column 1")]
+    #[case(b"(name\n  \"Test)", (2, 3), "Unclosed string starting at line 2, column 3")]
+    #[case(b"('test)", (1, 2), "Unclosed quoted symbol starting at line 1, column 2")]
+    #[case(b"(test) /* ", (1, 8), "Unclosed comment starting at line 1, column 8")]
+    #[case(b"\n\n  (value $0)", (3, 10), "Invalid token \"$0\" at line 3, column 10")]
+    #[case(b"(color 0x1FFFFFFFF)", (1, 8), "Invalid token \"0x1FFFFFFFF\" at line 1, column 8")]
+    #[case(b"#pragma once", (1, 1), "Unknown directive \"#pragma\" at line 1, column 1")]
+    #[case(b"(#define)", (1, 2), "Missing name for \"#define\" at line 1, column 2")]
+    fn parse_dta_error_test<const N: usize>(#[case] data: &[u8; N], #[case] expected_position: (usize, usize), #[case] expected_message: &str) {
+        let error = parse_dta(data).unwrap_err();
+
+        assert_eq!(expected_position, error.get_position());
+        assert_eq!(expected_message, error.to_string());
+    }
+
     #[rstest]
     #[case(b"", None)]
     #[case(b"0", Some(0))]
@@ -568,6 +500,10 @@ mod tests {
     #[case(b"0x1234", Some(0x1234))]
     #[case(b"0xAB00", Some(0xAB00))]
     #[case(b"0xGG", None)]
+    #[case(b"0x7FFFFFFF", Some(i32::MAX))]
+    #[case(b"0x80000000", Some(i32::MIN))]
+    #[case(b"0xFFFFFFFF", Some(-1))]
+    #[case(b"0x100000000", None)]
     fn parse_int_test<const N: usize>(#[case] data: &[u8; N], #[case] expected: Option<i32>) {
         let result = parse_int(data)
             .map(|(_, arr)| arr)
@@ -0,0 +1,343 @@
+use crate::convert::ConvertError;
+use crate::io::{MiloBlockStructureError, MiloPackError, MiloUnpackError, StreamError, VfsError};
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use thiserror::Error as ThisError;
+
+/// Location of error in archive/scene. Missing values are filled by outer contexts.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ErrorContext {
+    pub archive_path: Option<PathBuf>,
+    pub entry_name: Option<String>,
+    pub entry_type: Option<String>,
+    /// Version of object (or directory for archive errors)
+    pub version: Option<u32>,
+    /// Offset in stream (relative to start of entry data for entry errors)
+    pub offset: Option<u64>,
+}
+
+impl ErrorContext {
+    pub fn from_entry(name: &str, object_type: &str) -> ErrorContext {
+        ErrorContext {
+            entry_name: Some(name.to_owned()),
+            entry_type: Some(object_type.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    /// Fills missing values from other context
+    fn merge(&mut self, other: ErrorContext) {
+        self.archive_path = self.archive_path.take().or(other.archive_path);
+        self.entry_name = self.entry_name.take().or(other.entry_name);
+        self.entry_type = self.entry_type.take().or(other.entry_type);
+        self.version = self.version.or(other.version);
+        self.offset = self.offset.or(other.offset);
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+
+        if let Some(path) = self.archive_path.as_ref() {
+            parts.push(format!("archive \"{}\"", path.display()));
+        }
+
+        match (self.entry_name.as_ref(), self.entry_type.as_ref()) {
+            (Some(name), Some(object_type)) => parts.push(format!("entry \"{name}\" ({object_type})")),
+            (Some(name), None) => parts.push(format!("entry \"{name}\"")),
+            (None, Some(object_type)) => parts.push(format!("entry of type {object_type}")),
+            _ => {}
+        }
+
+        if let Some(version) = self.version {
+            parts.push(format!("version {version}"));
+        }
+
+        if let Some(offset) = self.offset {
+            parts.push(format!("offset 0x{offset:X}"));
+        }
+
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error(transparent)]
+    Stream(#[from] StreamError),
+    #[error(transparent)]
+    MiloBlockStructure(#[from] MiloBlockStructureError),
+    #[error(transparent)]
+    MiloUnpack(#[from] MiloUnpackError),
+    #[error(transparent)]
+    MiloPack(#[from] MiloPackError),
+    #[error(transparent)]
+    IO(#[from] std::io::Error),
+    #[error(transparent)]
+    Vfs(#[from] VfsError),
+    #[error(transparent)]
+    Convert(#[from] ConvertError),
+    #[error("Objects of type \"{object_type}\" aren't supported")]
+    UnsupportedObjectType {
+        object_type: String
+    },
+    #[error("Loading {object_type} isn't supported: {reason}")]
+    UnsupportedLoad {
+        object_type: String,
+        reason: &'static str
+    },
+    #[error("Saving {object_type} isn't supported: {reason}")]
+    UnsupportedSave {
+        object_type: String,
+        reason: &'static str
+    },
+    /// Error from object loader or other part of library
+    #[error("{0}")]
+    Other(Box<dyn std::error::Error + Send + Sync>),
+    #[error("{source} ({})", format_context(.context, .source))]
+    WithContext {
+        context: ErrorContext,
+        source: Box<Error>
+    },
+}
+
+impl Error {
+    /// Adds location to error. Values already set (from inner, more specific contexts) are kept
+    /// and offset of stream error is always used over given offset.
+    pub fn with_context(self, context: ErrorContext) -> Error {
+        match self {
+            Error::WithContext { context: mut inner_context, source } => {
+                inner_context.merge(context);
+
+                Error::WithContext {
+                    context: inner_context,
+                    source
+                }
+            },
+            error => {
+                let mut context = context;
+
+                if let Error::Stream(stream_error) = &error {
+                    context.offset = Some(stream_error.get_offset());
+                }
+
+                Error::WithContext {
+                    context,
+                    source: Box::new(error)
+                }
+            }
+        }
+    }
+
+    pub fn get_context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::WithContext { context, .. } => Some(context),
+            _ => None
+        }
+    }
+
+    /// Gets error without context
+    pub fn get_inner(&self) -> &Error {
+        match self {
+            Error::WithContext { source, .. } => source.get_inner(),
+            error => error
+        }
+    }
+
+    /// Gets offset from context or stream error
+    pub fn get_offset(&self) -> Option<u64> {
+        match self.get_inner() {
+            Error::Stream(stream_error) => Some(stream_error.get_offset()),
+            _ => self.get_context().and_then(|c| c.offset)
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for Error {
+    fn from(error: Box<dyn std::error::Error>) -> Error {
+        // Recovers known errors so they can be matched on
+        let error = match error.downcast::<Error>() {
+            Ok(error) => return *error,
+            Err(error) => error
+        };
+
+        let error = match error.downcast::<StreamError>() {
+            Ok(error) => return Error::Stream(*error),
+            Err(error) => error
+        };
+
+        let error = match error.downcast::<MiloBlockStructureError>() {
+            Ok(error) => return Error::MiloBlockStructure(*error),
+            Err(error) => error
+        };
+
+        let error = match error.downcast::<MiloUnpackError>() {
+            Ok(error) => return Error::MiloUnpack(*error),
+            Err(error) => error
+        };
+
+        let error = match error.downcast::<MiloPackError>() {
+            Ok(error) => return Error::MiloPack(*error),
+            Err(error) => error
+        };
+
+        match error.downcast::<std::io::Error>() {
+            Ok(error) => Error::IO(*error),
+            Err(error) => Error::Other(error.to_string().into())
+        }
+    }
+}
+
+/// Formats context without offset if already part of error message
+fn format_context(context: &ErrorContext, error: &Error) -> String {
+    match error.get_inner() {
+        Error::Stream(_) => ErrorContext {
+            offset: None,
+            ..context.to_owned()
+        }.to_string(),
+        _ => context.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+    use crate::{Platform, SystemInfo};
+    use crate::io::{BinaryStream, IOEndian, MemoryStream};
+    use crate::scene::{CamObject, CharBone, Object, ObjectDir, ObjectDirBase, ObjectReadWrite, PackedObject};
+
+    #[rstest]
+    fn error_from_binary_stream_has_offset() {
+        let data = [0u8; 6];
+        let mut stream = MemoryStream::from_slice_as_read(&data);
+        let mut reader = BinaryStream::from_stream(&mut stream);
+
+        reader.read_uint32().unwrap();
+        let error = Error::from(reader.read_uint32().unwrap_err());
+
+        assert!(matches!(error, Error::Stream(StreamError::Read { offset: 4, length: 4, .. })));
+        assert_eq!(Some(4), error.get_offset());
+    }
+
+    #[rstest]
+    fn error_with_nested_context() {
+        let error = Error::from(StreamError::InvalidString { offset: 0x20 })
+            .with_context(ErrorContext {
+                version: Some(28),
+                offset: Some(0x40),
+                ..ErrorContext::from_entry("floor.mat", "Mat")
+            })
+            .with_context(ErrorContext {
+                archive_path: Some(PathBuf::from("venue.milo_xbox")),
+                entry_name: Some(String::from("venue")),
+                ..Default::default()
+            });
+
+        assert_eq!(
+            Some(&ErrorContext {
+                archive_path: Some(PathBuf::from("venue.milo_xbox")),
+                entry_name: Some(String::from("floor.mat")),
+                entry_type: Some(String::from("Mat")),
+                version: Some(28),
+                offset: Some(0x20),
+            }),
+            error.get_context()
+        );
+        assert!(matches!(error.get_inner(), Error::Stream(StreamError::InvalidString { .. })));
+        assert_eq!(
+            "Invalid UTF-8 string at offset 0x20 (archive \"venue.milo_xbox\", entry \"floor.mat\" (Mat), version 28)",
+            error.to_string()
+        );
+    }
+
+    #[rstest]
+    fn unpack_entries_reports_errors() {
+        let info = SystemInfo {
+            version: 25,
+            platform: Platform::X360,
+            endian: IOEndian::Big,
+        };
+
+        let mut obj_dir = ObjectDir::ObjectDir(ObjectDirBase {
+            entries: vec![
+                Object::Packed(PackedObject {
+                    name: String::from("main.cam"),
+                    object_type: String::from("Cam"),
+                    data: vec![0, 0, 0, 12, 0, 0] // Cut off after version
+                }),
+                Object::Packed(PackedObject {
+                    name: String::from("crowd.fx"),
+                    object_type: String::from("UnknownFx"),
+                    data: Vec::new()
+                }),
+            ],
+            ..ObjectDirBase::new()
+        });
+
+        let errors = obj_dir.try_unpack_entries(&info);
+
+        assert_eq!(2, errors.len());
+        assert_eq!(
+            Some(&ErrorContext {
+                version: Some(12),
+                offset: Some(4),
+                ..ErrorContext::from_entry("main.cam", "Cam")
+            }),
+            errors[0].get_context()
+        );
+        assert!(matches!(errors[0].get_inner(), Error::Stream(StreamError::Read { offset: 4, .. })));
+        assert!(matches!(errors[1].get_inner(), Error::UnsupportedObjectType { object_type } if object_type == "UnknownFx"));
+        assert!(obj_dir.get_entries().iter().all(|e| e.is_packed()));
+    }
+
+    #[rstest]
+    fn load_object_error_has_context() {
+        let info = SystemInfo {
+            version: 25,
+            platform: Platform::X360,
+            endian: IOEndian::Big,
+        };
+
+        let data = [0u8, 0, 0, 0xFF];
+        let mut stream = MemoryStream::from_slice_as_read(&data);
+
+        let mut cam = CamObject::default();
+        let error = Error::from(cam.load(&mut stream, &info).unwrap_err());
+
+        assert_eq!(
+            Some(&ErrorContext {
+                version: Some(0xFF),
+                offset: Some(4),
+                ..Default::default()
+            }),
+            error.get_context()
+        );
+        assert_eq!("Cam version 255 is not supported (version 255, offset 0x4)", error.to_string());
+    }
+
+    #[rstest]
+    fn load_char_bone_returns_unsupported_error() {
+        let info = SystemInfo {
+            version: 24,
+            platform: Platform::X360,
+            endian: IOEndian::Big,
+        };
+
+        let mut data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        CharBone::default().save(&mut stream, &info).unwrap();
+
+        let mut stream = MemoryStream::from_slice_as_read(&data);
+        let error = Error::from(CharBone::default().load(&mut stream, &info).unwrap_err());
+
+        assert!(matches!(error.get_inner(), Error::UnsupportedLoad { object_type, .. } if object_type == "CharBone"));
+    }
+
+    #[rstest]
+    fn error_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Error>();
+    }
+}
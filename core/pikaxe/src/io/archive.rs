@@ -1,18 +1,23 @@
-use crate::SystemInfo;
+use crate::{ErrorContext, SystemInfo};
+use crate::convert::can_copy_data;
 use crate::io::compression::*;
-use crate::io::stream::{BinaryStream, IOEndian, MemoryStream, SeekFrom, Stream};
-use crate::scene::{Object, ObjectDir, ObjectDirBase, PackedObject};
+use crate::io::stream::{BinaryStream, FileStream, IOEndian, MemoryStream, SeekFrom, Stream, StreamError};
+use crate::scene::{get_object_data_size, load_object_dir, Object, ObjectDir, ObjectDirBase, ObjectDirEntry, ObjectDirObject, PackedObject};
+use flate2::Crc;
+use rayon::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::error::Error;
-
-
+use std::path::{Path, PathBuf};
 use thiserror::Error as ThisError;
 
 const MAX_BLOCK_SIZE: usize = 0x20000;
 const ADDE_PADDING: [u8; 4] = [0xAD, 0xDE, 0xAD, 0xDE];
-const GZIP_MAGIC: u32 = u32::from_le_bytes([0x1F, 0x8B, 0x08, 0x08]);
+const GZIP_MAGIC: u32 = u32::from_le_bytes([0x1F, 0x8B, 0x08, 0x00]); // Last byte is flags
+const MILO_VERSION_RANGE: std::ops::RangeInclusive<u32> = 6..=32;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum BlockType
 {
     TypeA, // Block structure, no compression
@@ -26,6 +31,64 @@ pub struct BlockInfo {
     block_type: BlockType,
     start_offset: u32,
     block_sizes: Vec<usize>,
+    layout: Option<BlockLayout>,
+}
+
+/// Blocks as originally stored. Blocks with unchanged data are written back as is.
+#[derive(Clone, Debug, Default)]
+struct BlockLayout {
+    max_inflate_size: u32,
+    /// Data between block sizes and first block
+    header_gap: Vec<u8>,
+    blocks: Vec<StoredBlock>,
+    compression_level: Option<u32>,
+    compression_checked: bool,
+}
+
+#[derive(Clone, Debug)]
+struct StoredBlock {
+    size_value: u32, // Includes flags
+    inflated_size: usize,
+    inflated_checksum: u32,
+    data: Vec<u8>,
+}
+
+/// Block read from archive that isn't inflated yet
+#[derive(Debug)]
+struct PendingBlock {
+    size_value: u32, // Includes flags
+    data: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct PendingBlocks {
+    max_inflate_size: usize,
+    blocks: VecDeque<PendingBlock>,
+}
+
+impl BlockLayout {
+    fn add_block(&mut self, block_type: BlockType, block: PendingBlock, inflated_data: &[u8]) {
+        if !self.compression_checked && !inflated_data.is_empty() {
+            // Find zlib level that reproduces first compressed block
+            let compressed_data = match (block_type, (block.size_value & 0xFF000000) == 0) {
+                (BlockType::TypeB, _) => Some(&block.data[..]),
+                (BlockType::TypeD, true) => block.data.get(4..),
+                _ => None
+            };
+
+            if let Some(compressed_data) = compressed_data {
+                self.compression_level = detect_compression_level(compressed_data, inflated_data);
+                self.compression_checked = true;
+            }
+        }
+
+        self.blocks.push(StoredBlock {
+            size_value: block.size_value,
+            inflated_size: inflated_data.len(),
+            inflated_checksum: get_checksum(inflated_data),
+            data: block.data
+        });
+    }
 }
 
 impl BlockInfo {
@@ -33,9 +96,29 @@ impl BlockInfo {
         BlockInfo {
             block_type: BlockType::TypeB,
             start_offset: 2064,
-            block_sizes: Vec::new()
+            block_sizes: Vec::new(),
+            layout: None,
         }
     }
+
+    pub fn get_block_type(&self) -> BlockType {
+        self.block_type
+    }
+
+    pub fn get_start_offset(&self) -> u32 {
+        self.start_offset
+    }
+
+    pub fn get_block_sizes(&self) -> &[usize] {
+        &self.block_sizes
+    }
+
+    /// Compression level of original blocks (only detected when preserving layout)
+    pub fn get_compression_level(&self) -> Option<u32> {
+        self.layout
+            .as_ref()
+            .and_then(|l| l.compression_level)
+    }
 }
 
 #[derive(Debug)]
@@ -45,10 +128,21 @@ pub enum MiloArchiveStructure {
     Uncompressed,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MiloStructureType {
+    #[default]
+    Blocked,
+    GZIP,
+    Uncompressed,
+}
+
 #[derive(Debug)]
 pub struct MiloArchive {
     structure: MiloArchiveStructure,
-    data: Vec<u8>
+    data: Vec<u8>,
+    pending_blocks: Option<PendingBlocks>,
+    /// Path archive was opened from (added to errors)
+    path: Option<PathBuf>,
 }
 
 #[derive(Debug, ThisError)]
@@ -57,8 +151,20 @@ pub enum MiloBlockStructureError {
     UnsupportedCompression {
         magic: u32
     },
-    #[error("UnknownIOError")] // TODO: Enclose IOError
-    IOError
+    #[error("Can't read block structure: {0}")]
+    IOError(#[source] StreamError),
+    #[error("Can't inflate block {index}")]
+    CantInflateBlock {
+        index: usize
+    },
+    #[error("Can't deflate block {index}")]
+    CantDeflateBlock {
+        index: usize
+    },
+    #[error("Archive has {count} blocks that aren't inflated yet")]
+    BlocksNotInflated {
+        count: usize
+    },
 }
 
 #[derive(Debug, ThisError)]
@@ -66,14 +172,99 @@ pub enum MiloUnpackError {
     #[error("Unsupported milo directory of version of {version}")]
     UnsupportedDirectoryVersion {
         version: u32
-    }
+    },
+    #[error("Expected padding for {expected} entries but found {found} (enable guessing entry sizes to try anyways)")]
+    EntryCountMismatch {
+        expected: usize,
+        found: usize
+    },
+    #[error("Can't find end of entry \"{name}\"")]
+    EntryPaddingNotFound {
+        name: String
+    },
+    #[error("Entry \"{name}\" not found")]
+    EntryNotFound {
+        name: String
+    },
+    #[error("Not enough inflated data to read directory")]
+    MissingData,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MiloUnpackSettings {
+    /// Guesses sizes of entries with unsupported types by only splitting at ADDE padding followed by
+    /// small magic, and allows data after last entry of root directory (can give false positives inside
+    /// Tex pixel data). Also searches for inline sub directories when directory entry can't be parsed.
+    pub guess_entry_sizes: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MiloReadSettings {
+    /// Records original block layout + compressed data so unchanged archive can be written back
+    /// byte-for-byte (uses more memory)
+    pub preserve_layout: bool,
+    /// Only inflates first block on load. Remaining blocks are inflated on demand when reading
+    /// entries or all at once with `inflate_all()`.
+    pub lazy_blocks: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MiloPackSettings<'a> {
+    pub structure_type: MiloStructureType,
+    /// Block type of blocked archive (TypeB by default)
+    pub block_type: Option<BlockType>,
+    /// Archive to preserve layout of. Keeps entry order + string table values of directory and
+    /// re-uses structure, block boundaries and stored blocks of archive (structure + block type are ignored).
+    pub original: Option<&'a MiloArchive>,
+}
+
+/// Directory fields stored before directory entry
+struct DirectoryHeader {
+    version: u32,
+    dir_type: String,
+    name: String,
+    string_table_count: u32,
+    string_table_size: u32,
+    unknown_bool: bool,
+    entries: Vec<PackedObject>,
+}
+
+#[derive(Debug, ThisError)]
+pub enum MiloPackError {
+    #[error("Directory entry is required for milo versions above 10")]
+    MissingDirectoryEntry
 }
 
 impl MiloArchive {
-    pub fn from_stream<T: Stream>(stream: &mut T) -> Result<MiloArchive, Box<dyn Error>> {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<MiloArchive, crate::Error> {
+        MiloArchive::from_path_with_settings(path, &MiloReadSettings::default())
+    }
+
+    pub fn from_path_with_settings<T: AsRef<Path>>(path: T, settings: &MiloReadSettings) -> Result<MiloArchive, crate::Error> {
+        let path = path.as_ref();
+        let context = || ErrorContext {
+            archive_path: Some(path.to_owned()),
+            ..Default::default()
+        };
+
+        let mut stream = FileStream::from_path_as_read_open(path)
+            .map_err(|e| crate::Error::from(e).with_context(context()))?;
+
+        let mut milo = MiloArchive::from_stream_with_settings(&mut stream, settings)
+            .map_err(|e| e.with_context(context()))?;
+
+        milo.path = Some(path.to_owned());
+        Ok(milo)
+    }
+
+    pub fn from_stream<T: Stream>(stream: &mut T) -> Result<MiloArchive, crate::Error> {
+        MiloArchive::from_stream_with_settings(stream, &MiloReadSettings::default())
+    }
+
+    pub fn from_stream_with_settings<T: Stream>(stream: &mut T, settings: &MiloReadSettings) -> Result<MiloArchive, crate::Error> {
         let mut reader = BinaryStream::from_stream(stream); // Should always be little endian
         
-        let mut structure: MiloArchiveStructure = MiloArchiveStructure::Uncompressed; // TODO: Handle in else case
+        let mut structure: MiloArchiveStructure = MiloArchiveStructure::Uncompressed;
         let mut uncompressed: Vec<u8> = Vec::new();
 
         let block_result = MiloArchive::get_block_type_or_none(&mut reader);
@@ -81,13 +272,17 @@ impl MiloArchive {
         if let Err(MiloBlockStructureError::UnsupportedCompression { magic }) = block_result {
             reader.seek(SeekFrom::Current(-4))?;
 
-            if magic == GZIP_MAGIC {
-                let mut data = vec![0u8; reader.len()?];
-                reader.read_bytes_into_slice(&mut data)?;
+            let mut data = vec![0u8; reader.len()? - reader.pos() as usize];
+            reader.read_bytes_into_slice(&mut data)?;
 
+            if (magic & 0xFFFFFF) == GZIP_MAGIC {
                 uncompressed = inflate_gzip_block_no_buffer(&data)?;
+                structure = MiloArchiveStructure::GZIP;
+            } else if MILO_VERSION_RANGE.contains(&magic) || MILO_VERSION_RANGE.contains(&magic.swap_bytes()) {
+                // Raw directory starting with version
+                uncompressed = data;
             } else {
-                return Err(Box::new(block_result.unwrap_err()));
+                return Err(block_result.unwrap_err().into());
             }
         } else if let Ok(Some(block_type)) = block_result {
             let mut block_info = BlockInfo::new();
@@ -105,62 +300,309 @@ impl MiloArchive {
                 *size = reader.read_uint32()?;
             }
 
+            let mut layout = settings.preserve_layout.then(|| BlockLayout {
+                max_inflate_size: max_inflate_size as u32,
+                ..Default::default()
+            });
+
+            if let Some(layout) = layout.as_mut() {
+                let gap_size = (block_info.start_offset as u64).saturating_sub(reader.pos());
+                layout.header_gap = reader.read_bytes(gap_size as usize)?;
+            }
+
             // Advances to first block
             reader.seek(SeekFrom::Start(block_info.start_offset as u64))?;
 
-            // Create buffer
-            let mut buffer = vec![0u8; max_inflate_size as usize];
+            let mut blocks = VecDeque::with_capacity(block_sizes.len());
 
             for block_size in block_sizes.iter() {
-                let bytes = reader.read_bytes((*block_size & 0xFFFFFF) as usize)?;
+                blocks.push_back(PendingBlock {
+                    size_value: *block_size,
+                    data: reader.read_bytes((*block_size & 0xFFFFFF) as usize)?
+                });
+            }
 
-                let mut data = match (block_type, ((*block_size & 0xFF000000) == 0)) {
-                    (BlockType::TypeA, _)
-                        | (BlockType::TypeD, false) => bytes, // No compression
-                    (BlockType::TypeB, _) => inflate_zlib_block(&bytes, &mut buffer[..])?,
-                    (BlockType::TypeC, _) => inflate_gzip_block(&bytes, &mut buffer[..])?,
-                    (BlockType::TypeD, true) => inflate_zlib_block(&bytes[4..], &mut buffer[..])?, // Skip 4-byte inflated size prefix
-                };
+            block_info.layout = layout;
 
-                uncompressed.append(&mut data);
-                block_info.block_sizes.push(data.len());
-            }
+            let mut milo = MiloArchive {
+                structure: MiloArchiveStructure::Blocked(block_info),
+                data: Vec::new(),
+                pending_blocks: Some(PendingBlocks {
+                    max_inflate_size: max_inflate_size as usize,
+                    blocks
+                }),
+                path: None,
+            };
+
+            // First block is always inflated so version can be read
+            milo.inflate_next_blocks(if settings.lazy_blocks { 1 } else { usize::MAX })?;
+            return Ok(milo);
+        }
 
-            structure = MiloArchiveStructure::Blocked(block_info);
-        } // TODO: Handle else case (should currently return error if not blocked)
-        
         Ok(MiloArchive {
             structure,
-            data: uncompressed
+            data: uncompressed,
+            pending_blocks: None,
+            path: None,
         })
     }
 
+    /// Inflates next pending blocks in parallel
+    fn inflate_next_blocks(&mut self, count: usize) -> Result<(), MiloBlockStructureError> {
+        let (MiloArchiveStructure::Blocked(block_info), Some(pending)) = (&mut self.structure, &mut self.pending_blocks) else {
+            return Ok(());
+        };
+
+        let count = count.min(pending.blocks.len());
+        let blocks = pending.blocks
+            .drain(..count)
+            .collect::<Vec<_>>();
+
+        let block_type = block_info.block_type;
+        let first_index = block_info.block_sizes.len();
+
+        let inflated_blocks = blocks
+            .par_iter()
+            .enumerate()
+            .map_init(|| vec![0u8; pending.max_inflate_size], |buffer, (i, block)| {
+                let data = match (block_type, (block.size_value & 0xFF000000) == 0) {
+                    (BlockType::TypeA, _)
+                        | (BlockType::TypeD, false) => Ok(block.data.to_owned()), // No compression
+                    (BlockType::TypeB, _) => inflate_zlib_block(&block.data, buffer),
+                    (BlockType::TypeC, _) => inflate_gzip_block(&block.data, buffer),
+                    (BlockType::TypeD, true) => inflate_zlib_block(block.data.get(4..).unwrap_or_default(), buffer), // Skip 4-byte inflated size prefix
+                };
+
+                data.map_err(|_| MiloBlockStructureError::CantInflateBlock { index: first_index + i })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if pending.blocks.is_empty() {
+            self.pending_blocks = None;
+        }
+
+        for (block, mut data) in blocks.into_iter().zip(inflated_blocks) {
+            if let Some(layout) = block_info.layout.as_mut() {
+                layout.add_block(block_type, block, &data);
+            }
+
+            block_info.block_sizes.push(data.len());
+            self.data.append(&mut data);
+        }
+
+        Ok(())
+    }
+
+    /// Gets count of blocks not inflated yet (lazy mode)
+    pub fn get_pending_block_count(&self) -> usize {
+        self.pending_blocks
+            .as_ref()
+            .map(|p| p.blocks.len())
+            .unwrap_or_default()
+    }
+
+    pub fn inflate_all(&mut self) -> Result<(), crate::Error> {
+        self.inflate_next_blocks(usize::MAX)
+            .map_err(|e| crate::Error::from(e).with_context(self.get_error_context()))
+    }
+
+    fn check_inflated(&self) -> Result<(), MiloBlockStructureError> {
+        match self.get_pending_block_count() {
+            0 => Ok(()),
+            count => Err(MiloBlockStructureError::BlocksNotInflated { count })
+        }
+    }
+
+    /// Retries read, inflating blocks needed to reach end of failed read each time until it succeeds or no blocks are left.
+    /// Errors not caused by missing data are returned right away.
+    fn read_with_pending_blocks<T>(&mut self, read: impl Fn(&MiloArchive) -> Result<T, Box<dyn Error>>) -> Result<T, crate::Error> {
+        loop {
+            let error = match read(self) {
+                Ok(value) => return Ok(value),
+                Err(error) => crate::Error::from(error)
+            };
+
+            let block_count = match self.get_pending_block_count() {
+                0 => None,
+                _ => self.get_blocks_needed_for_error(&error)
+            };
+
+            let Some(block_count) = block_count else {
+                return Err(error.with_context(self.get_error_context()));
+            };
+
+            self.inflate_next_blocks(block_count)?;
+        }
+    }
+
+    /// Gets count of pending blocks to inflate before retrying read that failed with error
+    fn get_blocks_needed_for_error(&self, error: &crate::Error) -> Option<usize> {
+        let max_inflate_size = self.pending_blocks
+            .as_ref()
+            .map(|p| p.max_inflate_size.max(1))?;
+
+        match error.get_inner() {
+            crate::Error::Stream(StreamError::Read { offset, length, .. }) => {
+                // Read past end of inflated data, inflate blocks covering read range
+                let read_end = (*offset as usize).saturating_add(*length);
+                let missing_size = read_end.checked_sub(self.data.len()).filter(|s| *s > 0)?;
+
+                Some(missing_size.div_ceil(max_inflate_size))
+            },
+            crate::Error::MiloUnpack(MiloUnpackError::MissingData
+                | MiloUnpackError::EntryCountMismatch { .. }
+                | MiloUnpackError::EntryPaddingNotFound { .. }) => {
+                // End of entry is unknown, double inflated blocks
+                let MiloArchiveStructure::Blocked(block_info) = &self.structure else {
+                    return None;
+                };
+
+                Some(block_info.block_sizes.len().max(1))
+            },
+            _ => None
+        }
+    }
+
     fn get_block_type_or_none(reader: &mut BinaryStream) -> Result<Option<BlockType>, MiloBlockStructureError> {
+        let offset = reader.pos();
         let magic = reader.read_uint32()
-            .map_err(|_| MiloBlockStructureError::IOError)?;
+            .map_err(|e| MiloBlockStructureError::IOError(StreamError::from_read_error(e, offset, 4)))?;
 
         match magic {
             0xCABEDEAF => Ok(Some(BlockType::TypeA)),
             0xCBBEDEAF => Ok(Some(BlockType::TypeB)),
             0xCCBEDEAF => Ok(Some(BlockType::TypeC)),
             0xCDBEDEAF => Ok(Some(BlockType::TypeD)),
-            // TODO: Assume uncompressed archive, or gzip then check version
+            // Gzip or uncompressed archive
             _ => Err(MiloBlockStructureError::UnsupportedCompression { magic })
         }
     }
 
+    pub fn get_structure(&self) -> &MiloArchiveStructure {
+        &self.structure
+    }
+
+    pub fn get_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn get_error_context(&self) -> ErrorContext {
+        ErrorContext {
+            archive_path: self.path.to_owned(),
+            ..Default::default()
+        }
+    }
+
     pub fn get_stream<'a>(&'a self) -> Box<dyn Stream + 'a> {
         let stream = MemoryStream::from_slice_as_read(&self.data);
         Box::new(stream)
     }
 
-    pub fn unpack_directory(&self, info: &SystemInfo) -> Result<ObjectDir, Box<dyn Error>> {
+    pub fn unpack_directory(&self, info: &SystemInfo) -> Result<ObjectDir, crate::Error> {
+        self.unpack_directory_with_settings(info, &MiloUnpackSettings::default())
+    }
+
+    pub fn unpack_directory_with_settings(&self, info: &SystemInfo, settings: &MiloUnpackSettings) -> Result<ObjectDir, crate::Error> {
+        self.check_inflated()
+            .map_err(|e| crate::Error::from(e).with_context(self.get_error_context()))?;
+
         let mut stream = self.get_stream();
         let stream_size = stream.len().unwrap() as u64;
 
         let stream = stream.as_mut();
         let mut reader = BinaryStream::from_stream_with_endian(stream, info.endian);
 
+        let obj_dir = self.read_directory(&mut reader, info, settings, true)
+            .map_err(|e| crate::Error::from(e).with_context(ErrorContext {
+                version: Some(info.version),
+                offset: Some(reader.pos()),
+                ..self.get_error_context()
+            }))?;
+
+        if reader.pos() < stream_size {
+            log::warn!("Read less data than length of milo file. Likely not parsed correctly.");
+        }
+
+        Ok(obj_dir)
+    }
+
+    /// Reads types + names of root directory entries. Only inflates blocks needed to read directory header.
+    pub fn read_entry_names(&mut self, info: &SystemInfo) -> Result<Vec<(String, String)>, crate::Error> {
+        self.read_with_pending_blocks(|milo| {
+            let mut stream = milo.get_stream();
+            let mut reader = BinaryStream::from_stream_with_endian(stream.as_mut(), info.endian);
+
+            let header = MiloArchive::read_directory_header(&mut reader, info)?;
+
+            Ok(header.entries
+                .into_iter()
+                .map(|e| (e.object_type, e.name))
+                .collect())
+        })
+    }
+
+    /// Reads single entry of root directory. Only inflates blocks needed to reach end of entry.
+    /// Sizes are found the same way as for sub directories, so padding inside data of earlier entries
+    /// with unsupported types isn't detected (use `unpack_directory()` for that).
+    pub fn read_entry(&mut self, info: &SystemInfo, name: &str) -> Result<PackedObject, crate::Error> {
+        let (object_type, name) = self.read_entry_names(info)?
+            .into_iter()
+            .find(|(_, n)| n.eq(name))
+            .ok_or_else(|| crate::Error::from(MiloUnpackError::EntryNotFound { name: name.to_owned() })
+                .with_context(self.get_error_context()))?;
+
+        let data = self.read_with_pending_blocks(|milo| {
+            let mut stream = milo.get_stream();
+            let mut reader = BinaryStream::from_stream_with_endian(stream.as_mut(), info.endian);
+            let settings = MiloUnpackSettings::default();
+
+            let header = MiloArchive::read_directory_header(&mut reader, info)?;
+            let mut index = header.entries
+                .iter()
+                .position(|e| e.name.eq(&name))
+                .unwrap_or_default();
+
+            if header.version == 10 {
+                MiloArchive::read_external_paths(&mut reader)?;
+            } else if header.version > 10 {
+                let mut sub_dirs = Vec::new();
+                let (_, object_dir) = milo.read_directory_entry_start(&mut reader, &header.dir_type, info, &settings, &mut sub_dirs)?;
+
+                if object_dir.is_none() && milo.get_pending_block_count() > 0 {
+                    // Directory entry might be cut off
+                    return Err(Box::new(MiloUnpackError::MissingData));
+                }
+
+                // Rest of directory entry comes first
+                index += 1;
+            }
+
+            let entry_types = (header.version > 10)
+                .then_some(header.dir_type.as_str())
+                .into_iter()
+                .chain(header.entries.iter().map(|e| e.object_type.as_str()))
+                .take(index + 1)
+                .collect::<Vec<_>>();
+
+            let entry_sizes = milo.get_entry_sizes(&mut reader, &entry_types, info, &settings, false)?;
+            let entry_offset = entry_sizes[..index]
+                .iter()
+                .map(|s| s + ADDE_PADDING.len())
+                .sum::<usize>();
+
+            reader.seek(SeekFrom::Current(entry_offset as i64))?;
+            reader.read_bytes(entry_sizes[index])
+        }).map_err(|e| e.with_context(ErrorContext::from_entry(&name, &object_type)))?;
+
+        Ok(PackedObject {
+            name,
+            object_type,
+            data
+        })
+    }
+
+    /// Reads version, name, type and entry names of directory
+    fn read_directory_header(reader: &mut BinaryStream, info: &SystemInfo) -> Result<DirectoryHeader, Box<dyn Error>> {
         // Read and verify version
         let version = reader.read_uint32()?;
         if info.version != version {
@@ -169,16 +611,20 @@ impl MiloArchive {
 
         let mut dir_type;
         let dir_name;
+        let mut string_table_count = 0;
+        let mut string_table_size = 0;
+        let mut unknown_bool = false;
 
         if version >= 24 {
             // Read object dir name + type
             dir_type = reader.read_prefixed_string()?;
             dir_name = reader.read_prefixed_string()?;
 
-            reader.seek(SeekFrom::Current(8))?; // Skip extra nums
+            string_table_count = reader.read_uint32()?;
+            string_table_size = reader.read_uint32()?;
 
             if version >= 32 {
-                reader.seek(SeekFrom::Current(1))?; // Skip unknown bool
+                unknown_bool = reader.read_boolean()?;
             }
 
             // Update class name
@@ -226,42 +672,73 @@ impl MiloArchive {
             }
         }
 
-        if version == 10 {
-            // Read external paths
-            let ext_count = reader.read_int32()?;
+        Ok(DirectoryHeader {
+            version,
+            dir_type,
+            name: dir_name,
+            string_table_count,
+            string_table_size,
+            unknown_bool,
+            entries: packed_entries
+        })
+    }
 
-            // TODO: Collect into struct
-            for _ in 0..ext_count {
-                reader.read_prefixed_string()?;
-            }
+    fn read_directory(&self, reader: &mut BinaryStream, info: &SystemInfo, settings: &MiloUnpackSettings, is_root: bool) -> Result<ObjectDir, Box<dyn Error>> {
+        let DirectoryHeader {
+            version,
+            dir_type,
+            name: dir_name,
+            string_table_count,
+            string_table_size,
+            unknown_bool,
+            entries: mut packed_entries
+        } = MiloArchive::read_directory_header(reader, info)?;
+
+        let mut dir_entry = None;
+        let mut sub_dirs = Vec::new();
+        let mut external_paths = Vec::new();
+
+        if version == 10 {
+            external_paths = MiloArchive::read_external_paths(reader)?;
         } else if version > 10 {
-            // TODO: Parse directory info (entry)
-            /*let entry_size = self.guess_entry_size(&mut reader)?.unwrap();
-            reader.seek(SeekFrom::Current((entry_size + 4) as i64))?;*/
-
-            // Hacky way to read directory entry
-            // Only works if no sub dirs
-            packed_entries.insert(0, PackedObject {
-                name: dir_name.to_owned(),
-                object_type: dir_type.to_owned(),
-                data: Vec::new()
+            // Rest of directory entry is read with other entries
+            let (data, object_dir) = self.read_directory_entry_start(reader, &dir_type, info, settings, &mut sub_dirs)?;
+
+            dir_entry = Some(ObjectDirEntry {
+                sub_dirs_offset: data.len(),
+                data,
+                object_dir,
             });
         }
 
-        // Get data for entries
-        for entry_obj in packed_entries.iter_mut() {
-            if let Some(size) = self.guess_entry_size(&mut reader)? {
-                // Read data and skip padding
-                entry_obj.data = reader.read_bytes(size)?;
-                reader.seek(SeekFrom::Current(4))?;
-            } else {
-                // TODO: Else throw error?
-                break;
-            }
+        let entry_types = dir_entry
+            .as_ref()
+            .map(|_| dir_type.as_str())
+            .into_iter()
+            .chain(packed_entries.iter().map(|e| e.object_type.as_str()))
+            .collect::<Vec<_>>();
+
+        let entry_sizes = self.get_entry_sizes(reader, &entry_types, info, settings, is_root)?;
+        let mut entry_sizes = entry_sizes.into_iter();
+
+        if let Some(dir_entry) = dir_entry.as_mut() {
+            let Some(size) = entry_sizes.next() else {
+                return Err(Box::new(MiloUnpackError::EntryPaddingNotFound { name: dir_name }));
+            };
+
+            let mut data = reader.read_bytes(size)
+                .map_err(|e| crate::Error::from(e).with_context(ErrorContext::from_entry(&dir_name, &dir_type)))?;
+
+            dir_entry.data.append(&mut data);
+            reader.seek(SeekFrom::Current(4))?;
         }
 
-        if stream.pos() < stream_size {
-            log::warn!("Read less data than length of milo file. Likely not parsed correctly.");
+        // Get data for entries
+        for (entry_obj, size) in packed_entries.iter_mut().zip(entry_sizes) {
+            // Read data and skip padding
+            entry_obj.data = reader.read_bytes(size)
+                .map_err(|e| crate::Error::from(e).with_context(ErrorContext::from_entry(&entry_obj.name, &entry_obj.object_type)))?;
+            reader.seek(SeekFrom::Current(4))?;
         }
 
         Ok(ObjectDir::ObjectDir(ObjectDirBase {
@@ -271,185 +748,304 @@ impl MiloArchive {
                 .collect(),
             name: dir_name,
             dir_type,
-            sub_dirs: Vec::new()
+            string_table_count,
+            string_table_size,
+            unknown_bool,
+            external_paths,
+            dir_entry,
+            sub_dirs,
+            original_data: HashMap::new(),
+            original_info: None,
         }))
     }
 
-    fn guess_entry_size<'a>(&'a self, reader: &mut BinaryStream) -> Result<Option<usize>, Box<dyn Error>> {
-        let start_pos = reader.pos();
-        let stream_len = reader.len()?;
+    fn read_external_paths(reader: &mut BinaryStream) -> Result<Vec<String>, Box<dyn Error>> {
+        let ext_count = reader.read_int32()?;
+        let mut external_paths = Vec::new();
 
-        let mut magic: i32;
+        for _ in 0..ext_count {
+            external_paths.push(reader.read_prefixed_string()?);
+        }
 
-        loop {
-            if reader.seek_until(&ADDE_PADDING)?.is_none() {
-                // End of file reached
-                reader.seek(SeekFrom::Start(start_pos))?;
-                return Ok(None);
-            }
+        Ok(external_paths)
+    }
 
-            // Found padding, skip needle bytes
-            reader.seek(SeekFrom::Current(4))?;
+    /// Reads start of directory entry (common ObjectDir fields) + any inline sub directories stored inside it.
+    /// Returns data before sub directories.
+    fn read_directory_entry_start(&self, reader: &mut BinaryStream, dir_type: &str, info: &SystemInfo, settings: &MiloUnpackSettings, sub_dirs: &mut Vec<ObjectDir>) -> Result<(Vec<u8>, Option<ObjectDirObject>), Box<dyn Error>> {
+        let start_pos = reader.pos();
 
-            if (reader.pos() as usize) >= stream_len {
-                // EOF reached
-                break;
-            }
+        let mut object_dir = ObjectDirObject::default();
+        let load_result = {
+            let mut obj_reader = Box::new(BinaryStream::from_stream_with_endian(reader, info.endian));
+            load_object_dir(&mut object_dir, dir_type, &mut obj_reader, info)
+        };
 
-            // Checks magic because ADDE padding can also be found in some Tex files as pixel data
-            // This should reduce false positives
-            magic = reader.read_int32()?;
-            reader.seek(SeekFrom::Current(-4))?;
+        // Inline sub directories should directly follow parsed fields
+        let parsed = load_result.is_ok()
+            && (object_dir.inline_sub_dir_names.is_empty() || MiloArchive::is_directory_header(reader, info));
 
-            if (0..=0xFF).contains(&magic) {
-                break;
+        let (sub_dirs_pos, object_dir) = match (parsed, settings.guess_entry_sizes) {
+            (true, _) => (reader.pos(), Some(object_dir)),
+            (false, true) => {
+                // Search for inline sub directories instead
+                reader.seek(SeekFrom::Start(start_pos))?;
+
+                match MiloArchive::find_inline_sub_dir(reader, info)? {
+                    Some(pos) => (pos, None),
+                    None => return Ok((Vec::new(), None))
+                }
+            },
+            _ => {
+                // Read as raw data
+                reader.seek(SeekFrom::Start(start_pos))?;
+                return Ok((Vec::new(), None));
             }
-        }
+        };
 
-        // Calculates size and returns to start of stream
-        let entry_size = (reader.pos() - (start_pos + 4)) as usize;
+        // Read data before sub directories
         reader.seek(SeekFrom::Start(start_pos))?;
+        let data = reader.read_bytes((sub_dirs_pos - start_pos) as usize)?;
 
-        Ok(Some(entry_size))
-    }
-
-    fn get_type_order_value(obj_type: &str) -> i32 {
-        // Same sort order in dta scripts
-        match obj_type {
-            "Tex" => 0,
-            "Mat" => 1,
-            "Font" => 2,
-            "Text" => 3,
-            "Mesh" => 4,
-            "Blur" => 5,
-            "Group" => 6,
-            "View" => 6,
-            "Trans" => 7,
-            _ => 100
-        }
-    }
-
-    fn compare_entries_by_type_and_name<'r, 's>(a: &'r &Object, b: &'s &Object) -> Ordering {
-        // Get entry types
-        let a_type = MiloArchive::get_type_order_value(a.get_type());
-        let b_type = MiloArchive::get_type_order_value(b.get_type());
-
-        // First compare type
-        match a_type.cmp(&b_type) {
-            Ordering::Less => Ordering::Less,
-            Ordering::Greater => Ordering::Greater,
-            Ordering::Equal => {
-                // Get entry names
-                let a_name = a.get_name();
-                let b_name = b.get_name();
+        match &object_dir {
+            Some(obj) => {
+                for _ in 0..obj.inline_sub_dir_names.len() {
+                    sub_dirs.push(self.read_directory(reader, info, settings, false)?);
+                }
+            },
+            None => {
+                // Sub directories are stored back to back
+                loop {
+                    sub_dirs.push(self.read_directory(reader, info, settings, false)?);
 
-                // Then compare name
-                a_name.cmp(b_name)
+                    if !MiloArchive::is_directory_header(reader, info) {
+                        break;
+                    }
+                }
             }
         }
+
+        Ok((data, object_dir))
     }
 
-    pub fn from_object_dir(obj_dir: &ObjectDir, info: &SystemInfo, block_type: Option<BlockType>) -> Result<MiloArchive, Box<dyn Error>> {
-        // Create stream
-        let mut data = Vec::<u8>::new();
-        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
-        let mut writer = BinaryStream::from_stream_with_endian(&mut stream, info.endian);
+    /// Gets sizes of next entries. Entries of supported types are read to find where their data ends,
+    /// other entries end at next ADDE padding. Root directory must end after last entry, otherwise
+    /// padding is only used if followed by start of next entry when guessing is enabled.
+    fn get_entry_sizes(&self, reader: &mut BinaryStream, entry_types: &[&str], info: &SystemInfo, settings: &MiloUnpackSettings, is_root: bool) -> Result<Vec<usize>, Box<dyn Error>> {
+        let start_pos = reader.pos() as usize;
+        let data = self.data.get(start_pos..).unwrap_or_default();
 
-        let mut entries: Vec<&Object> = obj_dir.get_entries().iter().collect();
+        let mut sizes = Vec::new();
+        let mut entry_start = 0;
 
-        // Write version
-        writer.write_uint32(info.version)?;
+        for (i, object_type) in entry_types.iter().enumerate() {
+            let entry_data = &data[entry_start..];
 
-        let mut dir_entry_op = None;
-        if info.version >= 24 {
-            // TODO: Refactor and get from field instead of hacky entries
-            let dir_entry = entries.swap_remove(0); // Faster than remove(), will sort anyways
+            let size = match get_object_data_size(object_type, entry_data, info) {
+                Some(size) if entry_data.get(size..).is_some_and(|d| d.starts_with(&ADDE_PADDING)) => Some(size),
+                _ => {
+                    // ADDE padding can also be found in entry data (e.g. Tex pixel data) so when guessing,
+                    // padding is only used if followed by start of next entry (or end of data).
+                    // Sub directory is followed by rest of parent directory entry so last padding can't be checked.
+                    let is_last = (i + 1) == entry_types.len();
+                    let mut size = None;
+
+                    for offset in find_padding_offsets(entry_data) {
+                        if !settings.guess_entry_sizes
+                            || (is_last && !is_root)
+                            || MiloArchive::is_entry_start(reader, (start_pos + entry_start + offset + ADDE_PADDING.len()) as u64)? {
+                            size = Some(offset);
+                            break;
+                        }
+                    }
 
-            // Write directory name + type
-            let dir_type = dir_entry.get_type();
-            let dir_name = dir_entry.get_name();
+                    size
+                }
+            };
 
-            writer.write_prefixed_string(dir_type)?;
-            writer.write_prefixed_string(dir_name)?;
+            let Some(size) = size else {
+                break;
+            };
 
-            // Compute values for string table
-            let hash_count = (entries.len() + 1) * 2;
-            let blob_size = entries
-                .iter()
-                .map(|o| o.get_name().len() + 1)
-                .sum::<usize>() + (dir_name.len() + 1);
+            sizes.push(size);
+            entry_start += size + ADDE_PADDING.len();
+        }
 
-            // Write string table values
-            writer.write_uint32(hash_count as u32)?;
-            writer.write_uint32(blob_size as u32)?;
+        if settings.guess_entry_sizes {
+            return Ok(sizes);
+        }
 
-            dir_entry_op = Some(dir_entry);
+        if sizes.len() != entry_types.len() || (is_root && entry_start < data.len()) {
+            return Err(Box::new(MiloUnpackError::EntryCountMismatch {
+                expected: entry_types.len(),
+                found: sizes.len() + find_padding_offsets(&data[entry_start..]).len()
+            }));
         }
 
-        writer.write_uint32(entries.len() as u32)?;
+        Ok(sizes)
+    }
 
-        // Write types + names
-        entries.sort_by(MiloArchive::compare_entries_by_type_and_name);
-        for entry in entries.iter() {
-            let obj_type = entry.get_type();
-            let obj_name = entry.get_name();
+    /// Checks if entry version (or end of data) is at position. Doesn't advance stream.
+    fn is_entry_start(reader: &mut BinaryStream, pos: u64) -> Result<bool, Box<dyn Error>> {
+        let start_pos = reader.pos();
 
-            writer.write_prefixed_string(obj_type)?;
-            writer.write_prefixed_string(obj_name)?;
+        if pos >= reader.len()? as u64 {
+            return Ok(true);
         }
 
-        if info.version == 10 {
-            // TODO: Determine external dependencies or get from directory property
-            writer.write_uint32(0)?;
-        } else {
-            // Hacky way to write directory entry
-            let dir_entry = dir_entry_op.unwrap();
+        reader.seek(SeekFrom::Start(pos))?;
+        let version = reader.read_int32().unwrap_or(-1);
+        reader.seek(SeekFrom::Start(start_pos))?;
 
-            if let Object::Packed(packed) = dir_entry {
-                writer.write_bytes(&packed.data.as_slice())?;
+        Ok((0..=0xFF).contains(&version))
+    }
+
+    /// Searches for start of inline sub directory before end of current entry
+    /// (fallback when directory entry can't be parsed)
+    fn find_inline_sub_dir(reader: &mut BinaryStream, info: &SystemInfo) -> Result<Option<u64>, Box<dyn Error>> {
+        if info.version < 24 {
+            // Sub directories can't be identified without name + type
+            return Ok(None);
+        }
+
+        let start_pos = reader.pos();
+        let entry_size = reader.seek_until(&ADDE_PADDING)?;
+        let end_pos = match entry_size {
+            Some(size) => start_pos + size as u64,
+            None => reader.len()? as u64,
+        };
+
+        let mut sub_dir_pos = None;
+
+        for pos in start_pos..end_pos {
+            reader.seek(SeekFrom::Start(pos))?;
+
+            if MiloArchive::is_directory_header(reader, info) {
+                sub_dir_pos = Some(pos);
+                break;
             }
+        }
 
-            writer.write_bytes(&ADDE_PADDING)?;
+        reader.seek(SeekFrom::Start(start_pos))?;
+        Ok(sub_dir_pos)
+    }
+
+    /// Checks if directory header (version, type, name, entry names) is at current position.
+    /// Doesn't advance stream.
+    fn is_directory_header(reader: &mut BinaryStream, info: &SystemInfo) -> bool {
+        let start_pos = reader.pos();
+        let stream_len = reader.len().unwrap_or_default() as u64;
+
+        let read_string = |reader: &mut BinaryStream, max_length: u32, is_class: bool| -> Result<bool, Box<dyn Error>> {
+            let length = reader.read_uint32()?;
+            if length > max_length || (reader.pos() + length as u64) > stream_len {
+                return Ok(false);
+            }
+
+            let data = reader.read_bytes(length as usize)?;
+
+            Ok(match is_class {
+                true => data.first().is_some_and(|c| c.is_ascii_uppercase()) && data.iter().all(|c| c.is_ascii_alphanumeric()),
+                _ => data.iter().all(|c| (0x20..0x7F).contains(c)),
+            })
+        };
+
+        let mut read_header = || -> Result<bool, Box<dyn Error>> {
+            if reader.read_uint32()? != info.version
+                || !read_string(reader, 0x40, true)?
+                || !read_string(reader, 0x100, false)? {
+                return Ok(false);
+            }
+
+            // Skip string table counts
+            reader.seek(SeekFrom::Current(if info.version >= 32 { 9 } else { 8 }))?;
+
+            let entry_count = reader.read_int32()?;
+            if !(0..=0xFFFF).contains(&entry_count) {
+                return Ok(false);
+            }
+
+            for _ in 0..entry_count {
+                if !read_string(reader, 0x40, true)? || !read_string(reader, 0x100, false)? {
+                    return Ok(false);
+                }
+            }
+
+            Ok(true)
+        };
+
+        let is_header = read_header().unwrap_or_default();
+
+        reader.seek(SeekFrom::Start(start_pos)).ok();
+        is_header
+    }
+
+    fn get_type_order_value(obj_type: &str) -> i32 {
+        // Same sort order in dta scripts
+        match obj_type {
+            "Tex" => 0,
+            "Mat" => 1,
+            "Font" => 2,
+            "Text" => 3,
+            "Mesh" => 4,
+            "Blur" => 5,
+            "Group" => 6,
+            "View" => 6,
+            "Trans" => 7,
+            _ => 100
+        }
+    }
+
+    fn compare_entries_by_type_and_name<'r, 's>(a: &'r &Object, b: &'s &Object) -> Ordering {
+        // Get entry types
+        let a_type = MiloArchive::get_type_order_value(a.get_type());
+        let b_type = MiloArchive::get_type_order_value(b.get_type());
+
+        // First compare type
+        match a_type.cmp(&b_type) {
+            Ordering::Less => Ordering::Less,
+            Ordering::Greater => Ordering::Greater,
+            Ordering::Equal => {
+                // Get entry names
+                let a_name = a.get_name();
+                let b_name = b.get_name();
+
+                // Then compare name
+                a_name.cmp(b_name)
+            }
         }
+    }
+
+    pub fn from_object_dir(obj_dir: &ObjectDir, info: &SystemInfo, block_type: Option<BlockType>) -> Result<MiloArchive, crate::Error> {
+        let settings = MiloPackSettings {
+            block_type,
+            ..Default::default()
+        };
+
+        MiloArchive::from_object_dir_with_settings(obj_dir, info, &settings)
+    }
+
+    pub fn from_object_dir_with_settings(obj_dir: &ObjectDir, info: &SystemInfo, settings: &MiloPackSettings) -> Result<MiloArchive, crate::Error> {
+        let preserve_order = settings.original.is_some();
+
+        // Create stream
+        let mut data = Vec::<u8>::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        let mut writer = BinaryStream::from_stream_with_endian(&mut stream, info.endian);
+
+        let entries = MiloArchive::write_directory_header(&mut writer, obj_dir, info, preserve_order)?;
 
         let mut block_sizes = Vec::new();
         let mut current_size = writer.len()?;
 
         // Write data for entries
-        for entry in entries.iter() {
-            // Get packed entry
-            match entry {
-                Object::Packed(packed) => {
-                    let data = &packed.data;
+        let ObjectDir::ObjectDir(dir) = obj_dir;
 
-                    // Write to stream
-                    writer.write_bytes(&data[..])?;
-                    writer.write_bytes(&ADDE_PADDING)?;
+        for entry in entries.iter() {
+            let entry_size = MiloArchive::write_entry_data(&mut writer, entry, info, dir)?;
 
-                    // Update block size
-                    current_size += data.len() + 4;
-                },
-                _ => {
-                    // Pack entry
-                    let data = entry
-                        .pack(info)
-                        .and_then(|o| match o {
-                            Object::Packed(p) => Some(p.data),
-                            _ => None
-                        });
-
-                    if let Some(data) = &data {
-                        // Write to stream
-                        writer.write_bytes(&data[..])?;
-                        writer.write_bytes(&ADDE_PADDING)?;
-
-                        // Update block size
-                        current_size += data.len() + 4;
-                    } else {
-                        continue;
-                    }
-                }
-            };
+            // Update block size
+            current_size += entry_size;
 
             if current_size >= MAX_BLOCK_SIZE {
                 block_sizes.push(current_size);
@@ -461,17 +1057,191 @@ impl MiloArchive {
             block_sizes.push(current_size);
         }
 
-        Ok(MiloArchive {
-            structure: MiloArchiveStructure::Blocked(BlockInfo {
-                block_type: block_type.unwrap_or(BlockType::TypeB),
-                start_offset: 2064,
-                block_sizes
+        let structure = match settings.original.map(|o| &o.structure) {
+            Some(MiloArchiveStructure::Blocked(original_info)) => MiloArchiveStructure::Blocked(BlockInfo {
+                block_type: original_info.block_type,
+                start_offset: original_info.start_offset,
+                block_sizes: get_preserved_block_sizes(&original_info.block_sizes, data.len()),
+                layout: original_info.layout.clone()
             }),
-            data
+            Some(MiloArchiveStructure::GZIP) => MiloArchiveStructure::GZIP,
+            Some(MiloArchiveStructure::Uncompressed) => MiloArchiveStructure::Uncompressed,
+            None => match settings.structure_type {
+                MiloStructureType::Blocked => MiloArchiveStructure::Blocked(BlockInfo {
+                    block_type: settings.block_type.unwrap_or(BlockType::TypeB),
+                    block_sizes,
+                    ..BlockInfo::new()
+                }),
+                MiloStructureType::GZIP => MiloArchiveStructure::GZIP,
+                MiloStructureType::Uncompressed => MiloArchiveStructure::Uncompressed,
+            }
+        };
+
+        Ok(MiloArchive {
+            structure,
+            data,
+            pending_blocks: None,
+            path: None,
         })
     }
 
-    pub fn write_to_stream(&self, stream: &mut dyn Stream) -> Result<(), Box<dyn Error>> {
+    /// Writes version, entry names and directory entry (with inline sub directories).
+    /// Returns entries in order their data should be written.
+    fn write_directory_header<'a>(writer: &mut BinaryStream, obj_dir: &'a ObjectDir, info: &SystemInfo, preserve_order: bool) -> Result<Vec<&'a Object>, Box<dyn Error>> {
+        let ObjectDir::ObjectDir(dir) = obj_dir;
+        let mut entries: Vec<&Object> = dir.entries.iter().collect();
+
+        // Write version
+        writer.write_uint32(info.version)?;
+
+        if info.version >= 24 {
+            // Write directory name + type
+            writer.write_prefixed_string(&dir.dir_type)?;
+            writer.write_prefixed_string(&dir.name)?;
+
+            // Compute values for string table
+            let mut hash_count = ((entries.len() + 1) * 2) as u32;
+            let mut blob_size = (entries
+                .iter()
+                .map(|o| o.get_name().len() + 1)
+                .sum::<usize>() + (dir.name.len() + 1)) as u32;
+
+            if preserve_order {
+                // Original values are only kept if still large enough (i.e. entries weren't added or renamed)
+                hash_count = hash_count.max(dir.string_table_count);
+                blob_size = blob_size.max(dir.string_table_size);
+            }
+
+            // Write string table values
+            writer.write_uint32(hash_count)?;
+            writer.write_uint32(blob_size)?;
+
+            if info.version >= 32 {
+                writer.write_boolean(preserve_order && dir.unknown_bool)?;
+            }
+        }
+
+        writer.write_uint32(entries.len() as u32)?;
+
+        // Write types + names
+        if !preserve_order {
+            entries.sort_by(MiloArchive::compare_entries_by_type_and_name);
+        }
+
+        for entry in entries.iter() {
+            let obj_type = entry.get_type();
+            let obj_name = entry.get_name();
+
+            writer.write_prefixed_string(obj_type)?;
+            writer.write_prefixed_string(obj_name)?;
+        }
+
+        if info.version == 10 {
+            let mut external_paths = obj_dir.get_external_paths(info);
+
+            // Original paths are only kept (in original order) if they're still the same
+            let is_unchanged = external_paths.len() == dir.external_paths.len()
+                && external_paths
+                    .iter()
+                    .all(|p| dir.external_paths.iter().any(|op| op.eq_ignore_ascii_case(p)));
+
+            if preserve_order && is_unchanged {
+                external_paths = dir.external_paths.to_owned();
+            }
+
+            writer.write_uint32(external_paths.len() as u32)?;
+
+            for path in external_paths.iter() {
+                writer.write_prefixed_string(path)?;
+            }
+        } else if info.version > 10 {
+            let dir_entry = dir.dir_entry
+                .as_ref()
+                .ok_or(MiloPackError::MissingDirectoryEntry)?;
+
+            // Inline sub directories are written in middle of directory entry
+            let (entry_start, entry_end) = dir_entry.data.split_at(dir_entry.sub_dirs_offset.min(dir_entry.data.len()));
+            writer.write_bytes(entry_start)?;
+
+            for sub_dir in dir.sub_dirs.iter() {
+                MiloArchive::write_sub_directory(writer, sub_dir, info, preserve_order)?;
+            }
+
+            writer.write_bytes(entry_end)?;
+            writer.write_bytes(&ADDE_PADDING)?;
+        }
+
+        Ok(entries)
+    }
+
+    fn write_sub_directory(writer: &mut BinaryStream, obj_dir: &ObjectDir, info: &SystemInfo, preserve_order: bool) -> Result<(), Box<dyn Error>> {
+        let entries = MiloArchive::write_directory_header(writer, obj_dir, info, preserve_order)?;
+        let ObjectDir::ObjectDir(dir) = obj_dir;
+
+        for entry in entries.iter() {
+            MiloArchive::write_entry_data(writer, entry, info, dir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes entry data + padding and returns size written. Original data of entry is written if it
+    /// can't be packed and original data can be used for system.
+    fn write_entry_data(writer: &mut BinaryStream, entry: &Object, info: &SystemInfo, dir: &ObjectDirBase) -> Result<usize, Box<dyn Error>> {
+        let packed_data;
+
+        let original_data = dir.original_info
+            .filter(|original_info| can_copy_data(entry.get_type(), original_info, info))
+            .and_then(|_| dir.original_data.get(entry.get_name()));
+
+        let data = match entry {
+            Object::Packed(packed) => &packed.data,
+            _ => match (entry.try_pack(info), original_data) {
+                (Ok(packed), _) => {
+                    packed_data = packed.data;
+                    &packed_data
+                },
+                (Err(error), Some(data)) => {
+                    log::warn!("Unable to pack, writing original data instead: {error}");
+                    data
+                },
+                (Err(error), None) => return Err(Box::new(error)),
+            }
+        };
+
+        // Write to stream
+        writer.write_bytes(&data[..])?;
+        writer.write_bytes(&ADDE_PADDING)?;
+
+        Ok(data.len() + 4)
+    }
+
+    /// Returns stored size (with flags) + data of block. Original block is re-used if data is unchanged.
+    fn encode_block<'a>(info: &'a BlockInfo, index: usize, block_data: &[u8], buffer: &mut [u8], compression_level: u32) -> Result<(u32, Cow<'a, [u8]>), MiloBlockStructureError> {
+        let stored_block = info.layout
+            .as_ref()
+            .and_then(|l| l.blocks.get(index))
+            .filter(|b| b.inflated_size == block_data.len() && b.inflated_checksum == get_checksum(block_data));
+
+        if let Some(stored_block) = stored_block {
+            // Write original block as is
+            return Ok((stored_block.size_value, Cow::Borrowed(&stored_block.data)));
+        }
+
+        let compressed_data = match &info.block_type {
+            BlockType::TypeA => Ok(block_data.to_owned()), // No compression
+            BlockType::TypeB => deflate_zlib_block_with_level(block_data, buffer, compression_level),
+            BlockType::TypeC => deflate_gzip_block_with_level(block_data, compression_level),
+            BlockType::TypeD => deflate_zlib_block_with_level(block_data, buffer, compression_level)
+                .map(|data| [&(block_data.len() as u32).to_le_bytes()[..], &data].concat()), // Prefix inflated size
+        }.map_err(|_| MiloBlockStructureError::CantDeflateBlock { index })?;
+
+        Ok((compressed_data.len() as u32, Cow::Owned(compressed_data)))
+    }
+
+    pub fn write_to_stream(&self, stream: &mut dyn Stream) -> Result<(), crate::Error> {
+        self.check_inflated()?;
+
         let mut writer = BinaryStream::from_stream(stream);
 
         match &self.structure {
@@ -485,11 +1255,15 @@ impl MiloArchive {
                 };
 
                 // Get max uncompressed size
-                let max_block_size = match info.block_sizes.iter().max() {
+                let mut max_block_size = match info.block_sizes.iter().max() {
                     Some(max) => *max,
                     None => 0
                 };
 
+                if let Some(layout) = &info.layout {
+                    max_block_size = max_block_size.max(layout.max_inflate_size as usize);
+                }
+
                 // Write infos
                 writer.write_uint32(magic)?;
                 writer.write_uint32(info.start_offset)?;
@@ -503,43 +1277,32 @@ impl MiloArchive {
                 // Write empty bytes for now
                 writer.write_bytes(&vec![0u8; (info.start_offset - 16) as usize][..])?;
 
-                // Create buffer
-                let mut buffer = vec![0u8; max_block_size];
+                let compression_level = info.get_compression_level().unwrap_or(9);
 
-                // Iterate over blocks and compress data
+                // Get data ranges of blocks
                 let mut block_offset = 0;
-                let mut deflate_sizes = Vec::new();
-                for block_size in info.block_sizes.iter() {
-                    let block_data = &self.data[block_offset..(block_offset + *block_size)];
-
-                    if let BlockType::TypeA = &info.block_type {
-                        // Write uncompressed block to stream
-                        writer.write_bytes(block_data)?;
+                let block_ranges = info.block_sizes
+                    .iter()
+                    .map(|size| {
+                        block_offset += size;
+                        (block_offset - size)..block_offset
+                    })
+                    .collect::<Vec<_>>();
+
+                // Compress blocks in parallel
+                let blocks = block_ranges
+                    .into_par_iter()
+                    .enumerate()
+                    .map_init(|| vec![0u8; get_deflate_bound(max_block_size)], |buffer, (i, range)| {
+                        MiloArchive::encode_block(info, i, &self.data[range], buffer, compression_level)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
 
-                        // Add uncompressed size
-                        deflate_sizes.push(block_data.len());
-                    }
-                    else if let BlockType::TypeD = &info.block_type {
-                        let compressed_data = &deflate_zlib_block(block_data, &mut buffer)?[..];
-
-                        // Write compressed block to stream
-                        writer.write_uint32(block_data.len() as u32)?; // Write inflated size
-                        writer.write_bytes(compressed_data)?;
-
-                        // Add compressed size
-                        deflate_sizes.push(compressed_data.len() + 4);
-                    } else {
-                        let compressed_data = &deflate_zlib_block(block_data, &mut buffer)?[..];
-
-                        // Write compressed block to stream
-                        writer.write_bytes(compressed_data)?;
-
-                        // Add compressed size
-                        deflate_sizes.push(compressed_data.len());
-                    }
+                let mut deflate_sizes = Vec::new();
 
-                    // Update current offset
-                    block_offset += *block_size;
+                for (size_value, block_data) in blocks.iter() {
+                    writer.write_bytes(block_data)?;
+                    deflate_sizes.push(*size_value as usize);
                 }
 
                 // Go back to block sizes offset
@@ -549,9 +1312,16 @@ impl MiloArchive {
                 for size in deflate_sizes.iter() {
                     writer.write_uint32(*size as u32)?;
                 }
+
+                // Restore original data between block sizes and first block
+                if let Some(layout) = &info.layout
+                    && (writer.pos() + layout.header_gap.len() as u64) == info.start_offset as u64 {
+                    writer.write_bytes(&layout.header_gap)?;
+                }
             },
             MiloArchiveStructure::GZIP => {
-                todo!("Gzip compression for milo archive not supported")
+                // Write whole archive as single gzip stream
+                writer.write_bytes(&deflate_gzip_block_with_level(&self.data, 9)?)?;
             }
             MiloArchiveStructure::Uncompressed => {
                 // Write uncompressed data
@@ -596,3 +1366,879 @@ impl MiloArchive {
         Some(version)
     }
 }
+
+/// Keeps original block boundaries that still fit in data. Last block takes remaining data
+/// (split at max block size if needed).
+fn get_preserved_block_sizes(original_sizes: &[usize], data_size: usize) -> Vec<usize> {
+    let mut block_sizes = Vec::new();
+    let mut offset = 0;
+
+    for size in original_sizes.iter().take(original_sizes.len().saturating_sub(1)) {
+        if offset + size > data_size {
+            break;
+        }
+
+        block_sizes.push(*size);
+        offset += size;
+    }
+
+    while offset < data_size {
+        let size = (data_size - offset).min(MAX_BLOCK_SIZE);
+
+        block_sizes.push(size);
+        offset += size;
+    }
+
+    block_sizes
+}
+
+/// Finds zlib level that reproduces compressed data
+fn detect_compression_level(compressed_data: &[u8], data: &[u8]) -> Option<u32> {
+    // Compressed data must fit in original size
+    let mut buffer = vec![0u8; compressed_data.len()];
+
+    (0..=9)
+        .rev()
+        .find(|level| deflate_zlib_block_with_level(data, &mut buffer, *level)
+            .is_ok_and(|d| d == compressed_data))
+}
+
+/// Max size of deflated data (same as zlib compressBound)
+fn get_deflate_bound(size: usize) -> usize {
+    size + (size >> 12) + (size >> 14) + (size >> 25) + 13
+}
+
+fn get_checksum(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+/// Returns offsets of non-overlapping ADDE padding in data
+fn find_padding_offsets(data: &[u8]) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut i = 0;
+
+    while i + ADDE_PADDING.len() <= data.len() {
+        if data[i..].starts_with(&ADDE_PADDING) {
+            offsets.push(i);
+            i += ADDE_PADDING.len();
+        } else {
+            i += 1;
+        }
+    }
+
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+    use crate::Platform;
+    use crate::scene::{CharLipSync, get_object_dir_class_depth, save_object_dir, Tex};
+    use crate::texture::{Bitmap, DXGI_Encoding};
+
+    fn get_info(version: u32) -> SystemInfo {
+        SystemInfo {
+            version,
+            platform: Platform::X360,
+            endian: IOEndian::Big,
+        }
+    }
+
+    fn create_dir_entry(dir_type: &str, inline_sub_dirs: &[&str], info: &SystemInfo) -> ObjectDirEntry {
+        let object_dir = ObjectDirObject {
+            class_revisions: vec![10; get_object_dir_class_depth(dir_type).unwrap()],
+            revision: 27,
+            current_viewport: 1,
+            sub_dir_paths: vec![String::from("../shared/shared.milo")],
+            inline_sub_dir: !inline_sub_dirs.is_empty(),
+            inline_sub_dir_names: inline_sub_dirs
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            inline_sub_dir_types: vec![1; inline_sub_dirs.len()],
+            ..Default::default()
+        };
+
+        let mut data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        let mut writer = Box::new(BinaryStream::from_stream_with_endian(&mut stream, info.endian));
+        save_object_dir(&object_dir, &mut writer, info).unwrap();
+
+        // Class specific data
+        let sub_dirs_offset = data.len();
+        data.extend_from_slice(&[0, 0, 0, 1, 0xAB, 0xCD]);
+
+        ObjectDirEntry {
+            data,
+            sub_dirs_offset,
+            object_dir: None
+        }
+    }
+
+    fn create_dir(name: &str, dir_type: &str, entries: &[(&str, &str)], dir_entry: Option<ObjectDirEntry>, sub_dirs: Vec<ObjectDir>) -> ObjectDir {
+        ObjectDir::ObjectDir(ObjectDirBase {
+            entries: entries
+                .iter()
+                .map(|(object_type, name)| Object::Packed(PackedObject {
+                    name: name.to_string(),
+                    object_type: object_type.to_string(),
+                    data: [&[0u8, 0, 0, 2][..], name.as_bytes()].concat()
+                }))
+                .collect(),
+            name: name.to_owned(),
+            dir_type: dir_type.to_owned(),
+            dir_entry,
+            sub_dirs,
+            ..ObjectDirBase::new()
+        })
+    }
+
+    /// Creates packed Tex with ADDE padding followed by value that looks like entry version in pixel data
+    fn create_tex_with_padding(name: &str, info: &SystemInfo) -> Object {
+        let mut tex = Tex::new();
+        tex.name = name.to_owned();
+        tex.width = 8;
+        tex.height = 8;
+        tex.bpp = 4;
+        tex.bitmap = Some(Bitmap {
+            bpp: 4,
+            encoding: DXGI_Encoding::DXGI_FORMAT_BC1_UNORM as u32,
+            mip_maps: 0,
+
+            width: 8,
+            height: 8,
+            bpl: 4,
+
+            raw_data: [0xAD, 0xDE, 0xAD, 0xDE, 0, 0, 0, 1].repeat(4)
+        });
+
+        Object::Packed(Object::Tex(tex).try_pack(info).unwrap())
+    }
+
+    // Name, type, entries, dir entry, sub dir count
+    type DirSummary = (String, String, Vec<(String, String, Vec<u8>)>, Option<(Vec<u8>, usize)>, usize);
+
+    fn get_dir_summary(obj_dir: &ObjectDir) -> DirSummary {
+        let ObjectDir::ObjectDir(dir) = obj_dir;
+
+        (
+            dir.name.to_owned(),
+            dir.dir_type.to_owned(),
+            dir.entries
+                .iter()
+                .map(|e| match e {
+                    Object::Packed(p) => (p.object_type.to_owned(), p.name.to_owned(), p.data.to_owned()),
+                    _ => unreachable!()
+                })
+                .collect(),
+            dir.dir_entry.as_ref().map(|e| (e.data.to_owned(), e.sub_dirs_offset)),
+            dir.sub_dirs.len()
+        )
+    }
+
+    #[rstest]
+    #[case(25)]
+    #[case(28)]
+    #[case(32)]
+    fn unpack_directory_with_inline_sub_dirs(#[case] version: u32) {
+        let info = get_info(version);
+
+        let sub_dir = create_dir(
+            "head.milo",
+            "RndDir",
+            &[("Tex", "skin.tex"), ("Mat", "skin.mat")],
+            Some(create_dir_entry("RndDir", &[], &info)),
+            Vec::new()
+        );
+
+        let obj_dir = create_dir(
+            "character",
+            "Character",
+            &[("Mesh", "body.mesh"), ("Trans", "bone_root")],
+            Some(create_dir_entry("Character", &["head.milo"], &info)),
+            vec![sub_dir]
+        );
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+        let unpacked_dir = milo.unpack_directory(&info).unwrap();
+
+        assert_eq!(get_dir_summary(&obj_dir), get_dir_summary(&unpacked_dir));
+        assert_eq!(get_dir_summary(&obj_dir.get_sub_dirs()[0]), get_dir_summary(&unpacked_dir.get_sub_dirs()[0]));
+
+        let ObjectDir::ObjectDir(dir) = &unpacked_dir;
+        let object_dir = dir.dir_entry.as_ref().and_then(|e| e.object_dir.as_ref()).unwrap();
+
+        assert_eq!(vec![10, 10], object_dir.class_revisions);
+        assert_eq!(27, object_dir.revision);
+        assert_eq!(1, object_dir.current_viewport);
+        assert_eq!(vec!["../shared/shared.milo"], object_dir.sub_dir_paths);
+        assert!(object_dir.inline_sub_dir);
+        assert_eq!(vec!["head.milo"], object_dir.inline_sub_dir_names);
+
+        // String table values = (entries + 1) * 2, names + null bytes
+        assert_eq!(6, dir.string_table_count);
+        assert_eq!(30, dir.string_table_size);
+    }
+
+    #[rstest]
+    #[case("ObjectDir")]
+    #[case("PanelDir")]
+    #[case("WorldDir")]
+    fn unpack_directory_without_sub_dirs(#[case] dir_type: &str) {
+        let info = get_info(25);
+
+        let obj_dir = create_dir(
+            "venue",
+            dir_type,
+            &[("Mat", "floor.mat")],
+            Some(create_dir_entry(dir_type, &[], &info)),
+            Vec::new()
+        );
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+        let unpacked_dir = milo.unpack_directory(&info).unwrap();
+
+        assert_eq!(get_dir_summary(&obj_dir), get_dir_summary(&unpacked_dir));
+        assert!(unpacked_dir.get_sub_dirs().is_empty());
+    }
+
+    #[rstest]
+    fn unpack_directory_with_legacy_object_dir_revision() {
+        let info = get_info(25);
+
+        let object_dir = ObjectDirObject {
+            revision: 12,
+            proxy_path: String::from("proxy.milo"),
+            unknown_string: String::from("legacy"),
+            ..Default::default()
+        };
+
+        let mut data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        let mut writer = Box::new(BinaryStream::from_stream_with_endian(&mut stream, info.endian));
+        save_object_dir(&object_dir, &mut writer, &info).unwrap();
+
+        let obj_dir = create_dir(
+            "venue",
+            "ObjectDir",
+            &[("Mat", "floor.mat")],
+            Some(ObjectDirEntry {
+                sub_dirs_offset: data.len(),
+                data,
+                object_dir: None
+            }),
+            Vec::new()
+        );
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+        let unpacked_dir = milo.unpack_directory(&info).unwrap();
+
+        assert_eq!(get_dir_summary(&obj_dir), get_dir_summary(&unpacked_dir));
+
+        let ObjectDir::ObjectDir(dir) = &unpacked_dir;
+        let unpacked_object_dir = dir.dir_entry.as_ref().and_then(|e| e.object_dir.as_ref()).unwrap();
+
+        assert_eq!("proxy.milo", unpacked_object_dir.proxy_path);
+        assert_eq!("legacy", unpacked_object_dir.unknown_string);
+    }
+
+    #[rstest]
+    fn unpack_directory_with_unknown_dir_type() {
+        let info = get_info(25);
+
+        let obj_dir = create_dir(
+            "band",
+            "BandCharacter",
+            &[("Mat", "floor.mat")],
+            Some(ObjectDirEntry {
+                data: vec![0, 0, 0, 0x11, 0xFF, 0xFF],
+                ..Default::default()
+            }),
+            Vec::new()
+        );
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+        let unpacked_dir = milo.unpack_directory(&info).unwrap();
+
+        // Dir entry is read as raw data
+        let ObjectDir::ObjectDir(dir) = &unpacked_dir;
+        let dir_entry = dir.dir_entry.as_ref().unwrap();
+
+        assert_eq!(vec![0, 0, 0, 0x11, 0xFF, 0xFF], dir_entry.data);
+        assert_eq!(0, dir_entry.sub_dirs_offset);
+        assert!(dir_entry.object_dir.is_none());
+        assert_eq!(get_dir_summary(&obj_dir).2, get_dir_summary(&unpacked_dir).2);
+    }
+
+    #[rstest]
+    fn unpack_directory_with_padding_in_entry_data() {
+        let info = get_info(25);
+
+        let mut obj_dir = create_dir(
+            "venue",
+            "ObjectDir",
+            &[("Mat", "floor.mat")],
+            Some(create_dir_entry("ObjectDir", &[], &info)),
+            Vec::new()
+        );
+
+        // Tex is read to find end of data so padding in pixel data is skipped
+        obj_dir.get_entries_mut().insert(0, create_tex_with_padding("floor.tex", &info));
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+        let unpacked_dir = milo.unpack_directory(&info).unwrap();
+
+        assert_eq!(get_dir_summary(&obj_dir), get_dir_summary(&unpacked_dir));
+    }
+
+    #[rstest]
+    fn unpack_directory_with_padding_in_unsupported_entry_data() {
+        let info = get_info(25);
+
+        let mut obj_dir = create_dir(
+            "venue",
+            "ObjectDir",
+            &[("Mat", "floor.mat"), ("Environ", "venue.env")],
+            Some(create_dir_entry("ObjectDir", &[], &info)),
+            Vec::new()
+        );
+
+        // Add padding to data of entry that can't be read
+        if let Object::Packed(packed) = &mut obj_dir.get_entries_mut()[1] {
+            packed.data = vec![0, 0, 0, 10, 0xAD, 0xDE, 0xAD, 0xDE, 0xFF, 0xFF, 0xFF, 0xFF];
+        }
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+
+        // Entries are only split at padding followed by entry version when guessing
+        let result = milo.unpack_directory(&info);
+        assert!(matches!(
+            result.err().as_ref().map(|e| e.get_inner()),
+            Some(crate::Error::MiloUnpack(MiloUnpackError::EntryCountMismatch { expected: 3, found: 4 }))
+        ));
+
+        let settings = MiloUnpackSettings { guess_entry_sizes: true };
+        let unpacked_dir = milo.unpack_directory_with_settings(&info, &settings).unwrap();
+
+        assert_eq!(get_dir_summary(&obj_dir), get_dir_summary(&unpacked_dir));
+    }
+
+    #[rstest]
+    fn unpack_directory_with_padding_in_sub_dir_entry_data() {
+        let info = get_info(25);
+
+        let mut sub_dir = create_dir(
+            "head.milo",
+            "RndDir",
+            &[("Mat", "skin.mat")],
+            Some(create_dir_entry("RndDir", &[], &info)),
+            Vec::new()
+        );
+
+        sub_dir.get_entries_mut().insert(0, create_tex_with_padding("skin.tex", &info));
+
+        let obj_dir = create_dir(
+            "character",
+            "Character",
+            &[("Mesh", "body.mesh")],
+            Some(create_dir_entry("Character", &["head.milo"], &info)),
+            vec![sub_dir]
+        );
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+        let unpacked_dir = milo.unpack_directory(&info).unwrap();
+
+        assert_eq!(get_dir_summary(&obj_dir), get_dir_summary(&unpacked_dir));
+        assert_eq!(get_dir_summary(&obj_dir.get_sub_dirs()[0]), get_dir_summary(&unpacked_dir.get_sub_dirs()[0]));
+    }
+
+    #[rstest]
+    fn unpack_directory_with_ambiguous_padding() {
+        let info = get_info(25);
+
+        let mut obj_dir = create_dir(
+            "venue",
+            "ObjectDir",
+            &[("Tex", "floor.tex"), ("Mat", "floor.mat")],
+            Some(create_dir_entry("ObjectDir", &[], &info)),
+            Vec::new()
+        );
+
+        // Padding followed by value that looks like entry version
+        let tex_data = vec![0, 0, 0, 10, 0xAD, 0xDE, 0xAD, 0xDE, 0, 0, 0, 1];
+        if let Object::Packed(packed) = &mut obj_dir.get_entries_mut()[0] {
+            packed.data = tex_data.to_owned();
+        }
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+
+        let result = milo.unpack_directory(&info);
+        assert!(matches!(
+            result.err().as_ref().map(|e| e.get_inner()),
+            Some(crate::Error::MiloUnpack(MiloUnpackError::EntryCountMismatch { expected: 3, found: 4 }))
+        ));
+    }
+
+    #[rstest]
+    fn pack_directory_with_external_paths() {
+        let info = get_info(10);
+
+        let mut obj_dir = create_dir(
+            "",
+            "",
+            &[("Tex", "floor.tex"), ("Mat", "floor.mat")],
+            None,
+            Vec::new()
+        );
+
+        let ObjectDir::ObjectDir(dir) = &mut obj_dir;
+        dir.external_paths = vec![String::from("Floor.mat"), String::from("shared.tex")];
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+        let unpacked_dir = milo.unpack_directory(&info).unwrap();
+
+        // Paths of objects in directory are removed
+        let ObjectDir::ObjectDir(unpacked) = &unpacked_dir;
+        assert_eq!(vec!["shared.tex"], unpacked.external_paths);
+        assert_eq!(get_dir_summary(&obj_dir).2, get_dir_summary(&unpacked_dir).2);
+    }
+
+    #[rstest]
+    fn pack_directory_with_unsupported_entry() {
+        let info = get_info(10);
+
+        let mut obj_dir = create_dir("", "", &[("Tex", "floor.tex")], None, Vec::new());
+        let original_data = vec![0u8, 0, 0, 1, 0, 0, 0, 0];
+
+        let ObjectDir::ObjectDir(dir) = &mut obj_dir;
+        dir.entries.push(Object::CharLipSync(CharLipSync {
+            name: String::from("song.lipsync"),
+            ..Default::default()
+        }));
+
+        let result = MiloArchive::from_object_dir(&obj_dir, &info, None);
+        assert!(matches!(
+            result.err().as_ref().map(|e| e.get_inner()),
+            Some(crate::Error::UnsupportedSave { object_type, .. }) if object_type == "CharLipSync"
+        ));
+
+        // Original data of different system isn't used
+        let ObjectDir::ObjectDir(dir) = &mut obj_dir;
+        dir.original_data.insert(String::from("song.lipsync"), original_data.to_owned());
+        dir.original_info = Some(SystemInfo {
+            endian: IOEndian::Little,
+            ..info
+        });
+
+        let result = MiloArchive::from_object_dir(&obj_dir, &info, None);
+        assert!(matches!(
+            result.err().as_ref().map(|e| e.get_inner()),
+            Some(crate::Error::UnsupportedSave { object_type, .. }) if object_type == "CharLipSync"
+        ));
+
+        // Falls back to data entry was unpacked from
+        let ObjectDir::ObjectDir(dir) = &mut obj_dir;
+        dir.original_info = Some(info);
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+        let unpacked_dir = milo.unpack_directory(&info).unwrap();
+
+        assert_eq!(
+            Some(&original_data),
+            unpacked_dir.get_entries()
+                .iter()
+                .find_map(|e| match e {
+                    Object::Packed(p) if p.name == "song.lipsync" => Some(&p.data),
+                    _ => None
+                })
+        );
+    }
+
+    #[rstest]
+    fn pack_directory_without_dir_entry() {
+        let info = get_info(25);
+
+        let obj_dir = create_dir("venue", "ObjectDir", &[], None, Vec::new());
+        let result = MiloArchive::from_object_dir(&obj_dir, &info, None);
+
+        assert!(matches!(result.unwrap_err().get_inner(), crate::Error::MiloPack(MiloPackError::MissingDirectoryEntry)));
+    }
+
+    fn create_blocked_milo(data: &[u8], block_sizes: &[usize], start_offset: u32, level: u32) -> Vec<u8> {
+        let mut milo_data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut milo_data);
+        let mut writer = BinaryStream::from_stream(&mut stream);
+
+        writer.write_uint32(0xCBBEDEAF).unwrap();
+        writer.write_uint32(start_offset).unwrap();
+        writer.write_uint32(block_sizes.len() as u32).unwrap();
+        writer.write_uint32(*block_sizes.iter().max().unwrap() as u32).unwrap();
+
+        let mut blocks = Vec::new();
+        let mut offset = 0;
+
+        for size in block_sizes.iter() {
+            let mut buffer = vec![0u8; size + 0x100];
+            let block = deflate_zlib_block_with_level(&data[offset..(offset + size)], &mut buffer, level).unwrap();
+
+            writer.write_uint32(block.len() as u32).unwrap();
+            blocks.push(block);
+            offset += size;
+        }
+
+        // Non-zero header gap
+        while writer.pos() < start_offset as u64 {
+            writer.write_uint8(0x11).unwrap();
+        }
+
+        for block in blocks.iter() {
+            writer.write_bytes(block).unwrap();
+        }
+
+        milo_data
+    }
+
+    fn create_unsorted_milo(info: &SystemInfo) -> (ObjectDir, Vec<u8>) {
+        let mut obj_dir = create_dir(
+            "venue",
+            "ObjectDir",
+            &[("Mat", "floor.mat"), ("Tex", "floor.tex"), ("Mesh", "floor.mesh")],
+            Some(create_dir_entry("ObjectDir", &[], info)),
+            Vec::new()
+        );
+
+        let ObjectDir::ObjectDir(dir) = &mut obj_dir;
+        dir.string_table_count = 20;
+        dir.string_table_size = 100;
+
+        // Last entry spans many blocks
+        if let Some(Object::Packed(packed)) = dir.entries.last_mut() {
+            packed.data.extend(vec![0x11; 0x400]);
+        }
+
+        // Write uncompressed directory in original order
+        let original = MiloArchive {
+            structure: MiloArchiveStructure::Uncompressed,
+            data: Vec::new(),
+            pending_blocks: None,
+            path: None,
+        };
+
+        let settings = MiloPackSettings {
+            original: Some(&original),
+            ..Default::default()
+        };
+
+        let milo = MiloArchive::from_object_dir_with_settings(&obj_dir, info, &settings).unwrap();
+        (obj_dir, milo.data)
+    }
+
+    fn write_milo(milo: &MiloArchive) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
+        milo.write_to_stream(&mut stream).unwrap();
+
+        data
+    }
+
+    #[rstest]
+    fn save_preserved_milo_is_byte_identical() {
+        let info = get_info(25);
+        let (obj_dir, dir_data) = create_unsorted_milo(&info);
+        let milo_data = create_blocked_milo(&dir_data, &[10, dir_data.len() - 10], 0x40, 1);
+
+        let mut stream = MemoryStream::from_slice_as_read(&milo_data);
+        let settings = MiloReadSettings {
+            preserve_layout: true,
+            ..Default::default()
+        };
+        let milo = MiloArchive::from_stream_with_settings(&mut stream, &settings).unwrap();
+
+        let MiloArchiveStructure::Blocked(block_info) = &milo.structure else {
+            panic!("Expected blocked milo");
+        };
+        assert_eq!(0x40, block_info.get_start_offset());
+        assert_eq!(&[10, dir_data.len() - 10], block_info.get_block_sizes());
+        assert!(block_info.get_compression_level().is_some());
+
+        let unpacked_dir = milo.unpack_directory(&info).unwrap();
+        assert_eq!(get_dir_summary(&obj_dir), get_dir_summary(&unpacked_dir));
+
+        let settings = MiloPackSettings {
+            original: Some(&milo),
+            ..Default::default()
+        };
+        let saved_milo = MiloArchive::from_object_dir_with_settings(&unpacked_dir, &info, &settings).unwrap();
+
+        assert_eq!(milo_data, write_milo(&saved_milo));
+    }
+
+    #[rstest]
+    fn save_preserved_milo_with_changed_entry() {
+        let info = get_info(25);
+        let (_, dir_data) = create_unsorted_milo(&info);
+        let milo_data = create_blocked_milo(&dir_data, &[10, dir_data.len() - 10], 0x40, 1);
+
+        let mut stream = MemoryStream::from_slice_as_read(&milo_data);
+        let settings = MiloReadSettings {
+            preserve_layout: true,
+            ..Default::default()
+        };
+        let milo = MiloArchive::from_stream_with_settings(&mut stream, &settings).unwrap();
+
+        let mut unpacked_dir = milo.unpack_directory(&info).unwrap();
+        if let Object::Packed(packed) = &mut unpacked_dir.get_entries_mut()[2] {
+            packed.data.extend_from_slice(&[1, 2, 3, 4]);
+        }
+
+        let settings = MiloPackSettings {
+            original: Some(&milo),
+            ..Default::default()
+        };
+        let saved_milo = MiloArchive::from_object_dir_with_settings(&unpacked_dir, &info, &settings).unwrap();
+        let saved_data = write_milo(&saved_milo);
+
+        // Header gap and unchanged first block are kept
+        let first_block_size = u32::from_le_bytes(milo_data[16..20].try_into().unwrap()) as usize;
+        let first_block_end = 0x40 + first_block_size;
+
+        assert_eq!(milo_data[..8], saved_data[..8]);
+        assert_eq!(milo_data[24..first_block_end], saved_data[24..first_block_end]);
+
+        let mut stream = MemoryStream::from_slice_as_read(&saved_data);
+        let saved_dir = MiloArchive::from_stream(&mut stream)
+            .unwrap()
+            .unpack_directory(&info)
+            .unwrap();
+
+        assert_eq!(get_dir_summary(&unpacked_dir), get_dir_summary(&saved_dir));
+    }
+
+    #[rstest]
+    fn save_preserved_milo_with_added_entries_updates_string_table() {
+        let info = get_info(25);
+        let (_, dir_data) = create_unsorted_milo(&info);
+        let milo_data = create_blocked_milo(&dir_data, &[dir_data.len()], 0x40, 1);
+
+        let mut stream = MemoryStream::from_slice_as_read(&milo_data);
+        let settings = MiloReadSettings {
+            preserve_layout: true,
+            ..Default::default()
+        };
+        let milo = MiloArchive::from_stream_with_settings(&mut stream, &settings).unwrap();
+
+        let mut unpacked_dir = milo.unpack_directory(&info).unwrap();
+        for i in 0..10 {
+            unpacked_dir.get_entries_mut().push(Object::Packed(PackedObject {
+                name: format!("added_entry_with_long_name_{i}.tex"),
+                object_type: String::from("Tex"),
+                data: vec![0, 0, 0, 2]
+            }));
+        }
+
+        let settings = MiloPackSettings {
+            original: Some(&milo),
+            ..Default::default()
+        };
+        let saved_milo = MiloArchive::from_object_dir_with_settings(&unpacked_dir, &info, &settings).unwrap();
+
+        let saved_data = write_milo(&saved_milo);
+        let mut stream = MemoryStream::from_slice_as_read(&saved_data);
+        let ObjectDir::ObjectDir(saved_dir) = MiloArchive::from_stream(&mut stream)
+            .unwrap()
+            .unpack_directory(&info)
+            .unwrap();
+
+        // Original values (20, 100) are too small for added entries
+        let name_sizes = saved_dir.entries
+            .iter()
+            .map(|e| e.get_name().len() + 1)
+            .sum::<usize>() + saved_dir.name.len() + 1;
+
+        assert_eq!(13, saved_dir.entries.len());
+        assert_eq!(28, saved_dir.string_table_count);
+        assert_eq!(name_sizes as u32, saved_dir.string_table_size);
+    }
+
+    #[rstest]
+    fn save_preserved_v10_milo_with_changed_external_paths() {
+        let info = get_info(10);
+        let mut obj_dir = create_dir("venue", "ObjectDir", &[("Mat", "floor.mat"), ("Tex", "floor.tex")], None, Vec::new());
+
+        let ObjectDir::ObjectDir(dir) = &mut obj_dir;
+        dir.external_paths = vec![String::from("shared.tex"), String::from("floor.tex")];
+
+        let original = MiloArchive {
+            structure: MiloArchiveStructure::Uncompressed,
+            data: Vec::new(),
+            pending_blocks: None,
+            path: None,
+        };
+
+        let settings = MiloPackSettings {
+            original: Some(&original),
+            ..Default::default()
+        };
+        let milo = MiloArchive::from_object_dir_with_settings(&obj_dir, &info, &settings).unwrap();
+
+        // Object in directory is no longer written as external path
+        let ObjectDir::ObjectDir(saved_dir) = milo.unpack_directory(&info).unwrap();
+        assert_eq!(vec!["shared.tex"], saved_dir.external_paths);
+    }
+
+    #[rstest]
+    fn read_entries_from_lazy_milo() {
+        let info = get_info(25);
+        let (obj_dir, dir_data) = create_unsorted_milo(&info);
+
+        // Many small blocks
+        let block_sizes = dir_data
+            .chunks(16)
+            .map(|c| c.len())
+            .collect::<Vec<_>>();
+        let milo_data = create_blocked_milo(&dir_data, &block_sizes, 0x800, 9);
+
+        let mut stream = MemoryStream::from_slice_as_read(&milo_data);
+        let settings = MiloReadSettings {
+            lazy_blocks: true,
+            ..Default::default()
+        };
+        let mut milo = MiloArchive::from_stream_with_settings(&mut stream, &settings).unwrap();
+
+        assert_eq!(block_sizes.len() - 1, milo.get_pending_block_count());
+        assert_eq!(Some((IOEndian::Big, 25)), milo.guess_endian_version());
+
+        let entry_names = milo.read_entry_names(&info).unwrap();
+        assert_eq!(
+            vec![
+                (String::from("Mat"), String::from("floor.mat")),
+                (String::from("Tex"), String::from("floor.tex")),
+                (String::from("Mesh"), String::from("floor.mesh"))
+            ],
+            entry_names
+        );
+
+        let entry = milo.read_entry(&info, "floor.mat").unwrap();
+        let pending_count = milo.get_pending_block_count();
+
+        assert_eq!(get_dir_summary(&obj_dir).2[0], (entry.object_type, entry.name, entry.data));
+        assert!(pending_count > 0);
+        assert!(matches!(
+            milo.read_entry(&info, "floor.bmp").unwrap_err().get_inner(),
+            crate::Error::MiloUnpack(MiloUnpackError::EntryNotFound { .. })
+        ));
+        assert!(matches!(
+            milo.unpack_directory(&info).err().unwrap().get_inner(),
+            crate::Error::MiloBlockStructure(MiloBlockStructureError::BlocksNotInflated { count }) if *count == pending_count
+        ));
+
+        milo.inflate_all().unwrap();
+
+        assert_eq!(0, milo.get_pending_block_count());
+        assert_eq!(dir_data, milo.data);
+        assert_eq!(get_dir_summary(&obj_dir), get_dir_summary(&milo.unpack_directory(&info).unwrap()));
+    }
+
+    #[rstest]
+    fn read_entry_names_from_lazy_milo_with_wrong_version() {
+        let info = get_info(25);
+        let (_, dir_data) = create_unsorted_milo(&info);
+
+        let block_sizes = dir_data
+            .chunks(16)
+            .map(|c| c.len())
+            .collect::<Vec<_>>();
+        let milo_data = create_blocked_milo(&dir_data, &block_sizes, 0x800, 9);
+
+        let mut stream = MemoryStream::from_slice_as_read(&milo_data);
+        let settings = MiloReadSettings {
+            lazy_blocks: true,
+            ..Default::default()
+        };
+        let mut milo = MiloArchive::from_stream_with_settings(&mut stream, &settings).unwrap();
+        let pending_count = milo.get_pending_block_count();
+
+        // Parse error is returned without inflating other blocks
+        assert!(matches!(
+            milo.read_entry_names(&get_info(24)).unwrap_err().get_inner(),
+            crate::Error::MiloUnpack(MiloUnpackError::UnsupportedDirectoryVersion { version: 25 })
+        ));
+        assert_eq!(pending_count, milo.get_pending_block_count());
+    }
+
+    #[rstest]
+    #[case(MiloStructureType::Blocked, Some(BlockType::TypeA))]
+    #[case(MiloStructureType::Blocked, Some(BlockType::TypeB))]
+    #[case(MiloStructureType::Blocked, Some(BlockType::TypeC))]
+    #[case(MiloStructureType::Blocked, Some(BlockType::TypeD))]
+    #[case(MiloStructureType::GZIP, None)]
+    #[case(MiloStructureType::Uncompressed, None)]
+    fn write_and_read_milo_structure(#[case] structure_type: MiloStructureType, #[case] block_type: Option<BlockType>) {
+        let info = get_info(25);
+        let (obj_dir, _) = create_unsorted_milo(&info);
+
+        let settings = MiloPackSettings {
+            structure_type,
+            block_type,
+            ..Default::default()
+        };
+        let milo = MiloArchive::from_object_dir_with_settings(&obj_dir, &info, &settings).unwrap();
+        let milo_data = write_milo(&milo);
+
+        let mut stream = MemoryStream::from_slice_as_read(&milo_data);
+        let read_milo = MiloArchive::from_stream(&mut stream).unwrap();
+
+        let read_structure_type = match read_milo.get_structure() {
+            MiloArchiveStructure::Blocked(block_info) => {
+                assert_eq!(block_type, Some(block_info.get_block_type()));
+                MiloStructureType::Blocked
+            },
+            MiloArchiveStructure::GZIP => MiloStructureType::GZIP,
+            MiloArchiveStructure::Uncompressed => MiloStructureType::Uncompressed,
+        };
+
+        assert_eq!(structure_type, read_structure_type);
+        assert_eq!(milo.data, read_milo.data);
+    }
+
+    #[rstest]
+    #[case(&[0, 0, 0, 25, 0, 0, 0, 0], true)]
+    #[case(&[24, 0, 0, 0, 0, 0, 0, 0], true)]
+    #[case(&[0x1F, 0x8B, 0x08, 0x08, 0, 0, 0, 0], false)]
+    #[case(&[0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0], false)]
+    fn read_uncompressed_milo(#[case] data: &[u8], #[case] is_uncompressed: bool) {
+        let mut stream = MemoryStream::from_slice_as_read(data);
+        let result = MiloArchive::from_stream(&mut stream);
+
+        match is_uncompressed {
+            true => {
+                let milo = result.unwrap();
+
+                assert!(matches!(milo.get_structure(), MiloArchiveStructure::Uncompressed));
+                assert_eq!(data, milo.data);
+            },
+            _ => assert!(result.is_err())
+        }
+    }
+
+    #[rstest]
+    #[case(&[10, 20], 30, vec![10, 20])]
+    #[case(&[10, 20], 34, vec![10, 24])]
+    #[case(&[10, 20], 8, vec![8])]
+    #[case(&[], 0x20001, vec![0x20000, 1])]
+    fn get_preserved_block_sizes_test(#[case] original_sizes: &[usize], #[case] data_size: usize, #[case] expected: Vec<usize>) {
+        assert_eq!(expected, get_preserved_block_sizes(original_sizes, data_size));
+    }
+
+    #[rstest]
+    #[case(&[], vec![])]
+    #[case(&[0xAD, 0xDE, 0xAD, 0xDE], vec![0])]
+    #[case(&[1, 0xAD, 0xDE, 0xAD, 0xDE, 0xAD, 0xDE], vec![1])]
+    #[case(&[0xAD, 0xDE, 0xAD, 0xDE, 0xAD, 0xDE, 0xAD, 0xDE], vec![0, 4])]
+    fn find_padding_offsets_test(#[case] data: &[u8], #[case] expected: Vec<usize>) {
+        assert_eq!(expected, find_padding_offsets(data));
+    }
+}
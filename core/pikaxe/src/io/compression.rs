@@ -2,8 +2,9 @@
 
 use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
 use flate2::read::{DeflateDecoder, GzDecoder};
+use flate2::write::GzEncoder;
 use std::error::Error;
-use std::io::Read;
+use std::io::{Read, Write};
 
 
 pub fn inflate_zlib_block(data: &[u8], buffer: &mut [u8]) -> Result<Vec<u8>, Box<dyn Error>> {
@@ -123,7 +124,12 @@ pub fn inflate_deflate_block_no_buffer(data: &[u8]) -> Result<Vec<u8>, Box<dyn E
 }
 
 pub fn deflate_zlib_block(data: &[u8], buffer: &mut [u8]) -> Result<Vec<u8>, Box<dyn Error>> {
-    let mut compressor = Compress::new(Compression::best(), false);
+    deflate_zlib_block_with_level(data, buffer, Compression::best().level())
+}
+
+/// Compresses data with level (0-9). Returns empty data if compressed data doesn't fit in buffer.
+pub fn deflate_zlib_block_with_level(data: &[u8], buffer: &mut [u8], level: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut compressor = Compress::new(Compression::new(level), false);
     let status = compressor.compress(data, buffer, FlushCompress::Finish)?;
 
     match &status {
@@ -139,4 +145,11 @@ pub fn deflate_zlib_block(data: &[u8], buffer: &mut [u8]) -> Result<Vec<u8>, Box
             Ok(vec![0u8; 0])
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn deflate_gzip_block_with_level(data: &[u8], level: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+    encoder.write_all(data)?;
+
+    Ok(encoder.finish()?)
+}
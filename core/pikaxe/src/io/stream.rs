@@ -4,6 +4,7 @@ use std::io::prelude::*;
 pub use std::io::SeekFrom;
 use std::path::Path;
 pub use half::f16;
+use thiserror::Error as ThisError;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum IOEndian {
@@ -11,6 +12,58 @@ pub enum IOEndian {
     Big,
 }
 
+#[derive(Debug, ThisError)]
+pub enum StreamError {
+    #[error("Can't read {length} bytes at offset 0x{offset:X}: {source}")]
+    Read {
+        offset: u64,
+        length: usize,
+        source: std::io::Error
+    },
+    #[error("Can't write {length} bytes at offset 0x{offset:X}: {source}")]
+    Write {
+        offset: u64,
+        length: usize,
+        source: std::io::Error
+    },
+    #[error("Can't seek from offset 0x{offset:X}: {source}")]
+    Seek {
+        offset: u64,
+        source: std::io::Error
+    },
+    #[error("Invalid UTF-8 string at offset 0x{offset:X}")]
+    InvalidString {
+        offset: u64
+    },
+}
+
+impl StreamError {
+    /// Gets offset of stream where error occurred
+    pub fn get_offset(&self) -> u64 {
+        match self {
+            StreamError::Read { offset, .. }
+                | StreamError::Write { offset, .. }
+                | StreamError::Seek { offset, .. }
+                | StreamError::InvalidString { offset } => *offset
+        }
+    }
+
+    /// Wraps error from underlying stream (errors from nested binary streams are kept as is)
+    pub(crate) fn from_read_error(error: Box<dyn Error>, offset: u64, length: usize) -> StreamError {
+        match error.downcast::<StreamError>() {
+            Ok(error) => *error,
+            Err(error) => StreamError::Read { offset, length, source: into_io_error(error) }
+        }
+    }
+}
+
+fn into_io_error(error: Box<dyn Error>) -> std::io::Error {
+    match error.downcast::<std::io::Error>() {
+        Ok(error) => *error,
+        Err(error) => std::io::Error::other(error.to_string())
+    }
+}
+
 pub trait Stream {
     // io
     fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, Box<dyn Error>>;
@@ -64,39 +117,33 @@ impl<'a> MemoryStream<'a> {
         }
     }
 
-    // TODO:: Safely handle possible panic
-    fn get_slice(&'a self, pos: u64, size: usize) -> &'a [u8] {
+    fn get_slice(&'a self, pos: u64, size: usize) -> Result<&'a [u8], std::io::Error> {
         let pos = pos as usize;
 
-        match &self.data {
-            MemoryData::Read(data) => {
-                &(*data)[pos..(pos + size)]
-            },
-            MemoryData::ReadWrite(vec) => {
-                &(*vec)[pos..(pos + size)]
-            },
-            MemoryData::ReadWriteOwned(vec) => {
-                &vec[pos..(pos + size)]
-            }
-        }
+        let data: &[u8] = match &self.data {
+            MemoryData::Read(data) => data,
+            MemoryData::ReadWrite(vec) => vec,
+            MemoryData::ReadWriteOwned(vec) => vec,
+        };
+
+        pos.checked_add(size)
+            .and_then(|end| data.get(pos..end))
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))
     }
 }
 
 impl<'a> Stream for MemoryStream<'a> {
     fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, Box<dyn Error>> {
-        let mut buffer_vec = vec![0u8; length];
-        let data = self.get_slice(self.position, length);
-
-        buffer_vec.clone_from_slice(data); // TODO:: Safely handle possible panic
+        let data = self.get_slice(self.position, length)?.to_vec();
         self.position += length as u64;
 
-        Ok(buffer_vec)
+        Ok(data)
     }
 
     fn read_bytes_into_slice(&mut self, buffer: &mut [u8]) -> Result<(), Box<dyn Error>> {
-        let data = self.get_slice(self.position, buffer.len());
+        let data = self.get_slice(self.position, buffer.len())?;
 
-        buffer.clone_from_slice(data); // TODO:: Safely handle possible panic
+        buffer.clone_from_slice(data);
         self.position += buffer.len() as u64;
 
         Ok(())
@@ -293,17 +340,30 @@ pub struct BinaryStream<'a> {
     stream: &'a mut dyn Stream
 }
 
+// Errors from underlying stream are wrapped with offset
 impl<'a> Stream for BinaryStream<'a> {
     fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+        let offset = self.stream.pos();
+
         self.stream.read_bytes(length)
+            .map_err(|e| StreamError::from_read_error(e, offset, length).into())
     }
 
     fn read_bytes_into_slice(&mut self, buffer: &mut [u8]) -> Result<(), Box<dyn Error>> {
+        let offset = self.stream.pos();
+
         self.stream.read_bytes_into_slice(buffer)
+            .map_err(|e| StreamError::from_read_error(e, offset, buffer.len()).into())
     }
 
     fn write_bytes(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        let offset = self.stream.pos();
+
         self.stream.write_bytes(data)
+            .map_err(|e| match e.is::<StreamError>() {
+                true => e,
+                false => Box::new(StreamError::Write { offset, length: data.len(), source: into_io_error(e) }),
+            })
     }
 
     fn pos(&self) -> u64 {
@@ -319,7 +379,13 @@ impl<'a> Stream for BinaryStream<'a> {
     }
 
     fn seek(&mut self, pos: SeekFrom) -> Result<(), Box<dyn Error>> {
+        let offset = self.stream.pos();
+
         self.stream.seek(pos)
+            .map_err(|e| match e.is::<StreamError>() {
+                true => e,
+                false => Box::new(StreamError::Seek { offset, source: into_io_error(e) }),
+            })
     }
 }
 
@@ -498,13 +564,15 @@ impl<'a> BinaryStream<'a> {
     // Read strings
     pub fn read_prefixed_string(&mut self) -> Result<String, Box<dyn Error>> {
         let length = self.read_int32()?;
+        let offset = self.pos();
         let raw_bytes = self.read_bytes(length as usize)?;
 
-        // TODO: Replace with better one (FromUtf8Error message is awful)
-        Ok(String::from_utf8(raw_bytes)?)
+        String::from_utf8(raw_bytes)
+            .map_err(|_| Box::new(StreamError::InvalidString { offset }) as Box<dyn Error>)
     }
 
     pub fn read_null_terminated_string(&mut self) -> Result<String, Box<dyn Error>> {
+        let offset = self.pos();
         let mut raw_bytes = Vec::new();
 
         loop {
@@ -516,8 +584,8 @@ impl<'a> BinaryStream<'a> {
             raw_bytes.push(b);
         }
 
-        // TODO: Replace with better one (FromUtf8Error message is awful)
-        Ok(String::from_utf8(raw_bytes)?)
+        String::from_utf8(raw_bytes)
+            .map_err(|_| Box::new(StreamError::InvalidString { offset }) as Box<dyn Error>)
     }
 }
 
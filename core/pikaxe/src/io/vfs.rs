@@ -0,0 +1,561 @@
+use crate::SystemInfo;
+use crate::ark::Ark;
+use crate::io::{FileSearchDepth, MemoryStream, MiloArchive, PathFinder};
+use crate::scene::{DIR_INFO_FILE_NAME, ObjectDir};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error as ThisError;
+
+pub const ARK_SCHEME: &str = "ark://";
+
+#[derive(Debug, ThisError)]
+pub enum VfsError {
+    #[error("File \"{path}\" not found")]
+    FileNotFound {
+        path: String
+    },
+    #[error("Can't read file \"{path}\"")]
+    CantReadFile {
+        path: String
+    },
+}
+
+/// Read-only file system that addresses files by `/` separated path relative to its root.
+/// Paths can optionally be prefixed with `ark://`.
+pub trait VirtualFileSystem {
+    fn exists(&self, path: &str) -> bool;
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>, VfsError>;
+    /// Returns paths of all files under directory (recursive)
+    fn list_files(&self, dir_path: &str) -> Vec<String>;
+
+    fn read(&self, path: &str) -> Result<Vec<u8>, VfsError> {
+        let mut data = Vec::new();
+
+        self.open(path)?
+            .read_to_end(&mut data)
+            .map_err(|_| VfsError::CantReadFile { path: path.to_owned() })?;
+
+        Ok(data)
+    }
+}
+
+/// Normalizes path for comparison (strips scheme, uses forward slashes, trims leading/trailing slashes)
+pub fn normalize_vfs_path(path: &str) -> String {
+    let path = path
+        .strip_prefix(ARK_SCHEME)
+        .unwrap_or(path)
+        .replace('\\', "/");
+
+    path
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub(crate) fn is_in_dir(path: &str, dir_path: &str) -> bool {
+    dir_path.is_empty() || path
+        .get(..dir_path.len())
+        .map(|p| p.eq_ignore_ascii_case(dir_path) && path[dir_path.len()..].starts_with('/'))
+        .unwrap_or_default()
+}
+
+/// Loose files on disk (case-insensitive to match arks)
+#[derive(Debug)]
+pub struct DirectoryFileSystem {
+    root: PathBuf,
+}
+
+impl DirectoryFileSystem {
+    pub fn new<T: Into<PathBuf>>(root: T) -> DirectoryFileSystem {
+        DirectoryFileSystem {
+            root: root.into()
+        }
+    }
+
+    /// Finds path on disk, matching each name case-insensitively if exact name isn't found.
+    /// Names that aren't plain file names (ex: `..` or drive prefixes) are rejected so path stays under root.
+    fn find_path(&self, path: &str) -> Option<PathBuf> {
+        let mut current = self.root.to_owned();
+
+        for name in normalize_vfs_path(path).split('/').filter(|s| !s.is_empty()) {
+            let mut components = Path::new(name).components();
+            if !matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) {
+                return None;
+            }
+
+            let exact_path = current.join(name);
+
+            current = match exact_path.exists() {
+                true => exact_path,
+                false => std::fs::read_dir(&current)
+                    .ok()?
+                    .filter_map(|e| e.ok())
+                    .find(|e| e
+                        .file_name()
+                        .to_str()
+                        .is_some_and(|n| n.eq_ignore_ascii_case(name)))
+                    .map(|e| e.path())?
+            };
+        }
+
+        Some(current)
+    }
+
+    fn find_file_path(&self, path: &str) -> Option<PathBuf> {
+        self.find_path(path).filter(|p| p.is_file())
+    }
+}
+
+impl VirtualFileSystem for DirectoryFileSystem {
+    fn exists(&self, path: &str) -> bool {
+        self.find_file_path(path).is_some()
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>, VfsError> {
+        let file_path = self
+            .find_file_path(path)
+            .ok_or_else(|| VfsError::FileNotFound { path: path.to_owned() })?;
+
+        let file = File::open(&file_path)
+            .map_err(|_| VfsError::CantReadFile { path: path.to_owned() })?;
+
+        Ok(Box::new(BufReader::new(file)))
+    }
+
+    fn list_files(&self, dir_path: &str) -> Vec<String> {
+        let Some(dir_path) = self.find_path(dir_path) else {
+            return Vec::new();
+        };
+
+        dir_path
+            .find_files_with_depth(FileSearchDepth::Unlimited)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|f| f
+                .strip_prefix(&self.root)
+                .ok()
+                .and_then(|p| p.to_str())
+                .map(normalize_vfs_path))
+            .collect()
+    }
+}
+
+/// Entries of hdr/ark archive (case-insensitive)
+#[derive(Debug)]
+pub struct ArkFileSystem {
+    ark: Ark,
+    lookup: HashMap<String, u32>, // Lowercase path -> entry id
+}
+
+impl ArkFileSystem {
+    pub fn new(ark: Ark) -> ArkFileSystem {
+        let lookup = ark.entries
+            .iter()
+            .map(|e| (normalize_vfs_path(&e.path).to_ascii_lowercase(), e.id))
+            .collect();
+
+        ArkFileSystem {
+            ark,
+            lookup
+        }
+    }
+
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<ArkFileSystem, crate::ark::ArkReadError> {
+        Ark::from_path(path).map(ArkFileSystem::new)
+    }
+
+    pub fn get_ark(&self) -> &Ark {
+        &self.ark
+    }
+
+    fn get_entry_id(&self, path: &str) -> Option<u32> {
+        self.lookup
+            .get(&normalize_vfs_path(path).to_ascii_lowercase())
+            .copied()
+    }
+}
+
+impl VirtualFileSystem for ArkFileSystem {
+    fn exists(&self, path: &str) -> bool {
+        self.get_entry_id(path).is_some()
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>, VfsError> {
+        let id = self
+            .get_entry_id(path)
+            .ok_or_else(|| VfsError::FileNotFound { path: path.to_owned() })?;
+
+        self.ark
+            .get_reader(id)
+            .map_err(|_| VfsError::CantReadFile { path: path.to_owned() })
+    }
+
+    fn list_files(&self, dir_path: &str) -> Vec<String> {
+        let dir_path = normalize_vfs_path(dir_path);
+
+        self.ark.entries
+            .iter()
+            .map(|e| normalize_vfs_path(&e.path))
+            .filter(|p| is_in_dir(p, &dir_path))
+            .collect()
+    }
+}
+
+/// Objects of milo archive laid out like `milo2dir` output: `<type>/<name>` per entry (including directory entry),
+/// inline sub directories in own folders and `dir.dta` per directory. Can be read back with `ObjectDir::from_vfs`.
+#[derive(Debug)]
+pub struct MiloFileSystem {
+    files: Vec<(String, Vec<u8>)>,
+    lookup: HashMap<String, usize>, // Lowercase path -> file index
+}
+
+impl MiloFileSystem {
+    /// Unpacked entries are packed with system info (skipped if they can't be packed)
+    pub fn new(obj_dir: &ObjectDir, info: &SystemInfo) -> MiloFileSystem {
+        let mut files = Vec::new();
+        add_milo_dir_files(obj_dir, "", info, &mut files);
+
+        let lookup = files
+            .iter()
+            .enumerate()
+            .map(|(i, (path, _))| (path.to_ascii_lowercase(), i))
+            .collect();
+
+        MiloFileSystem {
+            files,
+            lookup
+        }
+    }
+
+    pub fn from_milo(milo: &MiloArchive, info: &SystemInfo) -> Result<MiloFileSystem, crate::Error> {
+        let obj_dir = milo.unpack_directory(info)?;
+        Ok(MiloFileSystem::new(&obj_dir, info))
+    }
+
+    pub fn from_path<T: AsRef<Path>>(path: T, info: &SystemInfo) -> Result<MiloFileSystem, crate::Error> {
+        let milo = MiloArchive::from_path(path)?;
+        MiloFileSystem::from_milo(&milo, info)
+    }
+
+    /// Reads milo from other file system (ex: `ark://songs/gen/song.milo_xbox`)
+    pub fn from_vfs(fs: &dyn VirtualFileSystem, path: &str, info: &SystemInfo) -> Result<MiloFileSystem, crate::Error> {
+        let data = fs.read(path)?;
+        let mut stream = MemoryStream::from_slice_as_read(&data);
+
+        let milo = MiloArchive::from_stream(&mut stream)?;
+        MiloFileSystem::from_milo(&milo, info)
+    }
+
+    fn get_file(&self, path: &str) -> Option<&(String, Vec<u8>)> {
+        self.lookup
+            .get(&normalize_vfs_path(path).to_ascii_lowercase())
+            .map(|i| &self.files[*i])
+    }
+}
+
+fn add_milo_dir_files(obj_dir: &ObjectDir, dir_path: &str, info: &SystemInfo, files: &mut Vec<(String, Vec<u8>)>) {
+    let ObjectDir::ObjectDir(dir) = obj_dir;
+
+    let join_path = |name: &str| match dir_path.is_empty() {
+        true => name.to_owned(),
+        _ => format!("{dir_path}/{name}")
+    };
+
+    for entry in dir.entries.iter() {
+        if let Ok(packed) = entry.try_pack(info) {
+            files.push((join_path(&format!("{}/{}", packed.object_type, packed.name)), packed.data));
+        }
+    }
+
+    if let Some(dir_entry) = &dir.dir_entry {
+        files.push((join_path(&format!("{}/{}", dir.dir_type, dir.name)), dir_entry.data.to_owned()));
+    }
+
+    let mut dir_info = Vec::new();
+    if obj_dir.create_dir_info().print(&mut dir_info).is_ok() {
+        files.push((join_path(DIR_INFO_FILE_NAME), dir_info));
+    }
+
+    for sub_dir in dir.sub_dirs.iter() {
+        let ObjectDir::ObjectDir(sub_dir_base) = sub_dir;
+        add_milo_dir_files(sub_dir, &join_path(&sub_dir_base.name), info, files);
+    }
+}
+
+impl VirtualFileSystem for MiloFileSystem {
+    fn exists(&self, path: &str) -> bool {
+        self.get_file(path).is_some()
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>, VfsError> {
+        let (_, data) = self
+            .get_file(path)
+            .ok_or_else(|| VfsError::FileNotFound { path: path.to_owned() })?;
+
+        Ok(Box::new(Cursor::new(data.as_slice())))
+    }
+
+    fn list_files(&self, dir_path: &str) -> Vec<String> {
+        let dir_path = normalize_vfs_path(dir_path);
+
+        self.files
+            .iter()
+            .map(|(path, _)| path.to_owned())
+            .filter(|p| is_in_dir(p, &dir_path))
+            .collect()
+    }
+}
+
+/// Stack of file systems where files from later layers shadow files from earlier layers
+/// (i.e. add ark first then loose mod directory)
+#[derive(Default)]
+pub struct OverlayFileSystem {
+    layers: Vec<Box<dyn VirtualFileSystem>>,
+}
+
+impl OverlayFileSystem {
+    pub fn new() -> OverlayFileSystem {
+        OverlayFileSystem::default()
+    }
+
+    pub fn and_layer<T: VirtualFileSystem + 'static>(mut self, layer: T) -> Self {
+        self.push_layer(layer);
+        self
+    }
+
+    pub fn push_layer<T: VirtualFileSystem + 'static>(&mut self, layer: T) {
+        self.layers.push(Box::new(layer));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    fn find_layer(&self, path: &str) -> Option<&dyn VirtualFileSystem> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|l| l.exists(path))
+            .map(|l| l.as_ref())
+    }
+}
+
+impl VirtualFileSystem for OverlayFileSystem {
+    fn exists(&self, path: &str) -> bool {
+        self.find_layer(path).is_some()
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>, VfsError> {
+        self.find_layer(path)
+            .ok_or_else(|| VfsError::FileNotFound { path: path.to_owned() })?
+            .open(path)
+    }
+
+    fn list_files(&self, dir_path: &str) -> Vec<String> {
+        let mut files = Vec::new();
+        let mut found = std::collections::HashSet::new();
+
+        for layer in self.layers.iter().rev() {
+            for file in layer.list_files(dir_path) {
+                if found.insert(file.to_ascii_lowercase()) {
+                    files.push(file);
+                }
+            }
+        }
+
+        files.sort();
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+    use crate::ark::ArkBuilder;
+    use crate::scene::{Object, ObjectDirBase, ObjectDirEntry, PackedObject};
+
+    fn create_test_dir(name: &str) -> PathBuf {
+        let dir_path = std::env::temp_dir().join("pikaxe_tests").join(name);
+
+        if dir_path.exists() {
+            std::fs::remove_dir_all(&dir_path).unwrap();
+        }
+
+        std::fs::create_dir_all(&dir_path).unwrap();
+        dir_path
+    }
+
+    fn write_file(path: &Path, data: &[u8]) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, data).unwrap();
+    }
+
+    #[rstest]
+    #[case("ark://songs/gen/song.milo_xbox", "songs/gen/song.milo_xbox")]
+    #[case("songs\\gen\\song.milo_xbox", "songs/gen/song.milo_xbox")]
+    #[case("/songs//gen/./song.milo_xbox", "songs/gen/song.milo_xbox")]
+    #[case("ark://", "")]
+    fn normalize_vfs_path_test(#[case] path: &str, #[case] expected: &str) {
+        assert_eq!(expected, normalize_vfs_path(path));
+    }
+
+    #[rstest]
+    fn directory_file_system() {
+        let dir_path = create_test_dir("vfs_directory_file_system");
+        write_file(&dir_path.join("config/rb.dta"), b"(a)");
+        write_file(&dir_path.join("songs/gen/song.milo_xbox"), b"milo");
+
+        let fs = DirectoryFileSystem::new(&dir_path);
+
+        assert!(fs.exists("config/rb.dta"));
+        assert!(fs.exists("ark://songs/gen/song.milo_xbox"));
+        assert!(!fs.exists("songs/gen"));
+        assert_eq!(b"milo".to_vec(), fs.read("ark://songs/gen/song.milo_xbox").unwrap());
+        assert!(matches!(fs.read("missing.dta"), Err(VfsError::FileNotFound { .. })));
+
+        assert_eq!(vec!["songs/gen/song.milo_xbox"], fs.list_files("songs"));
+    }
+
+    #[rstest]
+    fn directory_file_system_is_case_insensitive() {
+        let dir_path = create_test_dir("vfs_directory_file_system_case");
+        write_file(&dir_path.join("Songs/Gen/Song.milo_xbox"), b"milo");
+
+        let fs = DirectoryFileSystem::new(&dir_path);
+
+        assert!(fs.exists("ark://songs/gen/song.milo_xbox"));
+        assert_eq!(b"milo".to_vec(), fs.read("SONGS/GEN/SONG.MILO_XBOX").unwrap());
+        assert_eq!(vec!["Songs/Gen/Song.milo_xbox"], fs.list_files("songs/gen"));
+        assert!(!fs.exists("songs/gen/other.milo_xbox"));
+    }
+
+    #[rstest]
+    #[case("../outside.dta")]
+    #[case("config/../../outside.dta")]
+    #[case("ark://..\\outside.dta")]
+    fn directory_file_system_stays_in_root(#[case] path: &str) {
+        let dir_path = create_test_dir("vfs_directory_file_system_root");
+        write_file(&dir_path.join("root").join("config/rb.dta"), b"(a)");
+        write_file(&dir_path.join("outside.dta"), b"(outside)");
+
+        let fs = DirectoryFileSystem::new(dir_path.join("root"));
+
+        assert!(!fs.exists(path));
+        assert!(matches!(fs.read(path), Err(VfsError::FileNotFound { .. })));
+        assert!(fs.list_files("..").is_empty());
+    }
+
+    fn create_test_milo_dir() -> ObjectDir {
+        let mut sub_dir = ObjectDirBase::new();
+        sub_dir.name = String::from("sub.milo");
+        sub_dir.dir_type = String::from("ObjectDir");
+        sub_dir.entries.push(Object::Packed(PackedObject {
+            name: String::from("sub.tex"),
+            object_type: String::from("Tex"),
+            data: b"sub tex".to_vec()
+        }));
+
+        let mut dir = ObjectDirBase::new();
+        dir.name = String::from("song.milo");
+        dir.dir_type = String::from("ObjectDir");
+        dir.entries.push(Object::Packed(PackedObject {
+            name: String::from("main.tex"),
+            object_type: String::from("Tex"),
+            data: b"tex".to_vec()
+        }));
+        dir.dir_entry = Some(ObjectDirEntry {
+            data: b"dir entry".to_vec(),
+            sub_dirs_offset: 3,
+            object_dir: None
+        });
+        dir.sub_dirs.push(ObjectDir::ObjectDir(sub_dir));
+
+        ObjectDir::ObjectDir(dir)
+    }
+
+    #[rstest]
+    fn milo_file_system() {
+        let info = SystemInfo::default();
+        let fs = MiloFileSystem::new(&create_test_milo_dir(), &info);
+
+        assert_eq!(b"tex".to_vec(), fs.read("ark://tex/MAIN.tex").unwrap());
+        assert_eq!(b"dir entry".to_vec(), fs.read("ObjectDir/song.milo").unwrap());
+        assert_eq!(b"sub tex".to_vec(), fs.read("sub.milo/Tex/sub.tex").unwrap());
+        assert!(fs.exists(DIR_INFO_FILE_NAME));
+        assert!(!fs.exists("Tex"));
+
+        assert_eq!(vec!["sub.milo/Tex/sub.tex", "sub.milo/dir.dta"], fs.list_files("sub.milo"));
+
+        // Same layout as milo2dir so directory can be read back
+        let ObjectDir::ObjectDir(dir) = ObjectDir::from_vfs(&fs, "", &info).unwrap();
+        assert_eq!("song.milo", dir.name);
+        assert_eq!(1, dir.entries.len());
+        assert_eq!(b"dir entry".to_vec(), dir.dir_entry.unwrap().data);
+        assert_eq!(1, dir.sub_dirs.len());
+    }
+
+    #[rstest]
+    fn overlay_file_system_shadows_milo_entries() {
+        let dir_path = create_test_dir("vfs_overlay_milo_file_system");
+        write_file(&dir_path.join("Tex/main.tex"), b"mod tex");
+
+        let info = SystemInfo::default();
+        let fs = OverlayFileSystem::new()
+            .and_layer(MiloFileSystem::new(&create_test_milo_dir(), &info))
+            .and_layer(DirectoryFileSystem::new(&dir_path));
+
+        assert_eq!(b"mod tex".to_vec(), fs.read("Tex/main.tex").unwrap());
+        assert_eq!(b"sub tex".to_vec(), fs.read("sub.milo/Tex/sub.tex").unwrap());
+    }
+
+    #[rstest]
+    fn ark_file_system() {
+        let dir_path = create_test_dir("vfs_ark_file_system");
+        let ark = ArkBuilder::new(5)
+            .and_data("config/rb.dta", b"(a)".to_vec())
+            .and_data("songs/gen/song.milo_xbox", b"milo".to_vec())
+            .and_data("songs_extra.dta", b"(b)".to_vec())
+            .build(dir_path.join("main.hdr"))
+            .unwrap();
+
+        let fs = ArkFileSystem::new(ark);
+
+        assert!(fs.exists("ark://Songs/Gen/Song.milo_xbox"));
+        assert!(!fs.exists("songs/gen"));
+        assert_eq!(b"(a)".to_vec(), fs.read("config\\rb.dta").unwrap());
+        assert!(matches!(fs.read("missing.dta"), Err(VfsError::FileNotFound { .. })));
+
+        assert_eq!(vec!["songs/gen/song.milo_xbox"], fs.list_files("songs"));
+        assert_eq!(3, fs.list_files("").len());
+    }
+
+    #[rstest]
+    fn overlay_file_system_shadows_lower_layers() {
+        let dir_path = create_test_dir("vfs_overlay_file_system");
+        let ark = ArkBuilder::new(3)
+            .and_data("config/rb.dta", b"(ark)".to_vec())
+            .and_data("songs/gen/song.milo_xbox", b"ark milo".to_vec())
+            .build(dir_path.join("ark").join("main.hdr"))
+            .unwrap();
+
+        let mod_dir = dir_path.join("mod");
+        write_file(&mod_dir.join("config/RB.dta"), b"(mod)");
+        write_file(&mod_dir.join("songs/new.dta"), b"(new)");
+
+        let fs = OverlayFileSystem::new()
+            .and_layer(ArkFileSystem::new(ark))
+            .and_layer(DirectoryFileSystem::new(&mod_dir));
+
+        assert_eq!(b"(mod)".to_vec(), fs.read("ark://config/RB.dta").unwrap());
+        assert_eq!(b"(mod)".to_vec(), fs.read("ark://config/rb.dta").unwrap());
+        assert_eq!(b"ark milo".to_vec(), fs.read("ark://songs/gen/song.milo_xbox").unwrap());
+        assert_eq!(b"(new)".to_vec(), fs.read("songs/new.dta").unwrap());
+        assert!(!fs.exists("missing.dta"));
+
+        assert_eq!(vec!["config/RB.dta", "songs/gen/song.milo_xbox", "songs/new.dta"], fs.list_files(""));
+    }
+}
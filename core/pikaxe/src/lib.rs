@@ -1,17 +1,23 @@
 pub mod ark;
 #[cfg(feature = "audio")] pub mod audio;
+pub mod convert;
 pub mod dta;
+mod error;
 pub mod io;
 #[cfg(feature = "midi")] pub mod midi {
     pub use pikaxe_midi::*;
 }
 #[cfg(feature = "model")] pub mod model;
+pub mod package;
+mod preset;
 pub mod scene;
 mod system;
 pub mod texture;
 
 #[cfg(feature = "python")] use pyo3::prelude::*;
+pub use error::*;
 pub use pikaxe_traits::*;
+pub use preset::*;
 pub use system::*;
 
 #[cfg(feature = "python")]
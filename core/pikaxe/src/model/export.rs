@@ -402,9 +402,22 @@ impl GltfExporter {
         let milo_path: PathBuf = path.into();
 
         // Open milo
-        let mut stream = FileStream::from_path_as_read_open(&milo_path)?;
+        let milo = MiloArchive::from_path(&milo_path)?;
+
+        self.add_milo(milo, milo_path)
+    }
+
+    /// Adds milo from virtual file system (e.g. `ark://char/main/gen/shared.milo_xbox`)
+    pub fn add_milo_from_vfs(&mut self, fs: &dyn VirtualFileSystem, path: &str) -> Result<(), Box<dyn Error>> {
+        let data = fs.read(path)?;
+
+        let mut stream = MemoryStream::from_slice_as_read(&data);
         let milo = MiloArchive::from_stream(&mut stream)?;
 
+        self.add_milo(milo, PathBuf::from(normalize_vfs_path(path)))
+    }
+
+    fn add_milo(&mut self, milo: MiloArchive, milo_path: PathBuf) -> Result<(), Box<dyn Error>> {
         // Guess system info and unpack dir + entries
         let system_info = SystemInfo::guess_system_info(&milo, &milo_path);
         let mut obj_dir = milo.unpack_directory(&system_info)?;
@@ -415,7 +428,7 @@ impl GltfExporter {
         // Add to list
         self.object_dirs.push(ObjectDirData {
             dir: obj_dir,
-            entries: entries,
+            entries,
             path: milo_path,
             info: system_info
         });
@@ -0,0 +1,22 @@
+pub mod stfs;
+pub mod u8arc;
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::path::PathBuf;
+
+    /// Path to file in temp test directory
+    pub fn get_test_path(name: &str, file_name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join("pikaxe_tests")
+            .join(name)
+            .join(file_name)
+    }
+
+    /// Generates non-repeating data for testing
+    pub fn get_test_data(size: usize, seed: u8) -> Vec<u8> {
+        (0..size)
+            .map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed))
+            .collect()
+    }
+}
@@ -0,0 +1,530 @@
+use crate::ark::{ArkBuilderEntry, BuilderEntries};
+use crate::package::stfs::*;
+use sha1_smol::Sha1;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const BLOCK_SEPARATION: u8 = 1; // Single copy of each hash table
+
+#[derive(Debug)]
+pub struct StfsBuilder {
+    package_type: StfsPackageType,
+    metadata: StfsMetadata,
+    entries: BuilderEntries,
+}
+
+impl StfsBuilder {
+    pub fn new(package_type: StfsPackageType) -> StfsBuilder {
+        StfsBuilder {
+            package_type,
+            metadata: StfsMetadata::default(),
+            entries: BuilderEntries::new(),
+        }
+    }
+
+    pub fn from_dir<T: AsRef<Path>>(dir_path: T, package_type: StfsPackageType) -> Result<StfsBuilder, StfsWriteError> {
+        let dir_path = dir_path.as_ref();
+
+        Ok(StfsBuilder {
+            entries: BuilderEntries::from_dir(dir_path)
+                .ok_or_else(|| StfsWriteError::CantReadEntry {
+                    path: dir_path.to_str().unwrap_or_default().to_owned()
+                })?,
+            ..StfsBuilder::new(package_type)
+        })
+    }
+
+    pub fn with_metadata(mut self, metadata: StfsMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn and_file<S: Into<String>, T: Into<PathBuf>>(mut self, package_path: S, file_path: T) -> Self {
+        self.entries.push_file(package_path, file_path);
+        self
+    }
+
+    pub fn and_data<S: Into<String>>(mut self, package_path: S, data: Vec<u8>) -> Self {
+        self.entries.push_data(package_path, data);
+        self
+    }
+
+    pub fn get_entries(&self) -> &Vec<ArkBuilderEntry> {
+        self.entries.get_entries()
+    }
+
+    /// Writes unsigned package to output path. Files are stored in consecutive blocks
+    /// and only a single copy of each hash table is written.
+    pub fn build<T: AsRef<Path>>(mut self, output_path: T) -> Result<StfsPackage, StfsWriteError> {
+        let output_path = output_path.as_ref();
+
+        self.entries.get_entries_mut().sort_by_key(|e| e.path.to_ascii_lowercase());
+        let (mut records, sources) = create_file_records(self.entries.get_entries())?;
+
+        // Assign blocks (file table comes first)
+        let file_table_block_count = records.len().div_ceil(FILE_ENTRIES_PER_BLOCK).max(1) as u32;
+        let mut block_count = file_table_block_count;
+
+        for record in records.iter_mut().filter(|r| !r.is_dir) {
+            record.block_count = (record.size as usize).div_ceil(BLOCK_SIZE) as u32;
+
+            if record.block_count > 0 {
+                record.start_block = block_count;
+                block_count += record.block_count;
+            }
+
+            if block_count > MAX_BLOCK_COUNT {
+                return Err(StfsWriteError::PackageTooLarge);
+            }
+        }
+
+        if let Some(output_dir) = output_path.parent() {
+            std::fs::create_dir_all(output_dir)
+                .map_err(|_| StfsWriteError::CantWritePackage)?;
+        }
+
+        let mut file = File::create(output_path)
+            .map_err(|_| StfsWriteError::CantWritePackage)?;
+
+        // Write data blocks
+        let mut block_hashes = vec![[0u8; 20]; block_count as usize];
+
+        let mut writer = StfsBlockWriter::new(&mut file, 0, &mut block_hashes);
+        writer.write_all(&create_file_table(&records, file_table_block_count))
+            .map_err(|_| StfsWriteError::CantWritePackage)?;
+        writer.finish()?;
+
+        for (record, source) in records.iter().zip(sources) {
+            let Some(source) = source else {
+                continue;
+            };
+
+            let mut writer = StfsBlockWriter::new(&mut file, record.start_block, &mut block_hashes);
+            source.write_data(&mut writer)
+                .map_err(|_| StfsWriteError::CantReadEntry { path: source.path.to_owned() })?;
+            writer.finish()?;
+        }
+
+        // Write hash tables
+        let next_blocks = get_next_blocks(&records, file_table_block_count, block_count);
+        let top_hash = write_hash_tables(&mut file, &block_hashes, &next_blocks)?;
+
+        // Write header
+        let file_size = file.seek(SeekFrom::End(0))
+            .map_err(|_| StfsWriteError::CantWritePackage)?;
+
+        let volume = StfsVolumeDescriptor {
+            block_separation: BLOCK_SEPARATION,
+            file_table_block_count: file_table_block_count as u16,
+            file_table_block: 0,
+            top_hash,
+            allocated_block_count: block_count,
+            unallocated_block_count: 0,
+        };
+
+        let header = create_header(self.package_type, &self.metadata, &volume, file_size);
+
+        file.seek(SeekFrom::Start(0))
+            .and_then(|_| file.write_all(&header))
+            .map_err(|_| StfsWriteError::CantWritePackage)?;
+
+        drop(file);
+
+        StfsPackage::from_path(output_path)
+            .map_err(|_| StfsWriteError::CantWritePackage)
+    }
+}
+
+/// Writes data to consecutive package blocks while computing hash of each block
+struct StfsBlockWriter<'a> {
+    file: &'a mut File,
+    block: u32,
+    buffer: Vec<u8>,
+    block_hashes: &'a mut Vec<[u8; 20]>,
+}
+
+impl<'a> StfsBlockWriter<'a> {
+    fn new(file: &'a mut File, start_block: u32, block_hashes: &'a mut Vec<[u8; 20]>) -> StfsBlockWriter<'a> {
+        StfsBlockWriter {
+            file,
+            block: start_block,
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            block_hashes,
+        }
+    }
+
+    fn write_block(&mut self) -> std::io::Result<()> {
+        // Last block is padded with zeros
+        self.buffer.resize(BLOCK_SIZE, 0);
+
+        let address = get_block_address(HEADER_SIZE, compute_backing_data_block(self.block, 0));
+        self.file.seek(SeekFrom::Start(address))?;
+        self.file.write_all(&self.buffer)?;
+
+        self.block_hashes[self.block as usize] = Sha1::from(&self.buffer).digest().bytes();
+        self.block += 1;
+        self.buffer.clear();
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), StfsWriteError> {
+        if !self.buffer.is_empty() {
+            self.write_block()
+                .map_err(|_| StfsWriteError::CantWritePackage)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Write for StfsBlockWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let write_size = (BLOCK_SIZE - self.buffer.len()).min(buf.len());
+        self.buffer.extend_from_slice(&buf[..write_size]);
+
+        if self.buffer.len() == BLOCK_SIZE {
+            self.write_block()?;
+        }
+
+        Ok(write_size)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Creates file table records for entries (directories are added before their children)
+fn create_file_records(entries: &[ArkBuilderEntry]) -> Result<(Vec<StfsFileEntry>, Vec<Option<&ArkBuilderEntry>>), StfsWriteError> {
+    let mut records = Vec::new();
+    let mut sources = Vec::new();
+    let mut dir_lookup: HashMap<String, i16> = HashMap::new();
+
+    for entry in entries.iter() {
+        let parts = entry.path
+            .split(['/', '\\'])
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>();
+
+        if let Some(name) = parts.iter().find(|p| p.len() > MAX_FILE_NAME_LENGTH) {
+            return Err(StfsWriteError::FileNameTooLong { name: name.to_string() });
+        }
+
+        let Some((file_name, dir_names)) = parts.split_last() else {
+            continue;
+        };
+
+        let mut parent = -1;
+
+        for i in 0..dir_names.len() {
+            let dir_path = dir_names[..=i].join("/").to_ascii_lowercase();
+
+            parent = match dir_lookup.get(&dir_path) {
+                Some(idx) => *idx,
+                None => {
+                    let idx = records.len() as i16;
+
+                    records.push(StfsFileEntry {
+                        name: dir_names[i].to_string(),
+                        is_dir: true,
+                        parent,
+                        ..Default::default()
+                    });
+                    sources.push(None);
+
+                    dir_lookup.insert(dir_path, idx);
+                    idx
+                }
+            };
+        }
+
+        let size = entry.get_size()
+            .map_err(|_| StfsWriteError::CantReadEntry { path: entry.path.to_owned() })?;
+
+        records.push(StfsFileEntry {
+            name: file_name.to_string(),
+            is_dir: false,
+            is_consecutive: true,
+            size: u32::try_from(size).map_err(|_| StfsWriteError::PackageTooLarge)?,
+            parent,
+            ..Default::default()
+        });
+        sources.push(Some(entry));
+    }
+
+    Ok((records, sources))
+}
+
+fn create_file_table(records: &[StfsFileEntry], block_count: u32) -> Vec<u8> {
+    let mut data = vec![0u8; block_count as usize * BLOCK_SIZE];
+
+    for (record, entry_data) in records.iter().zip(data.chunks_exact_mut(FILE_ENTRY_SIZE)) {
+        let name = record.name.as_bytes();
+        entry_data[..name.len()].copy_from_slice(name);
+
+        entry_data[0x28] = (name.len() as u8 & 0x3F)
+            | if record.is_consecutive { 0x40 } else { 0 }
+            | if record.is_dir { 0x80 } else { 0 };
+
+        write_u24_le(entry_data, 0x29, record.block_count); // Valid blocks
+        write_u24_le(entry_data, 0x2C, record.block_count); // Allocated blocks
+        write_u24_le(entry_data, 0x2F, record.start_block);
+        entry_data[0x32..0x34].copy_from_slice(&record.parent.to_be_bytes());
+        entry_data[0x34..0x38].copy_from_slice(&record.size.to_be_bytes());
+    }
+
+    data
+}
+
+/// Gets next block in chain for every block (file table + files are consecutive)
+fn get_next_blocks(records: &[StfsFileEntry], file_table_block_count: u32, block_count: u32) -> Vec<u32> {
+    let mut next_blocks = (1..=block_count).collect::<Vec<_>>();
+
+    let chain_ends = records
+        .iter()
+        .filter(|r| !r.is_dir && r.block_count > 0)
+        .map(|r| r.start_block + r.block_count - 1)
+        .chain([file_table_block_count - 1]);
+
+    for block in chain_ends {
+        next_blocks[block as usize] = END_OF_CHAIN;
+    }
+
+    next_blocks
+}
+
+/// Writes hash tables for each level and returns hash of top table
+fn write_hash_tables(file: &mut File, block_hashes: &[[u8; 20]], next_blocks: &[u32]) -> Result<[u8; 20], StfsWriteError> {
+    let top_level = get_top_level(block_hashes.len() as u32);
+
+    // Level 0 entries are data blocks
+    let mut entries = block_hashes
+        .iter()
+        .zip(next_blocks)
+        .map(|(hash, next_block)| (*hash, 0x80u8, *next_block))
+        .collect::<Vec<_>>();
+
+    let mut top_hash = [0u8; 20];
+
+    for level in 0..=top_level {
+        let mut table_hashes = Vec::new();
+
+        for (i, table_entries) in entries.chunks(HASHES_PER_TABLE as usize).enumerate() {
+            let mut table = vec![0u8; BLOCK_SIZE];
+
+            for ((hash, status, next_block), entry_data) in table_entries.iter().zip(table.chunks_exact_mut(HASH_ENTRY_SIZE)) {
+                entry_data[..20].copy_from_slice(hash);
+                entry_data[0x14] = *status;
+                write_u24_be(entry_data, 0x15, *next_block);
+            }
+
+            let first_block = i as u32 * get_blocks_per_table(level);
+            let address = get_block_address(HEADER_SIZE, compute_backing_hash_block(first_block, level, 0));
+
+            file.seek(SeekFrom::Start(address))
+                .and_then(|_| file.write_all(&table))
+                .map_err(|_| StfsWriteError::CantWritePackage)?;
+
+            table_hashes.push(Sha1::from(&table).digest().bytes());
+        }
+
+        top_hash = table_hashes[0];
+
+        // Tables become entries of next level
+        entries = table_hashes
+            .into_iter()
+            .map(|hash| (hash, 0u8, 0u32))
+            .collect();
+    }
+
+    Ok(top_hash)
+}
+
+fn create_header(package_type: StfsPackageType, metadata: &StfsMetadata, volume: &StfsVolumeDescriptor, file_size: u64) -> Vec<u8> {
+    let mut header = vec![0u8; get_first_hash_table_offset(HEADER_SIZE) as usize];
+    let first_hash_table_offset = header.len() as u64;
+
+    header[..4].copy_from_slice(package_type.get_magic());
+    write_u32_be(&mut header, HEADER_SIZE_OFFSET, HEADER_SIZE);
+    write_u32_be(&mut header, CONTENT_TYPE_OFFSET, metadata.content_type);
+    write_u32_be(&mut header, METADATA_VERSION_OFFSET, metadata.metadata_version);
+    header[CONTENT_SIZE_OFFSET..(CONTENT_SIZE_OFFSET + 8)].copy_from_slice(&file_size.saturating_sub(first_hash_table_offset).to_be_bytes());
+    write_u32_be(&mut header, MEDIA_ID_OFFSET, metadata.media_id);
+    write_u32_be(&mut header, VERSION_OFFSET, metadata.version);
+    write_u32_be(&mut header, BASE_VERSION_OFFSET, metadata.base_version);
+    write_u32_be(&mut header, TITLE_ID_OFFSET, metadata.title_id);
+
+    // Volume descriptor
+    let vd = &mut header[VOLUME_DESCRIPTOR_OFFSET..];
+    vd[0] = 0x24; // Size
+    vd[2] = volume.block_separation;
+    vd[3..5].copy_from_slice(&volume.file_table_block_count.to_le_bytes());
+    write_u24_le(vd, 5, volume.file_table_block);
+    vd[8..28].copy_from_slice(&volume.top_hash);
+    write_u32_be(vd, 28, volume.allocated_block_count);
+    write_u32_be(vd, 32, volume.unallocated_block_count);
+
+    write_utf16_string(&mut header, DISPLAY_NAME_OFFSET, &metadata.display_name);
+    write_utf16_string(&mut header, DESCRIPTION_OFFSET, &metadata.description);
+    write_utf16_string(&mut header, PUBLISHER_OFFSET, &metadata.publisher);
+    write_utf16_string(&mut header, TITLE_NAME_OFFSET, &metadata.title_name);
+
+    // Hash everything after header hash + size
+    let header_hash = Sha1::from(&header[CONTENT_TYPE_OFFSET..]).digest().bytes();
+    header[HEADER_HASH_OFFSET..(HEADER_HASH_OFFSET + 20)].copy_from_slice(&header_hash);
+
+    header
+}
+
+fn write_u32_be(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..(offset + 4)].copy_from_slice(&value.to_be_bytes());
+}
+
+fn write_u24_le(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..(offset + 3)].copy_from_slice(&value.to_le_bytes()[..3]);
+}
+
+fn write_u24_be(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..(offset + 3)].copy_from_slice(&value.to_be_bytes()[1..]);
+}
+
+/// Writes UTF-16 (BE) string (truncated to fit with null terminator)
+fn write_utf16_string(data: &mut [u8], offset: usize, value: &str) {
+    let max_chars = (STRING_SIZE / 2) - 1;
+
+    for (i, ch) in value.encode_utf16().take(max_chars).enumerate() {
+        let pos = offset + (i * 2);
+        data[pos..(pos + 2)].copy_from_slice(&ch.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+
+    use crate::package::tests::get_test_data;
+
+    fn get_test_path(name: &str) -> PathBuf {
+        crate::package::tests::get_test_path(name, "package")
+    }
+
+    #[rstest]
+    #[case(StfsPackageType::Con)]
+    #[case(StfsPackageType::Live)]
+    fn build_and_read_package(#[case] package_type: StfsPackageType) {
+        let package_path = get_test_path(&format!("build_and_read_package_{package_type:?}"));
+
+        let metadata = StfsMetadata {
+            content_type: 0x0000_0002,
+            title_id: 0x4541_0914,
+            display_name: String::from("Custom Song"),
+            description: String::from("Description of song"),
+            ..Default::default()
+        };
+
+        let package = StfsBuilder::new(package_type)
+            .with_metadata(metadata)
+            .and_data("songs/songs.dta", b"(song (name \"songs/song/song\"))".to_vec())
+            .and_data("songs/song/song.mogg", get_test_data(0x2345, 1))
+            .and_data("songs/song/gen/song.milo_xbox", get_test_data(0x1000, 2))
+            .and_data("songs/song/song.mid", Vec::new())
+            .build(&package_path)
+            .unwrap();
+
+        assert_eq!(package_type, package.package_type);
+        assert_eq!(0x4541_0914, package.metadata.title_id);
+        assert_eq!(0x0000_0002, package.metadata.content_type);
+        assert_eq!("Custom Song", package.metadata.display_name);
+        assert_eq!("Description of song", package.metadata.description);
+        assert!(package.verify().unwrap());
+
+        let paths = package.entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.size))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![
+            ("songs/song/gen/song.milo_xbox", 0x1000),
+            ("songs/song/song.mid", 0),
+            ("songs/song/song.mogg", 0x2345),
+            ("songs/songs.dta", 31),
+        ], paths);
+
+        let get_data = |path: &str| {
+            let entry = package.entries.iter().find(|e| e.path == path).unwrap();
+            package.get_stream(entry.id).unwrap()
+        };
+
+        assert_eq!(get_test_data(0x2345, 1), get_data("songs/song/song.mogg"));
+        assert_eq!(get_test_data(0x1000, 2), get_data("songs/song/gen/song.milo_xbox"));
+        assert_eq!(Vec::<u8>::new(), get_data("songs/song/song.mid"));
+        assert_eq!(b"(song (name \"songs/song/song\"))".to_vec(), get_data("songs/songs.dta"));
+    }
+
+    #[rstest]
+    fn build_package_with_multi_level_hash_tree() {
+        // Needs more than 0xAA blocks for level 1 hash table (+ file table spanning multiple blocks)
+        let package_path = get_test_path("build_package_with_multi_level_hash_tree");
+        let file_size = 0x1000 * 3;
+
+        let builder = (0..100)
+            .fold(StfsBuilder::new(StfsPackageType::Con), |b, i| b
+                .and_data(format!("files/file_{i:03}.bin"), get_test_data(file_size, i as u8)));
+
+        let package = builder
+            .build(&package_path)
+            .unwrap();
+
+        assert_eq!(1, package.volume.get_top_level());
+        assert_eq!(2, package.volume.file_table_block_count);
+        assert!(package.verify().unwrap());
+        assert_eq!(100, package.entries.len());
+
+        for (i, entry) in package.entries.iter().enumerate() {
+            assert_eq!(format!("files/file_{i:03}.bin"), entry.path);
+            assert_eq!(get_test_data(file_size, i as u8), package.get_stream(entry.id).unwrap());
+        }
+    }
+
+    #[rstest]
+    fn corrupted_package_fails_verify() {
+        let package_path = get_test_path("corrupted_package_fails_verify");
+
+        let package = StfsBuilder::new(StfsPackageType::Con)
+            .and_data("file.bin", get_test_data(0x1800, 0))
+            .build(&package_path)
+            .unwrap();
+
+        let mut data = std::fs::read(&package_path).unwrap();
+        let offset = package.entries[0].offset as usize + 0x10;
+        data[offset] = !data[offset];
+        std::fs::write(&package_path, data).unwrap();
+
+        assert!(!package.verify().unwrap());
+    }
+
+    #[rstest]
+    fn build_package_file_name_too_long() {
+        let package_path = get_test_path("build_package_file_name_too_long");
+
+        let result = StfsBuilder::new(StfsPackageType::Con)
+            .and_data(format!("songs/{}.dta", "a".repeat(40)), Vec::new())
+            .build(&package_path);
+
+        assert!(matches!(result, Err(StfsWriteError::FileNameTooLong { .. })));
+    }
+
+    #[rstest]
+    fn read_non_package() {
+        let package_path = get_test_path("read_non_package");
+        std::fs::create_dir_all(package_path.parent().unwrap()).unwrap();
+        std::fs::write(&package_path, b"ARK\0not a package").unwrap();
+
+        assert!(!StfsPackage::is_stfs_package(&package_path));
+        assert!(matches!(StfsPackage::from_path(&package_path), Err(StfsReadError::NotStfsPackage)));
+    }
+}
@@ -0,0 +1,39 @@
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum StfsReadError {
+    #[error("Can't open package file")]
+    CantOpenPackage,
+    #[error("File isn't a CON, LIVE or PIRS package")]
+    NotStfsPackage,
+    #[error("SVOD packages are not supported")]
+    SvodNotSupported,
+    #[error("Can't read package header")]
+    CantReadHeader,
+    #[error("Can't read package file table")]
+    CantReadFileTable,
+    #[error("Entry with id {id} not found")]
+    EntryNotFound {
+        id: u32
+    },
+    #[error("Can't read data for entry \"{path}\"")]
+    CantReadEntry {
+        path: String
+    },
+}
+
+#[derive(Debug, ThisError)]
+pub enum StfsWriteError {
+    #[error("Can't write package file")]
+    CantWritePackage,
+    #[error("Can't read data for entry \"{path}\"")]
+    CantReadEntry {
+        path: String
+    },
+    #[error("File name \"{name}\" is longer than 40 characters")]
+    FileNameTooLong {
+        name: String
+    },
+    #[error("Package exceeds max block count")]
+    PackageTooLarge,
+}
@@ -0,0 +1,394 @@
+use crate::ark::ArkOffsetEntry;
+use crate::package::stfs::*;
+use sha1_smol::Sha1;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+pub(crate) const HEADER_HASH_OFFSET: usize = 0x32C;
+pub(crate) const HEADER_SIZE_OFFSET: usize = 0x340;
+pub(crate) const CONTENT_TYPE_OFFSET: usize = 0x344;
+pub(crate) const METADATA_VERSION_OFFSET: usize = 0x348;
+pub(crate) const CONTENT_SIZE_OFFSET: usize = 0x34C;
+pub(crate) const MEDIA_ID_OFFSET: usize = 0x354;
+pub(crate) const VERSION_OFFSET: usize = 0x358;
+pub(crate) const BASE_VERSION_OFFSET: usize = 0x35C;
+pub(crate) const TITLE_ID_OFFSET: usize = 0x360;
+pub(crate) const VOLUME_DESCRIPTOR_OFFSET: usize = 0x379;
+pub(crate) const DESCRIPTOR_TYPE_OFFSET: usize = 0x3A9;
+pub(crate) const DISPLAY_NAME_OFFSET: usize = 0x411;
+pub(crate) const DESCRIPTION_OFFSET: usize = 0xD11;
+pub(crate) const PUBLISHER_OFFSET: usize = 0x1611;
+pub(crate) const TITLE_NAME_OFFSET: usize = 0x1691;
+pub(crate) const STRING_SIZE: usize = 0x80;
+
+impl StfsPackage {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<StfsPackage, StfsReadError> {
+        let path = path.as_ref();
+
+        let mut file = File::open(path)
+            .map_err(|_| StfsReadError::CantOpenPackage)?;
+
+        // Read header size first to know how much to read
+        let mut header = vec![0u8; HEADER_SIZE_OFFSET + 4];
+        file.read_exact(&mut header)
+            .map_err(|_| StfsReadError::NotStfsPackage)?;
+
+        let package_type = StfsPackageType::from_magic(&header[..4])
+            .ok_or(StfsReadError::NotStfsPackage)?;
+
+        let header_size = read_u32_be(&header, HEADER_SIZE_OFFSET);
+        if (header_size as usize) < TITLE_NAME_OFFSET + STRING_SIZE {
+            return Err(StfsReadError::CantReadHeader);
+        }
+
+        header.resize(get_first_hash_table_offset(header_size) as usize, 0);
+        file.read_exact(&mut header[(HEADER_SIZE_OFFSET + 4)..])
+            .map_err(|_| StfsReadError::CantReadHeader)?;
+
+        let mut package = StfsPackage {
+            path: path.to_owned(),
+            package_type,
+            header_size,
+            ..Default::default()
+        };
+
+        package.parse_header(&header)?;
+        package.parse_file_table(&mut file)?;
+
+        Ok(package)
+    }
+
+    pub fn is_stfs_package<T: AsRef<Path>>(path: T) -> bool {
+        let mut magic = [0u8; 4];
+
+        File::open(path)
+            .and_then(|mut f| f.read_exact(&mut magic))
+            .map(|_| StfsPackageType::from_magic(&magic).is_some())
+            .unwrap_or_default()
+    }
+
+    fn parse_header(&mut self, header: &[u8]) -> Result<(), StfsReadError> {
+        if read_u32_be(header, DESCRIPTOR_TYPE_OFFSET) != 0 {
+            return Err(StfsReadError::SvodNotSupported);
+        }
+
+        self.metadata = StfsMetadata {
+            content_type: read_u32_be(header, CONTENT_TYPE_OFFSET),
+            metadata_version: read_u32_be(header, METADATA_VERSION_OFFSET),
+            media_id: read_u32_be(header, MEDIA_ID_OFFSET),
+            version: read_u32_be(header, VERSION_OFFSET),
+            base_version: read_u32_be(header, BASE_VERSION_OFFSET),
+            title_id: read_u32_be(header, TITLE_ID_OFFSET),
+            display_name: read_utf16_string(header, DISPLAY_NAME_OFFSET),
+            description: read_utf16_string(header, DESCRIPTION_OFFSET),
+            publisher: read_utf16_string(header, PUBLISHER_OFFSET),
+            title_name: read_utf16_string(header, TITLE_NAME_OFFSET),
+        };
+
+        // Note: Volume descriptor mixes little and big endian values
+        let vd = &header[VOLUME_DESCRIPTOR_OFFSET..];
+        self.volume = StfsVolumeDescriptor {
+            block_separation: vd[2],
+            file_table_block_count: u16::from_le_bytes([vd[3], vd[4]]),
+            file_table_block: read_u24_le(vd, 5),
+            top_hash: {
+                let mut hash = [0u8; 20];
+                hash.copy_from_slice(&vd[8..28]);
+                hash
+            },
+            allocated_block_count: read_u32_be(vd, 28),
+            unallocated_block_count: read_u32_be(vd, 32),
+        };
+
+        Ok(())
+    }
+
+    fn parse_file_table(&mut self, file: &mut File) -> Result<(), StfsReadError> {
+        let mut block = self.volume.file_table_block;
+        let mut buffer = [0u8; BLOCK_SIZE];
+
+        'blocks: for i in 0..self.volume.file_table_block_count {
+            self.read_block(file, block, &mut buffer)
+                .map_err(|_| StfsReadError::CantReadFileTable)?;
+
+            for entry_data in buffer.chunks_exact(FILE_ENTRY_SIZE) {
+                let flags = entry_data[0x28];
+                let name_length = (flags & 0x3F) as usize;
+
+                if name_length == 0 {
+                    // End of file table
+                    break 'blocks;
+                }
+
+                self.files.push(StfsFileEntry {
+                    name: String::from_utf8_lossy(&entry_data[..name_length.min(MAX_FILE_NAME_LENGTH)]).to_string(),
+                    is_dir: (flags & 0x80) != 0,
+                    is_consecutive: (flags & 0x40) != 0,
+                    block_count: read_u24_le(entry_data, 0x29),
+                    start_block: read_u24_le(entry_data, 0x2F),
+                    parent: i16::from_be_bytes([entry_data[0x32], entry_data[0x33]]),
+                    size: read_u32_be(entry_data, 0x34),
+                });
+            }
+
+            if i + 1 < self.volume.file_table_block_count {
+                block = self.read_next_block(file, block)
+                    .map_err(|_| StfsReadError::CantReadFileTable)?;
+            }
+        }
+
+        self.entries = self.files
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| !f.is_dir)
+            .map(|(i, f)| ArkOffsetEntry {
+                id: i as u32,
+                path: self.get_file_path(i),
+                offset: self.get_data_block_address(f.start_block),
+                part: 0,
+                size: f.size as usize,
+                inflated_size: 0,
+            })
+            .collect();
+
+        self.entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(())
+    }
+
+    /// Gets full path of file by walking up parent directories
+    fn get_file_path(&self, index: usize) -> String {
+        let mut parts = vec![self.files[index].name.as_str()];
+        let mut parent = self.files[index].parent;
+
+        while parent >= 0 && parts.len() <= self.files.len() {
+            let Some(dir) = self.files.get(parent as usize) else {
+                break;
+            };
+
+            parts.push(dir.name.as_str());
+            parent = dir.parent;
+        }
+
+        parts.reverse();
+        parts.join("/")
+    }
+
+    pub(crate) fn get_data_block_address(&self, block: u32) -> u64 {
+        let shift = self.volume.get_tables_per_level_shift();
+        get_block_address(self.header_size, compute_backing_data_block(block, shift))
+    }
+
+    /// Gets address of active hash table at level which covers data block. Packages that
+    /// store two copies of each table use parent entry status to select active table.
+    fn get_hash_table_address(&self, file: &mut File, block: u32, level: u32) -> std::io::Result<u64> {
+        let shift = self.volume.get_tables_per_level_shift();
+        let mut current_level = self.volume.get_top_level();
+        let mut address = get_block_address(self.header_size, compute_backing_hash_block(block, current_level, shift));
+
+        if shift == 1 && (self.volume.block_separation & 2) != 0 {
+            address += BLOCK_SIZE as u64;
+        }
+
+        while current_level > level {
+            let entry_index = (block / get_blocks_per_table(current_level - 1)) % HASHES_PER_TABLE;
+
+            let status = match shift {
+                1 => {
+                    let mut status = [0u8; 1];
+                    file.seek(SeekFrom::Start(address + (entry_index as u64 * HASH_ENTRY_SIZE as u64) + 0x14))?;
+                    file.read_exact(&mut status)?;
+                    status[0]
+                },
+                _ => 0
+            };
+
+            current_level -= 1;
+            address = get_block_address(self.header_size, compute_backing_hash_block(block, current_level, shift));
+
+            if (status & 0x40) != 0 {
+                address += BLOCK_SIZE as u64;
+            }
+        }
+
+        Ok(address)
+    }
+
+    fn read_hash_entry(&self, file: &mut File, block: u32, level: u32) -> std::io::Result<[u8; HASH_ENTRY_SIZE]> {
+        let table_address = self.get_hash_table_address(file, block, level)?;
+        let blocks_per_entry = match level {
+            0 => 1,
+            l => get_blocks_per_table(l - 1)
+        };
+        let entry_index = (block / blocks_per_entry) % HASHES_PER_TABLE;
+
+        let mut entry = [0u8; HASH_ENTRY_SIZE];
+        file.seek(SeekFrom::Start(table_address + (entry_index as u64 * HASH_ENTRY_SIZE as u64)))?;
+        file.read_exact(&mut entry)?;
+
+        Ok(entry)
+    }
+
+    fn read_next_block(&self, file: &mut File, block: u32) -> std::io::Result<u32> {
+        let entry = self.read_hash_entry(file, block, 0)?;
+        Ok(read_u24_be(&entry, 0x15))
+    }
+
+    fn read_block(&self, file: &mut File, block: u32, buffer: &mut [u8]) -> std::io::Result<()> {
+        file.seek(SeekFrom::Start(self.get_data_block_address(block)))?;
+        file.read_exact(buffer)
+    }
+
+    pub fn get_stream(&self, id: u32) -> Result<Vec<u8>, StfsReadError> {
+        let mut data = Vec::new();
+
+        self.get_reader(id)?
+            .read_to_end(&mut data)
+            .map_err(|_| StfsReadError::CantReadEntry { path: self.get_file_path(id as usize) })?;
+
+        Ok(data)
+    }
+
+    /// Opens streaming reader over file data (blocks are read as needed)
+    pub fn get_reader(&self, id: u32) -> Result<Box<dyn Read + '_>, StfsReadError> {
+        let entry = self.files
+            .get(id as usize)
+            .filter(|f| !f.is_dir)
+            .ok_or(StfsReadError::EntryNotFound { id })?;
+
+        let file = File::open(&self.path)
+            .map_err(|_| StfsReadError::CantOpenPackage)?;
+
+        Ok(Box::new(StfsEntryReader {
+            package: self,
+            file,
+            entry,
+            block: entry.start_block,
+            block_index: 0,
+            position: 0,
+        }))
+    }
+
+    /// Checks header hash and every hash in hash tree
+    pub fn verify(&self) -> Result<bool, StfsReadError> {
+        let mut file = File::open(&self.path)
+            .map_err(|_| StfsReadError::CantOpenPackage)?;
+
+        // Check header
+        let mut header = vec![0u8; get_first_hash_table_offset(self.header_size) as usize];
+        file.read_exact(&mut header)
+            .map_err(|_| StfsReadError::CantReadHeader)?;
+
+        if Sha1::from(&header[CONTENT_TYPE_OFFSET..]).digest().bytes() != header[HEADER_HASH_OFFSET..(HEADER_HASH_OFFSET + 20)] {
+            return Ok(false);
+        }
+
+        let verify_tree = |file: &mut File| -> std::io::Result<bool> {
+            let block_count = self.volume.allocated_block_count;
+            let top_level = self.volume.get_top_level();
+            let mut buffer = [0u8; BLOCK_SIZE];
+
+            // Check data blocks
+            for block in 0..block_count {
+                self.read_block(file, block, &mut buffer)?;
+                let entry = self.read_hash_entry(file, block, 0)?;
+
+                if Sha1::from(&buffer[..]).digest().bytes() != entry[..20] {
+                    return Ok(false);
+                }
+            }
+
+            // Check hash tables against parent tables
+            for level in 0..=top_level {
+                let blocks_per_table = get_blocks_per_table(level);
+
+                for block in (0..block_count.max(1)).step_by(blocks_per_table as usize) {
+                    let table_address = self.get_hash_table_address(file, block, level)?;
+                    file.seek(SeekFrom::Start(table_address))?;
+                    file.read_exact(&mut buffer)?;
+
+                    let expected_hash = match level {
+                        l if l == top_level => self.volume.top_hash,
+                        _ => {
+                            let entry = self.read_hash_entry(file, block, level + 1)?;
+                            let mut hash = [0u8; 20];
+                            hash.copy_from_slice(&entry[..20]);
+                            hash
+                        }
+                    };
+
+                    if Sha1::from(&buffer[..]).digest().bytes() != expected_hash {
+                        return Ok(false);
+                    }
+                }
+            }
+
+            Ok(true)
+        };
+
+        verify_tree(&mut file)
+            .map_err(|_| StfsReadError::CantReadHeader)
+    }
+}
+
+struct StfsEntryReader<'a> {
+    package: &'a StfsPackage,
+    file: File,
+    entry: &'a StfsFileEntry,
+    block: u32,
+    block_index: u32,
+    position: u32,
+}
+
+impl<'a> Read for StfsEntryReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position >= self.entry.size || buf.is_empty() {
+            return Ok(0);
+        }
+
+        // Move to next block if current one was fully read
+        let block_index = self.position / BLOCK_SIZE as u32;
+        if block_index != self.block_index {
+            self.block = match self.entry.is_consecutive {
+                true => self.block + 1,
+                _ => self.package.read_next_block(&mut self.file, self.block)?
+            };
+            self.block_index = block_index;
+        }
+
+        let block_offset = self.position % BLOCK_SIZE as u32;
+        let read_size = (BLOCK_SIZE as u32 - block_offset)
+            .min(self.entry.size - self.position)
+            .min(buf.len() as u32) as usize;
+
+        self.file.seek(SeekFrom::Start(self.package.get_data_block_address(self.block) + block_offset as u64))?;
+        self.file.read_exact(&mut buf[..read_size])?;
+
+        self.position += read_size as u32;
+        Ok(read_size)
+    }
+}
+
+pub(crate) fn read_u32_be(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+pub(crate) fn read_u24_le(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], 0])
+}
+
+pub(crate) fn read_u24_be(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([0, data[offset], data[offset + 1], data[offset + 2]])
+}
+
+/// Reads null-terminated UTF-16 (BE) string
+pub(crate) fn read_utf16_string(data: &[u8], offset: usize) -> String {
+    let chars = data[offset..(offset + STRING_SIZE)]
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .take_while(|c| *c != 0)
+        .collect::<Vec<_>>();
+
+    String::from_utf16_lossy(&chars)
+}
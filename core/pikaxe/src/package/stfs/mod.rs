@@ -0,0 +1,9 @@
+mod builder;
+mod errors;
+mod io;
+mod package;
+
+pub use self::builder::*;
+pub use self::errors::*;
+pub use self::io::*;
+pub use self::package::*;
@@ -0,0 +1,233 @@
+use crate::ark::ArkOffsetEntry;
+use std::path::PathBuf;
+
+pub(crate) const BLOCK_SIZE: usize = 0x1000;
+pub(crate) const HASH_ENTRY_SIZE: usize = 0x18;
+pub(crate) const FILE_ENTRY_SIZE: usize = 0x40;
+pub(crate) const FILE_ENTRIES_PER_BLOCK: usize = BLOCK_SIZE / FILE_ENTRY_SIZE;
+pub(crate) const MAX_FILE_NAME_LENGTH: usize = 0x28;
+pub(crate) const END_OF_CHAIN: u32 = 0xFF_FFFF;
+
+pub(crate) const HASHES_PER_TABLE: u32 = 0xAA; // Level 0
+pub(crate) const HASHES_PER_L1_TABLE: u32 = HASHES_PER_TABLE * HASHES_PER_TABLE; // Level 1
+pub(crate) const MAX_BLOCK_COUNT: u32 = HASHES_PER_L1_TABLE * HASHES_PER_TABLE; // Level 2
+
+pub(crate) const HEADER_SIZE: u32 = 0x971A;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StfsPackageType {
+    #[default]
+    Con,
+    Live,
+    Pirs,
+}
+
+impl StfsPackageType {
+    pub fn get_magic(&self) -> &'static [u8; 4] {
+        match self {
+            StfsPackageType::Con => b"CON ",
+            StfsPackageType::Live => b"LIVE",
+            StfsPackageType::Pirs => b"PIRS",
+        }
+    }
+
+    pub fn from_magic(magic: &[u8]) -> Option<StfsPackageType> {
+        match magic {
+            b"CON " => Some(StfsPackageType::Con),
+            b"LIVE" => Some(StfsPackageType::Live),
+            b"PIRS" => Some(StfsPackageType::Pirs),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StfsMetadata {
+    pub content_type: u32,
+    pub metadata_version: u32,
+    pub media_id: u32,
+    pub version: u32,
+    pub base_version: u32,
+    pub title_id: u32,
+    pub display_name: String,
+    pub description: String,
+    pub publisher: String,
+    pub title_name: String,
+}
+
+impl Default for StfsMetadata {
+    fn default() -> Self {
+        StfsMetadata {
+            content_type: 0x0000_0001, // Saved game
+            metadata_version: 2,
+            media_id: 0,
+            version: 0,
+            base_version: 0,
+            title_id: 0,
+            display_name: String::new(),
+            description: String::new(),
+            publisher: String::new(),
+            title_name: String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StfsVolumeDescriptor {
+    pub block_separation: u8,
+    pub file_table_block_count: u16,
+    pub file_table_block: u32,
+    pub top_hash: [u8; 20],
+    pub allocated_block_count: u32,
+    pub unallocated_block_count: u32,
+}
+
+impl StfsVolumeDescriptor {
+    /// Read-only packages only store a single copy of each hash table
+    pub fn get_tables_per_level_shift(&self) -> u32 {
+        (!self.block_separation & 1) as u32
+    }
+
+    pub fn get_top_level(&self) -> u32 {
+        get_top_level(self.allocated_block_count)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StfsFileEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub is_consecutive: bool,
+    pub block_count: u32,
+    pub start_block: u32,
+    pub parent: i16, // -1 = root
+    pub size: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct StfsPackage {
+    pub path: PathBuf,
+    pub package_type: StfsPackageType,
+    pub metadata: StfsMetadata,
+    /// Files in package (directories are only used to build paths). Ids are file table indices and
+    /// offsets point to first data block.
+    pub entries: Vec<ArkOffsetEntry>,
+    pub(crate) header_size: u32,
+    pub(crate) volume: StfsVolumeDescriptor,
+    pub(crate) files: Vec<StfsFileEntry>,
+}
+
+pub(crate) fn get_top_level(block_count: u32) -> u32 {
+    match block_count {
+        c if c <= HASHES_PER_TABLE => 0,
+        c if c <= HASHES_PER_L1_TABLE => 1,
+        _ => 2
+    }
+}
+
+/// Block spacing between hash tables of level 0 and 1
+fn get_block_steps(shift: u32) -> [u32; 2] {
+    match shift {
+        0 => [0xAB, 0x718F],
+        _ => [0xAC, 0x723A],
+    }
+}
+
+/// Converts data block number to block number in file (skips over interleaved hash tables)
+pub(crate) fn compute_backing_data_block(block: u32, shift: u32) -> u32 {
+    let backing_block = (((block + HASHES_PER_TABLE) / HASHES_PER_TABLE) << shift) + block;
+
+    if block < HASHES_PER_TABLE {
+        backing_block
+    } else if block < HASHES_PER_L1_TABLE {
+        backing_block + (((block + HASHES_PER_L1_TABLE) / HASHES_PER_L1_TABLE) << shift)
+    } else {
+        (1 << shift) + backing_block + (((block + HASHES_PER_L1_TABLE) / HASHES_PER_L1_TABLE) << shift)
+    }
+}
+
+/// Gets block number in file of hash table at given level which covers data block
+pub(crate) fn compute_backing_hash_block(block: u32, level: u32, shift: u32) -> u32 {
+    let steps = get_block_steps(shift);
+
+    match level {
+        0 => {
+            if block < HASHES_PER_TABLE {
+                return 0;
+            }
+
+            let backing_block = ((block / HASHES_PER_TABLE) * steps[0])
+                + (((block / HASHES_PER_L1_TABLE) + 1) << shift);
+
+            if block / HASHES_PER_L1_TABLE == 0 {
+                backing_block
+            } else {
+                backing_block + (1 << shift)
+            }
+        },
+        1 => {
+            if block < HASHES_PER_L1_TABLE {
+                steps[0]
+            } else {
+                (1 << shift) + ((block / HASHES_PER_L1_TABLE) * steps[1])
+            }
+        },
+        _ => steps[1]
+    }
+}
+
+/// Hash tables and data blocks start after header (aligned to block size)
+pub(crate) fn get_first_hash_table_offset(header_size: u32) -> u64 {
+    ((header_size as u64) + 0xFFF) & !0xFFF
+}
+
+pub(crate) fn get_block_address(header_size: u32, backing_block: u32) -> u64 {
+    get_first_hash_table_offset(header_size) + (backing_block as u64 * BLOCK_SIZE as u64)
+}
+
+/// Number of data blocks covered by single hash table at level
+pub(crate) fn get_blocks_per_table(level: u32) -> u32 {
+    match level {
+        0 => HASHES_PER_TABLE,
+        1 => HASHES_PER_L1_TABLE,
+        _ => MAX_BLOCK_COUNT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+
+    #[rstest]
+    #[case(0, 0, 1)]
+    #[case(0xA9, 0, 0xAA)]
+    #[case(0xAA, 0, 0xAD)]
+    #[case(0x153, 0, 0x156)]
+    #[case(0, 1, 2)]
+    #[case(0xAA, 1, 0xB0)]
+    fn compute_backing_data_block_test(#[case] block: u32, #[case] shift: u32, #[case] expected: u32) {
+        assert_eq!(expected, compute_backing_data_block(block, shift));
+    }
+
+    #[rstest]
+    #[case(0, 0, 0, 0)]
+    #[case(0xAA, 0, 0, 0xAC)]
+    #[case(0, 1, 0, 0xAB)]
+    #[case(0, 2, 0, 0x718F)]
+    #[case(0xAA, 0, 1, 0xAE)]
+    #[case(0, 1, 1, 0xAC)]
+    fn compute_backing_hash_block_test(#[case] block: u32, #[case] level: u32, #[case] shift: u32, #[case] expected: u32) {
+        assert_eq!(expected, compute_backing_hash_block(block, level, shift));
+    }
+
+    #[rstest]
+    #[case(1, 0)]
+    #[case(0xAA, 0)]
+    #[case(0xAB, 1)]
+    #[case(0x70E4, 1)]
+    #[case(0x70E5, 2)]
+    fn get_top_level_test(#[case] block_count: u32, #[case] expected: u32) {
+        assert_eq!(expected, get_top_level(block_count));
+    }
+}
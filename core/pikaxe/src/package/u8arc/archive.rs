@@ -0,0 +1,30 @@
+use crate::ark::ArkOffsetEntry;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub(crate) const U8_MAGIC: u32 = 0x55AA_382D;
+pub(crate) const U8_ROOT_NODE_OFFSET: u32 = 0x20;
+pub(crate) const U8_NODE_SIZE: usize = 12;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct U8Node {
+    pub is_dir: bool,
+    pub name_offset: u32,
+    pub data_offset: u32, // Parent index for directories
+    pub size: u32, // Index after last child node for directories
+}
+
+#[derive(Debug)]
+pub(crate) enum U8Source {
+    File(PathBuf),
+    Data(Vec<u8>),
+}
+
+/// Archive used by Wii games to wrap arks and other assets
+#[derive(Debug)]
+pub struct U8Archive {
+    /// Files in archive (directories are only used to build paths). Ids are node indices.
+    pub entries: Vec<ArkOffsetEntry>,
+    pub(crate) source: U8Source,
+    pub(crate) lookup: HashMap<String, u32>, // Lowercase path -> entry id
+}
@@ -0,0 +1,359 @@
+use crate::ark::{ArkBuilderEntry, BuilderEntries};
+use crate::package::u8arc::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const DATA_ALIGNMENT: u64 = 0x40;
+const FILE_ALIGNMENT: u64 = 0x20;
+
+type NodeSources<'a> = Vec<Option<&'a ArkBuilderEntry>>; // Data source for each node (none for directories)
+
+#[derive(Debug, Default)]
+pub struct U8Builder {
+    entries: BuilderEntries,
+}
+
+impl U8Builder {
+    pub fn new() -> U8Builder {
+        U8Builder::default()
+    }
+
+    pub fn from_dir<T: AsRef<Path>>(dir_path: T) -> Result<U8Builder, U8WriteError> {
+        let dir_path = dir_path.as_ref();
+
+        Ok(U8Builder {
+            entries: BuilderEntries::from_dir(dir_path)
+                .ok_or_else(|| U8WriteError::CantReadEntry {
+                    path: dir_path.to_str().unwrap_or_default().to_owned()
+                })?
+        })
+    }
+
+    pub fn and_file<S: Into<String>, T: Into<PathBuf>>(mut self, archive_path: S, file_path: T) -> Self {
+        self.entries.push_file(archive_path, file_path);
+        self
+    }
+
+    pub fn and_data<S: Into<String>>(mut self, archive_path: S, data: Vec<u8>) -> Self {
+        self.entries.push_data(archive_path, data);
+        self
+    }
+
+    pub fn get_entries(&self) -> &Vec<ArkBuilderEntry> {
+        self.entries.get_entries()
+    }
+
+    /// Writes archive to output path. Nodes are written depth-first with names sorted
+    /// case-insensitively and file data is aligned to 32 bytes.
+    pub fn build<T: AsRef<Path>>(mut self, output_path: T) -> Result<U8Archive, U8WriteError> {
+        let output_path = output_path.as_ref();
+
+        self.entries.get_entries_mut().sort_by_cached_key(|e| get_path_parts(&e.path)
+            .into_iter()
+            .map(|p| p.to_ascii_lowercase())
+            .collect::<Vec<_>>());
+
+        let (mut nodes, string_table, sources) = create_nodes(self.entries.get_entries())?;
+
+        let header_size = (nodes.len() * U8_NODE_SIZE + string_table.len()) as u64;
+        let data_offset = align(U8_ROOT_NODE_OFFSET as u64 + header_size, DATA_ALIGNMENT);
+
+        // Assign file offsets
+        let mut current_offset = data_offset;
+
+        for (node, source) in nodes.iter_mut().zip(sources.iter()) {
+            if source.is_none() {
+                continue;
+            }
+
+            node.data_offset = u32::try_from(current_offset)
+                .map_err(|_| U8WriteError::ArchiveTooLarge)?;
+            current_offset = align(current_offset + node.size as u64, FILE_ALIGNMENT);
+        }
+
+        if current_offset > u32::MAX as u64 {
+            return Err(U8WriteError::ArchiveTooLarge);
+        }
+
+        if let Some(output_dir) = output_path.parent() {
+            std::fs::create_dir_all(output_dir)
+                .map_err(|_| U8WriteError::CantWriteArchive)?;
+        }
+
+        let file = File::create(output_path)
+            .map_err(|_| U8WriteError::CantWriteArchive)?;
+        let mut writer = BufWriter::new(file);
+
+        // Write header
+        let mut header = Vec::with_capacity(data_offset as usize);
+        header.extend_from_slice(&U8_MAGIC.to_be_bytes());
+        header.extend_from_slice(&U8_ROOT_NODE_OFFSET.to_be_bytes());
+        header.extend_from_slice(&(header_size as u32).to_be_bytes());
+        header.extend_from_slice(&(data_offset as u32).to_be_bytes());
+        header.resize(U8_ROOT_NODE_OFFSET as usize, 0);
+
+        for node in nodes.iter() {
+            let type_and_name = ((node.is_dir as u32) << 24) | (node.name_offset & 0xFF_FFFF);
+
+            header.extend_from_slice(&type_and_name.to_be_bytes());
+            header.extend_from_slice(&node.data_offset.to_be_bytes());
+            header.extend_from_slice(&node.size.to_be_bytes());
+        }
+
+        header.extend_from_slice(&string_table);
+        header.resize(data_offset as usize, 0);
+
+        writer.write_all(&header)
+            .map_err(|_| U8WriteError::CantWriteArchive)?;
+
+        // Write file data
+        let mut current_offset = data_offset;
+
+        for (node, source) in nodes.iter().zip(sources) {
+            let Some(source) = source else {
+                continue;
+            };
+
+            let padding = (node.data_offset as u64) - current_offset;
+            writer.write_all(&vec![0u8; padding as usize])
+                .map_err(|_| U8WriteError::CantWriteArchive)?;
+
+            let written = source.write_data(&mut writer)
+                .map_err(|_| U8WriteError::CantReadEntry { path: source.path.to_owned() })?;
+
+            if written != node.size as u64 {
+                return Err(U8WriteError::CantReadEntry { path: source.path.to_owned() });
+            }
+
+            current_offset = node.data_offset as u64 + written;
+        }
+
+        writer.flush()
+            .map_err(|_| U8WriteError::CantWriteArchive)?;
+        drop(writer);
+
+        U8Archive::from_path(output_path)
+            .map_err(|_| U8WriteError::CantWriteArchive)
+    }
+}
+
+fn align(value: u64, alignment: u64) -> u64 {
+    value.div_ceil(alignment) * alignment
+}
+
+fn get_path_parts(path: &str) -> Vec<&str> {
+    path
+        .split(['/', '\\'])
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// Creates nodes for entries (expects entries sorted by path). Directories are added before
+/// their children and store parent index + index after last child.
+fn create_nodes(entries: &[ArkBuilderEntry]) -> Result<(Vec<U8Node>, Vec<u8>, NodeSources<'_>), U8WriteError> {
+    let mut string_table = vec![0u8]; // Root has empty name
+    let mut nodes = vec![U8Node {
+        is_dir: true,
+        ..Default::default()
+    }];
+    let mut sources = vec![None];
+
+    // Open directories as (lowercase name, node index)
+    let mut dir_stack: Vec<(String, usize)> = Vec::new();
+
+    let add_name = |name: &str, string_table: &mut Vec<u8>| {
+        let offset = string_table.len() as u32;
+        string_table.extend_from_slice(name.as_bytes());
+        string_table.push(0);
+        offset
+    };
+
+    for entry in entries.iter() {
+        let parts = get_path_parts(&entry.path);
+
+        let Some((file_name, dir_names)) = parts.split_last() else {
+            continue;
+        };
+
+        // Close directories not shared with current entry
+        let shared_count = dir_stack
+            .iter()
+            .zip(dir_names.iter())
+            .take_while(|((open_name, _), name)| open_name.eq(&name.to_ascii_lowercase()))
+            .count();
+
+        for (_, idx) in dir_stack.drain(shared_count..) {
+            nodes[idx].size = nodes.len() as u32;
+        }
+
+        for dir_name in dir_names.iter().skip(shared_count) {
+            let parent = dir_stack.last().map(|(_, idx)| *idx).unwrap_or_default();
+            dir_stack.push((dir_name.to_ascii_lowercase(), nodes.len()));
+
+            nodes.push(U8Node {
+                is_dir: true,
+                name_offset: add_name(dir_name, &mut string_table),
+                data_offset: parent as u32,
+                size: 0,
+            });
+            sources.push(None);
+        }
+
+        let size = entry.get_size()
+            .map_err(|_| U8WriteError::CantReadEntry { path: entry.path.to_owned() })?;
+
+        nodes.push(U8Node {
+            is_dir: false,
+            name_offset: add_name(file_name, &mut string_table),
+            data_offset: 0,
+            size: u32::try_from(size).map_err(|_| U8WriteError::ArchiveTooLarge)?,
+        });
+        sources.push(Some(entry));
+    }
+
+    for (_, idx) in dir_stack.drain(..) {
+        nodes[idx].size = nodes.len() as u32;
+    }
+
+    // Root size is total node count
+    nodes[0].size = nodes.len() as u32;
+
+    if string_table.len() > 0xFF_FFFF {
+        return Err(U8WriteError::ArchiveTooLarge);
+    }
+
+    Ok((nodes, string_table, sources))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+    use crate::io::{VfsError, VirtualFileSystem};
+
+    use crate::package::tests::get_test_data;
+
+    fn get_test_path(name: &str) -> PathBuf {
+        crate::package::tests::get_test_path(name, "archive.arc")
+    }
+
+    fn create_test_builder() -> U8Builder {
+        U8Builder::new()
+            .and_data("gen/main_wii.hdr", get_test_data(0x45, 1))
+            .and_data("gen/main_wii_0.ark", get_test_data(0x1234, 2))
+            .and_data("config/sub/a.dta", b"(a)".to_vec())
+            .and_data("Banner.bin", get_test_data(0x20, 3))
+            .and_data("config/b.dta", b"(b)".to_vec())
+            .and_data("empty.bin", Vec::new())
+    }
+
+    #[rstest]
+    fn build_and_read_archive() {
+        let archive_path = get_test_path("u8_build_and_read_archive");
+        let archive = create_test_builder()
+            .build(&archive_path)
+            .unwrap();
+
+        let paths = archive.entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.size))
+            .collect::<Vec<_>>();
+
+        assert_eq!(vec![
+            ("Banner.bin", 0x20),
+            ("config/b.dta", 3),
+            ("config/sub/a.dta", 3),
+            ("empty.bin", 0),
+            ("gen/main_wii.hdr", 0x45),
+            ("gen/main_wii_0.ark", 0x1234),
+        ], paths);
+
+        // File data is aligned
+        assert!(archive.entries.iter().all(|e| e.offset % FILE_ALIGNMENT == 0));
+
+        let get_data = |path: &str| {
+            let entry = archive.entries.iter().find(|e| e.path == path).unwrap();
+            archive.get_stream(entry.id).unwrap()
+        };
+
+        assert_eq!(get_test_data(0x20, 3), get_data("Banner.bin"));
+        assert_eq!(b"(b)".to_vec(), get_data("config/b.dta"));
+        assert_eq!(b"(a)".to_vec(), get_data("config/sub/a.dta"));
+        assert_eq!(Vec::<u8>::new(), get_data("empty.bin"));
+        assert_eq!(get_test_data(0x45, 1), get_data("gen/main_wii.hdr"));
+        assert_eq!(get_test_data(0x1234, 2), get_data("gen/main_wii_0.ark"));
+    }
+
+    #[rstest]
+    fn build_archive_node_table() {
+        let archive_path = get_test_path("u8_build_archive_node_table");
+        create_test_builder()
+            .build(&archive_path)
+            .unwrap();
+
+        let data = std::fs::read(&archive_path).unwrap();
+        let read_node = |i: usize| {
+            let offset = U8_ROOT_NODE_OFFSET as usize + (i * U8_NODE_SIZE);
+            let values = (0..3)
+                .map(|j| u32::from_be_bytes(data[(offset + j * 4)..(offset + j * 4 + 4)].try_into().unwrap()))
+                .collect::<Vec<_>>();
+
+            (values[0] >> 24, values[1], values[2])
+        };
+
+        // root, Banner.bin, config/, b.dta, sub/, a.dta, empty.bin, gen/, main_wii.hdr, main_wii_0.ark
+        assert_eq!((1, 0, 10), read_node(0));
+        assert_eq!((1, 0, 6), read_node(2)); // config
+        assert_eq!((1, 2, 6), read_node(4)); // config/sub
+        assert_eq!((1, 0, 10), read_node(7)); // gen
+        assert_eq!((0, 0x20), (read_node(1).0, read_node(1).2)); // Banner.bin
+    }
+
+    #[rstest]
+    fn read_archive_from_data() {
+        let archive_path = get_test_path("u8_read_archive_from_data");
+        create_test_builder()
+            .build(&archive_path)
+            .unwrap();
+
+        let archive = U8Archive::from_data(std::fs::read(&archive_path).unwrap()).unwrap();
+
+        assert_eq!(None, archive.get_path());
+        assert_eq!(6, archive.entries.len());
+        assert!(archive.exists("ark://Config/Sub/A.dta"));
+        assert!(!archive.exists("config/sub"));
+        assert_eq!(b"(a)".to_vec(), archive.read("config/sub/a.dta").unwrap());
+        assert_eq!(get_test_data(0x1234, 2), archive.read("gen/main_wii_0.ark").unwrap());
+        assert_eq!(vec!["config/b.dta", "config/sub/a.dta"], archive.list_files("config"));
+        assert!(matches!(archive.read("missing.dta"), Err(VfsError::FileNotFound { .. })));
+    }
+
+    #[rstest]
+    fn read_non_u8_archive() {
+        let archive_path = get_test_path("u8_read_non_u8_archive");
+        std::fs::create_dir_all(archive_path.parent().unwrap()).unwrap();
+        std::fs::write(&archive_path, vec![0xAAu8; 0x40]).unwrap();
+
+        assert!(!U8Archive::is_u8_archive(&archive_path));
+        assert!(matches!(U8Archive::from_path(&archive_path), Err(U8ReadError::NotU8Archive)));
+        assert!(matches!(U8Archive::from_data(vec![0u8; 4]), Err(U8ReadError::NotU8Archive)));
+    }
+
+    #[rstest]
+    #[case(0x20, 0xFFFF_FFF0)] // Overflows
+    #[case(0x20, 0x1000)] // Past end of archive
+    fn read_u8_archive_invalid_header_size(#[case] root_offset: u32, #[case] header_size: u32) {
+        let mut data = vec![0u8; 0x40];
+        data[0..4].copy_from_slice(&U8_MAGIC.to_be_bytes());
+        data[4..8].copy_from_slice(&root_offset.to_be_bytes());
+        data[8..12].copy_from_slice(&header_size.to_be_bytes());
+
+        let archive_path = get_test_path(&format!("u8_read_u8_archive_invalid_header_size_{header_size:X}"));
+        std::fs::create_dir_all(archive_path.parent().unwrap()).unwrap();
+        std::fs::write(&archive_path, &data).unwrap();
+
+        assert!(matches!(U8Archive::from_path(&archive_path), Err(U8ReadError::InvalidNodeTable)));
+        assert!(matches!(U8Archive::from_data(data), Err(U8ReadError::InvalidNodeTable)));
+    }
+}
@@ -0,0 +1,31 @@
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum U8ReadError {
+    #[error("Can't open U8 archive")]
+    CantOpenArchive,
+    #[error("File isn't a U8 archive")]
+    NotU8Archive,
+    #[error("Can't read U8 node table")]
+    InvalidNodeTable,
+    #[error("Entry with id {id} not found")]
+    EntryNotFound {
+        id: u32
+    },
+    #[error("Can't read data for entry \"{path}\"")]
+    CantReadEntry {
+        path: String
+    },
+}
+
+#[derive(Debug, ThisError)]
+pub enum U8WriteError {
+    #[error("Can't write U8 archive")]
+    CantWriteArchive,
+    #[error("Can't read data for entry \"{path}\"")]
+    CantReadEntry {
+        path: String
+    },
+    #[error("U8 archive exceeds max size of 4GB")]
+    ArchiveTooLarge,
+}
@@ -0,0 +1,250 @@
+use crate::ark::ArkOffsetEntry;
+use crate::io::*;
+use crate::package::u8arc::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+
+impl U8Archive {
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<U8Archive, U8ReadError> {
+        let path = path.as_ref();
+
+        let mut file = File::open(path)
+            .map_err(|_| U8ReadError::CantOpenArchive)?;
+
+        let mut header = [0u8; 0x20];
+        file.read_exact(&mut header)
+            .map_err(|_| U8ReadError::NotU8Archive)?;
+
+        let (root_offset, header_size) = parse_header(&header)?;
+
+        let file_size = file.metadata()
+            .map_err(|_| U8ReadError::CantOpenArchive)?
+            .len();
+        let node_table_range = get_node_table_range(root_offset, header_size, file_size)?;
+
+        // Read node table + string table
+        let mut node_data = vec![0u8; node_table_range.len()];
+        file.seek(std::io::SeekFrom::Start(node_table_range.start as u64))
+            .and_then(|_| file.read_exact(&mut node_data))
+            .map_err(|_| U8ReadError::InvalidNodeTable)?;
+
+        U8Archive::from_node_data(&node_data, U8Source::File(path.to_owned()))
+    }
+
+    /// Reads archive from memory (i.e. archive stored inside ark)
+    pub fn from_data(data: Vec<u8>) -> Result<U8Archive, U8ReadError> {
+        let (root_offset, header_size) = parse_header(data.get(..0x20).ok_or(U8ReadError::NotU8Archive)?)?;
+
+        let node_table_range = get_node_table_range(root_offset, header_size, data.len() as u64)?;
+        let node_data = data[node_table_range].to_vec();
+
+        U8Archive::from_node_data(&node_data, U8Source::Data(data))
+    }
+
+    pub fn is_u8_archive<T: AsRef<Path>>(path: T) -> bool {
+        let mut magic = [0u8; 4];
+
+        File::open(path)
+            .and_then(|mut f| f.read_exact(&mut magic))
+            .map(|_| u32::from_be_bytes(magic) == U8_MAGIC)
+            .unwrap_or_default()
+    }
+
+    fn from_node_data(node_data: &[u8], source: U8Source) -> Result<U8Archive, U8ReadError> {
+        let nodes = parse_nodes(node_data)?;
+        let string_table = &node_data[(nodes.len() * U8_NODE_SIZE)..];
+
+        let get_name = |node: &U8Node| -> Result<String, U8ReadError> {
+            let name_data = string_table
+                .get((node.name_offset as usize)..)
+                .ok_or(U8ReadError::InvalidNodeTable)?;
+
+            let name_length = name_data
+                .iter()
+                .position(|b| *b == 0)
+                .unwrap_or(name_data.len());
+
+            Ok(String::from_utf8_lossy(&name_data[..name_length]).to_string())
+        };
+
+        // Track current directories as (end index, path)
+        let mut dir_stack: Vec<(u32, String)> = vec![(nodes[0].size, String::new())];
+        let mut entries = Vec::new();
+
+        for (i, node) in nodes.iter().enumerate().skip(1) {
+            while dir_stack.len() > 1 && dir_stack.last().map(|(end, _)| i as u32 >= *end).unwrap_or_default() {
+                dir_stack.pop();
+            }
+
+            let name = get_name(node)?;
+            let path = match dir_stack.last() {
+                Some((_, dir_path)) if !dir_path.is_empty() => format!("{dir_path}/{name}"),
+                _ => name
+            };
+
+            if node.is_dir {
+                if node.size <= i as u32 || node.size > nodes.len() as u32 {
+                    return Err(U8ReadError::InvalidNodeTable);
+                }
+
+                dir_stack.push((node.size, path));
+                continue;
+            }
+
+            entries.push(ArkOffsetEntry {
+                id: i as u32,
+                path,
+                offset: node.data_offset as u64,
+                part: 0,
+                size: node.size as usize,
+                inflated_size: 0,
+            });
+        }
+
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let lookup = entries
+            .iter()
+            .map(|e| (e.path.to_ascii_lowercase(), e.id))
+            .collect::<HashMap<_, _>>();
+
+        Ok(U8Archive {
+            entries,
+            source,
+            lookup,
+        })
+    }
+
+    pub fn get_path(&self) -> Option<&Path> {
+        match &self.source {
+            U8Source::File(path) => Some(path.as_path()),
+            U8Source::Data(_) => None,
+        }
+    }
+
+    fn get_entry(&self, id: u32) -> Result<&ArkOffsetEntry, U8ReadError> {
+        self.entries
+            .iter()
+            .find(|e| e.id == id)
+            .ok_or(U8ReadError::EntryNotFound { id })
+    }
+
+    pub fn get_stream(&self, id: u32) -> Result<Vec<u8>, U8ReadError> {
+        let entry = self.get_entry(id)?;
+        let mut data = Vec::with_capacity(entry.size);
+
+        self.get_reader(id)?
+            .read_to_end(&mut data)
+            .map_err(|_| U8ReadError::CantReadEntry { path: entry.path.to_owned() })?;
+
+        Ok(data)
+    }
+
+    pub fn get_reader(&self, id: u32) -> Result<Box<dyn Read + '_>, U8ReadError> {
+        let entry = self.get_entry(id)?;
+        let start = entry.offset;
+        let end = entry.offset + entry.size as u64;
+
+        match &self.source {
+            U8Source::File(path) => {
+                let mut file = File::open(path)
+                    .map_err(|_| U8ReadError::CantOpenArchive)?;
+
+                file.seek(std::io::SeekFrom::Start(start))
+                    .map_err(|_| U8ReadError::CantReadEntry { path: entry.path.to_owned() })?;
+
+                Ok(Box::new(file.take(entry.size as u64)))
+            },
+            U8Source::Data(data) => {
+                let entry_data = data
+                    .get((start as usize)..(end as usize))
+                    .ok_or_else(|| U8ReadError::CantReadEntry { path: entry.path.to_owned() })?;
+
+                Ok(Box::new(Cursor::new(entry_data)))
+            }
+        }
+    }
+}
+
+impl VirtualFileSystem for U8Archive {
+    fn exists(&self, path: &str) -> bool {
+        self.lookup.contains_key(&normalize_vfs_path(path).to_ascii_lowercase())
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn Read + '_>, VfsError> {
+        let id = self.lookup
+            .get(&normalize_vfs_path(path).to_ascii_lowercase())
+            .ok_or_else(|| VfsError::FileNotFound { path: path.to_owned() })?;
+
+        self.get_reader(*id)
+            .map_err(|_| VfsError::CantReadFile { path: path.to_owned() })
+    }
+
+    fn list_files(&self, dir_path: &str) -> Vec<String> {
+        let dir_path = normalize_vfs_path(dir_path);
+
+        self.entries
+            .iter()
+            .filter(|e| is_in_dir(&e.path, &dir_path))
+            .map(|e| e.path.to_owned())
+            .collect()
+    }
+}
+
+/// Returns root node offset + size of node and string tables
+fn parse_header(header: &[u8]) -> Result<(u32, u32), U8ReadError> {
+    let mut stream = MemoryStream::from_slice_as_read(header);
+    let mut reader = BinaryStream::from_stream_with_endian(&mut stream, IOEndian::Big);
+
+    let magic = reader.read_uint32().map_err(|_| U8ReadError::NotU8Archive)?;
+    if magic != U8_MAGIC {
+        return Err(U8ReadError::NotU8Archive);
+    }
+
+    let root_offset = reader.read_uint32().map_err(|_| U8ReadError::NotU8Archive)?;
+    let header_size = reader.read_uint32().map_err(|_| U8ReadError::NotU8Archive)?;
+
+    Ok((root_offset, header_size))
+}
+
+/// Gets range of node + string table, checking that it's within archive
+fn get_node_table_range(root_offset: u32, header_size: u32, archive_size: u64) -> Result<std::ops::Range<usize>, U8ReadError> {
+    let end = root_offset
+        .checked_add(header_size)
+        .filter(|end| (*end as u64) <= archive_size)
+        .ok_or(U8ReadError::InvalidNodeTable)?;
+
+    Ok((root_offset as usize)..(end as usize))
+}
+
+fn parse_nodes(node_data: &[u8]) -> Result<Vec<U8Node>, U8ReadError> {
+    let mut stream = MemoryStream::from_slice_as_read(node_data);
+    let mut reader = BinaryStream::from_stream_with_endian(&mut stream, IOEndian::Big);
+
+    let mut read_node = || -> Result<U8Node, Box<dyn std::error::Error>> {
+        let type_and_name = reader.read_uint32()?;
+
+        Ok(U8Node {
+            is_dir: (type_and_name >> 24) == 1,
+            name_offset: type_and_name & 0xFF_FFFF,
+            data_offset: reader.read_uint32()?,
+            size: reader.read_uint32()?,
+        })
+    };
+
+    // Root node size is total node count
+    let root = read_node().map_err(|_| U8ReadError::InvalidNodeTable)?;
+    if !root.is_dir || root.size == 0 || (root.size as usize * U8_NODE_SIZE) > node_data.len() {
+        return Err(U8ReadError::InvalidNodeTable);
+    }
+
+    let mut nodes = vec![root];
+
+    for _ in 1..nodes[0].size {
+        nodes.push(read_node().map_err(|_| U8ReadError::InvalidNodeTable)?);
+    }
+
+    Ok(nodes)
+}
@@ -0,0 +1,9 @@
+mod archive;
+mod builder;
+mod errors;
+mod io;
+
+pub use self::archive::*;
+pub use self::builder::*;
+pub use self::errors::*;
+pub use self::io::*;
@@ -0,0 +1,417 @@
+use crate::{Platform, SystemInfo};
+use crate::io::{IOEndian, MiloArchive};
+use crate::scene::{Object, ObjectDir};
+use std::path::Path;
+use thiserror::Error as ThisError;
+
+#[derive(Debug, ThisError)]
+pub enum PresetError {
+    #[error("Game preset \"{name}\" not found (available: {available})")]
+    UnknownPreset {
+        name: String,
+        available: String
+    },
+}
+
+/// Milo version, platforms and object versions used by game
+#[derive(Debug)]
+pub struct GamePreset {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub version: u32,
+    /// Supported platforms (first is default)
+    pub platforms: &'static [Platform],
+    /// Use little endian on every platform (PS2 is always little endian)
+    pub little_endian: bool,
+    /// Versions of objects written by game (by class name)
+    pub object_versions: &'static [(&'static str, u32)],
+}
+
+pub static GAME_PRESETS: &[GamePreset] = &[
+    GamePreset {
+        id: "amp",
+        name: "Amplitude",
+        aliases: &[],
+        version: 10,
+        platforms: &[Platform::PS2],
+        little_endian: true,
+        object_versions: &[
+            ("Draw", 0),
+            ("Mesh", 14),
+            ("Tex", 5),
+        ],
+    },
+    GamePreset {
+        id: "antigrav",
+        name: "EyeToy: AntiGrav",
+        aliases: &[],
+        version: 10,
+        platforms: &[Platform::PS2],
+        little_endian: true,
+        object_versions: &[
+            ("Mesh", 22),
+            ("Tex", 7),
+        ],
+    },
+    GamePreset {
+        id: "gh1",
+        name: "Guitar Hero",
+        aliases: &[],
+        version: 10,
+        platforms: &[Platform::PS2],
+        little_endian: true,
+        object_versions: &[
+            ("Anim", 0),
+            ("Cam", 9),
+            ("Draw", 1),
+            ("Group", 7),
+            ("Mat", 21),
+            ("Mesh", 25),
+            ("Tex", 8),
+            ("Trans", 8),
+        ],
+    },
+    GamePreset {
+        id: "gh2",
+        name: "Guitar Hero II",
+        aliases: &[],
+        version: 24,
+        platforms: &[Platform::PS2],
+        little_endian: true,
+        object_versions: &[
+            ("Anim", 4),
+            ("BandPlacer", 2),
+            ("Cam", 12),
+            ("CharBone", 2),
+            ("CharClip", 5),
+            ("CharClipSamples", 10),
+            ("CharHair", 2),
+            ("Group", 12),
+            ("Mat", 27),
+            ("Mesh", 28),
+            ("SynthSample", 11),
+            ("Tex", 10),
+            ("Trans", 9),
+            ("TransAnim", 6),
+        ],
+    },
+    GamePreset {
+        id: "gh80s",
+        name: "Guitar Hero Encore: Rocks the 80s",
+        aliases: &["gh_80s", "gh2_80s"],
+        version: 25,
+        platforms: &[Platform::PS2],
+        little_endian: true,
+        object_versions: &[
+            ("Anim", 4),
+            ("BandPlacer", 2),
+            ("Cam", 12),
+            ("CharBone", 2),
+            ("CharClip", 5),
+            ("CharClipSamples", 10),
+            ("CharHair", 2),
+            ("Group", 12),
+            ("Mat", 27),
+            ("Mesh", 28),
+            ("Tex", 10),
+            ("Trans", 9),
+        ],
+    },
+    GamePreset {
+        id: "gh2_x360",
+        name: "Guitar Hero II (Xbox 360)",
+        aliases: &["gh2_360", "gh2x360"],
+        version: 25,
+        platforms: &[Platform::X360],
+        little_endian: true,
+        object_versions: &[
+            ("BandPlacer", 2),
+            ("CharBone", 3),
+            ("CharClip", 5),
+            ("CharClipSamples", 11),
+            ("CharHair", 2),
+            ("CubeTex", 1),
+            ("Group", 12),
+            ("Mat", 28),
+            ("Mesh", 28),
+            ("SynthSample", 11),
+            ("Tex", 10),
+        ],
+    },
+    GamePreset {
+        id: "rb1",
+        name: "Rock Band",
+        aliases: &["rb"],
+        version: 25,
+        platforms: &[Platform::X360, Platform::PS3, Platform::Wii],
+        little_endian: false,
+        object_versions: &[
+            ("ColorPalette", 1),
+            ("Group", 13),
+            ("Mat", 41),
+            ("Mesh", 34),
+            ("Tex", 10),
+            ("Trans", 9),
+        ],
+    },
+    GamePreset {
+        id: "rb2",
+        name: "Rock Band 2",
+        aliases: &[],
+        version: 25,
+        platforms: &[Platform::X360, Platform::PS3, Platform::Wii],
+        little_endian: false,
+        object_versions: &[
+            ("ColorPalette", 1),
+            ("Mat", 47),
+            ("Mesh", 34),
+            ("Tex", 10),
+            ("Trans", 9),
+        ],
+    },
+    GamePreset {
+        id: "tbrb",
+        name: "The Beatles: Rock Band",
+        aliases: &["beatles"],
+        version: 25,
+        platforms: &[Platform::X360, Platform::PS3, Platform::Wii],
+        little_endian: false,
+        object_versions: &[
+            ("Anim", 4),
+            ("Cam", 12),
+            ("CharBone", 8),
+            ("CharBonesSamples", 16),
+            ("CharClip", 12),
+            ("CharClipSamples", 16),
+            ("ColorPalette", 1),
+            ("CubeTex", 2),
+            ("Draw", 3),
+            ("Group", 14),
+            ("Mat", 55),
+            ("Mesh", 36),
+            ("SynthSample", 13),
+            ("Tex", 10),
+            ("Trans", 9),
+            ("TransAnim", 7),
+        ],
+    },
+    GamePreset {
+        id: "gdrb",
+        name: "Green Day: Rock Band",
+        aliases: &["greenday"],
+        version: 25,
+        platforms: &[Platform::X360, Platform::PS3, Platform::Wii],
+        little_endian: false,
+        object_versions: &[
+            ("CharBonesSamples", 16),
+            ("CharClip", 12),
+            ("CharClipSamples", 16),
+            ("ColorPalette", 1),
+            ("Draw", 4),
+            ("Group", 14),
+            ("Mat", 56),
+            ("Mesh", 37),
+            ("SynthSample", 13),
+            ("Tex", 11),
+            ("Trans", 9),
+        ],
+    },
+    GamePreset {
+        id: "rb3",
+        name: "Rock Band 3",
+        aliases: &[],
+        version: 28,
+        platforms: &[Platform::X360, Platform::PS3, Platform::Wii],
+        little_endian: false,
+        object_versions: &[
+            ("ColorPalette", 1),
+            ("CubeTex", 2),
+            ("Mat", 68),
+            ("Mesh", 38),
+            ("Tex", 11),
+        ],
+    },
+    GamePreset {
+        id: "dc1",
+        name: "Dance Central",
+        aliases: &["dc"],
+        version: 28,
+        platforms: &[Platform::X360],
+        little_endian: false,
+        object_versions: &[
+            ("CubeTex", 2),
+        ],
+    },
+];
+
+impl GamePreset {
+    /// Finds preset by id or alias (case-insensitive)
+    pub fn find(name: &str) -> Option<&'static GamePreset> {
+        let name = name.trim();
+
+        GAME_PRESETS
+            .iter()
+            .find(|p| p.id.eq_ignore_ascii_case(name) || p.aliases.iter().any(|a| a.eq_ignore_ascii_case(name)))
+    }
+
+    pub fn from_name(name: &str) -> Result<&'static GamePreset, PresetError> {
+        GamePreset::find(name).ok_or_else(|| PresetError::UnknownPreset {
+            name: name.to_owned(),
+            available: GAME_PRESETS
+                .iter()
+                .map(|p| p.id)
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+    }
+
+    /// Guesses preset from milo version, endian, platform (from path) and versions of objects in archive.
+    /// Returns None if no preset uses milo version + endian for platform.
+    pub fn guess_from_archive(milo: &MiloArchive, milo_path: &Path) -> Option<&'static GamePreset> {
+        let info = SystemInfo::guess_system_info(milo, milo_path);
+
+        let candidates = GAME_PRESETS
+            .iter()
+            .filter(|p| p.version == info.version
+                && p.platforms.contains(&info.platform)
+                && p.get_endian(info.platform) == info.endian)
+            .collect::<Vec<_>>();
+
+        if candidates.len() <= 1 {
+            return candidates.first().copied();
+        }
+
+        let Ok(obj_dir) = milo.unpack_directory(&info) else {
+            return candidates.first().copied();
+        };
+
+        let mut object_versions = Vec::new();
+        collect_object_versions(&obj_dir, info.endian, &mut object_versions);
+
+        // Earliest preset wins on ties
+        candidates
+            .into_iter()
+            .rev()
+            .max_by_key(|p| p.get_fingerprint_score(&object_versions))
+    }
+
+    /// Gets system info for platform (uses default platform of preset if not supported)
+    pub fn get_system_info(&self, platform: Option<Platform>) -> SystemInfo {
+        let platform = platform
+            .filter(|p| self.platforms.contains(p))
+            .unwrap_or(self.platforms[0]);
+
+        SystemInfo {
+            version: self.version,
+            platform,
+            endian: self.get_endian(platform),
+        }
+    }
+
+    pub fn get_endian(&self, platform: Platform) -> IOEndian {
+        match (self.little_endian, platform) {
+            (true, _) | (_, Platform::PS2) => IOEndian::Little,
+            _ => IOEndian::Big,
+        }
+    }
+
+    pub fn get_object_version(&self, object_type: &str) -> Option<u32> {
+        self.object_versions
+            .iter()
+            .find(|(t, _)| t.eq(&object_type))
+            .map(|(_, v)| *v)
+    }
+
+    /// Matching object versions add to score, mismatched versions subtract
+    fn get_fingerprint_score(&self, object_versions: &[(String, u32)]) -> i32 {
+        object_versions
+            .iter()
+            .map(|(object_type, version)| match self.get_object_version(object_type) {
+                Some(v) if v == *version => 1,
+                Some(_) => -1,
+                None => 0
+            })
+            .sum()
+    }
+}
+
+fn collect_object_versions(obj_dir: &ObjectDir, endian: IOEndian, object_versions: &mut Vec<(String, u32)>) {
+    for entry in obj_dir.get_entries() {
+        let Object::Packed(packed) = entry else {
+            continue;
+        };
+
+        let Some(version) = packed.get_version(endian) else {
+            continue;
+        };
+
+        object_versions.push((packed.object_type.to_owned(), version));
+    }
+
+    for sub_dir in obj_dir.get_sub_dirs() {
+        collect_object_versions(sub_dir, endian, object_versions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+    use crate::scene::{ObjectDirBase, PackedObject};
+
+    #[rstest]
+    #[case("RB3", Some("rb3"))]
+    #[case(" beatles ", Some("tbrb"))]
+    #[case("gh2_360", Some("gh2_x360"))]
+    #[case("rb4", None)]
+    fn find_preset(#[case] name: &str, #[case] expected: Option<&str>) {
+        assert_eq!(expected, GamePreset::find(name).map(|p| p.id));
+    }
+
+    #[rstest]
+    #[case("gh2", None, 24, Platform::PS2, IOEndian::Little)]
+    #[case("gh2", Some(Platform::X360), 24, Platform::PS2, IOEndian::Little)]
+    #[case("gh2_x360", None, 25, Platform::X360, IOEndian::Little)]
+    #[case("tbrb", Some(Platform::PS3), 25, Platform::PS3, IOEndian::Big)]
+    #[case("rb3", Some(Platform::Wii), 28, Platform::Wii, IOEndian::Big)]
+    fn preset_system_info(#[case] name: &str, #[case] platform: Option<Platform>, #[case] version: u32, #[case] expected_platform: Platform, #[case] endian: IOEndian) {
+        let info = GamePreset::from_name(name).unwrap().get_system_info(platform);
+
+        assert_eq!(version, info.version);
+        assert_eq!(expected_platform, info.platform);
+        assert_eq!(endian, info.endian);
+    }
+
+    #[rstest]
+    fn unknown_preset_lists_available() {
+        let error = GamePreset::from_name("rb4").unwrap_err();
+        assert!(error.to_string().contains("gh1, gh2"));
+    }
+
+    #[rstest]
+    #[case(&[], "amp")]
+    #[case(&[("Mesh", 25), ("Tex", 8)], "gh1")]
+    #[case(&[("Mesh", 22), ("Tex", 8)], "antigrav")]
+    fn guess_preset_from_archive(#[case] entries: &[(&str, u32)], #[case] expected: &str) {
+        let info = GamePreset::from_name("gh1").unwrap().get_system_info(None);
+
+        let obj_dir = ObjectDir::ObjectDir(ObjectDirBase {
+            entries: entries
+                .iter()
+                .enumerate()
+                .map(|(i, (object_type, version))| Object::Packed(PackedObject {
+                    name: format!("entry_{i}"),
+                    object_type: object_type.to_string(),
+                    data: version.to_le_bytes().to_vec()
+                }))
+                .collect(),
+            ..ObjectDirBase::new()
+        });
+
+        let milo = MiloArchive::from_object_dir(&obj_dir, &info, None).unwrap();
+        let preset = GamePreset::guess_from_archive(&milo, Path::new("test.milo_ps2"));
+
+        assert_eq!(Some(expected), preset.map(|p| p.id));
+    }
+}
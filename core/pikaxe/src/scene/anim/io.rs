@@ -13,7 +13,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for AnimObject {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         load_anim(self, &mut reader, info, true)?;
@@ -21,7 +21,10 @@ impl ObjectReadWrite for AnimObject {
     }
 
     fn save(&self, _stream: &mut dyn Stream, _info: &SystemInfo) -> Result<(), Box<dyn Error>> {
-        todo!()
+        Err(Box::new(crate::Error::UnsupportedSave {
+            object_type: String::from("Anim"),
+            reason: "not implemented yet"
+        }))
     }
 }
 
@@ -22,7 +22,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for CamObject {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -24,7 +24,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for CharBone {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -37,8 +37,10 @@ impl ObjectReadWrite for CharBone {
         load_object(self, &mut reader, info)?;
         load_trans(self, &mut reader, info, false)?;
 
-        //Ok(())
-        todo!()
+        Err(Box::new(crate::Error::UnsupportedLoad {
+            object_type: String::from("CharBone"),
+            reason: "reading bone values after trans isn't implemented yet"
+        }))
     }
 
     fn save(&self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
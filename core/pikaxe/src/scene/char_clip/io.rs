@@ -104,7 +104,10 @@ pub(crate) fn load_char_clip<T: CharClip>(char_clip: &mut T, reader: &mut Box<Bi
     char_clip.set_nodes(nodes);
 
     if version < 3 {
-        todo!()
+        return Err(Box::new(crate::Error::UnsupportedLoad {
+            object_type: String::from("CharClip"),
+            reason: "reading v2 or below isn't implemented yet"
+        }));
     }
 
     if version < 7 {
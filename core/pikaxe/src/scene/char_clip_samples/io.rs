@@ -22,7 +22,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for CharClipSamples {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -114,7 +114,10 @@ impl ObjectReadWrite for CharClipSamples {
         }
 
         if version > 14 {
-            todo!("Writing of extra bone data not currently supported for v15 or above");
+            return Err(Box::new(crate::Error::UnsupportedSave {
+                object_type: String::from("CharClipSamples"),
+                reason: "writing extra bone data for v15 or above isn't implemented yet"
+            }));
         }
 
         Ok(())
@@ -22,7 +22,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for CharHair {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -99,7 +99,9 @@ impl ObjectReadWrite for CharHair {
 
         save_object(self, &mut stream, info)?;
 
-        todo!("Support for writing CharHair not implemented yet");
-        //Ok(())
+        Err(Box::new(crate::Error::UnsupportedSave {
+            object_type: String::from("CharHair"),
+            reason: "writing hair properties and strands isn't implemented yet"
+        }))
     }
 }
\ No newline at end of file
@@ -21,7 +21,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for CharLipSync {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -54,7 +54,10 @@ impl ObjectReadWrite for CharLipSync {
     }
 
     fn save(&self, _stream: &mut dyn Stream, _info: &SystemInfo) -> Result<(), Box<dyn Error>> {
-        todo!("Implement save() for CharLipSync")
+        Err(Box::new(crate::Error::UnsupportedSave {
+            object_type: String::from("CharLipSync"),
+            reason: "not implemented yet"
+        }))
 
         /*let mut writer = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
@@ -0,0 +1,267 @@
+use crate::SystemInfo;
+use crate::scene::*;
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DependencyTarget {
+    /// Object referenced by name
+    Object {
+        name: String,
+        object_type: String,
+    },
+    /// File loaded from disk (external textures, sub directory milos)
+    File(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dependency {
+    /// Name of object (or directory) with reference
+    pub source: String,
+    pub target: DependencyTarget,
+    /// Target isn't in directory (always true for files)
+    pub is_external: bool,
+}
+
+/// References between objects in directory (including inline sub directories) and to files outside of it
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    pub dependencies: Vec<Dependency>,
+    object_names: HashSet<String>, // Lowercase
+}
+
+impl DependencyGraph {
+    /// Builds graph from references of Mat, Mesh, Tex and CubeTex entries + directory sub paths.
+    /// Packed entries are unpacked to read references.
+    pub fn from_object_dir(obj_dir: &ObjectDir, info: &SystemInfo) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        graph.add_object_names(obj_dir);
+
+        let mut references = Vec::new();
+        DependencyGraph::collect_references(obj_dir, info, &mut references);
+
+        graph.dependencies = references
+            .into_iter()
+            .map(|(source, target)| Dependency {
+                is_external: match &target {
+                    DependencyTarget::Object { name, .. } => !graph.has_object(name),
+                    DependencyTarget::File(_) => true,
+                },
+                source,
+                target,
+            })
+            .collect();
+
+        graph
+    }
+
+    fn add_object_names(&mut self, obj_dir: &ObjectDir) {
+        for entry in obj_dir.get_entries() {
+            self.object_names.insert(entry.get_name().to_ascii_lowercase());
+        }
+
+        for sub_dir in obj_dir.get_sub_dirs() {
+            self.add_object_names(sub_dir);
+        }
+    }
+
+    fn collect_references(obj_dir: &ObjectDir, info: &SystemInfo, references: &mut Vec<(String, DependencyTarget)>) {
+        let ObjectDir::ObjectDir(dir) = obj_dir;
+
+        if let Some(object_dir) = dir.dir_entry.as_ref().and_then(|e| e.object_dir.as_ref()) {
+            let paths = object_dir.sub_dir_paths
+                .iter()
+                .chain(std::iter::once(&object_dir.proxy_path));
+
+            for path in paths.filter(|p| !p.is_empty()) {
+                references.push((dir.name.to_owned(), DependencyTarget::File(path.to_owned())));
+            }
+        }
+
+        for entry in dir.entries.iter() {
+            let unpacked = match entry {
+                Object::Packed(packed) if ["CubeTex", "Mat", "Mesh", "Tex"].contains(&packed.object_type.as_str()) => entry.unpack(info),
+                _ => None
+            };
+
+            let source = entry.get_name();
+            let mut add_object = |name: &String, object_type: &str| {
+                if !name.is_empty() {
+                    references.push((source.to_owned(), DependencyTarget::Object {
+                        name: name.to_owned(),
+                        object_type: object_type.to_owned()
+                    }));
+                }
+            };
+
+            match unpacked.as_ref().unwrap_or(entry) {
+                Object::Mat(mat) => {
+                    let textures = [
+                        &mat.diffuse_tex,
+                        &mat.normal_map,
+                        &mat.emissive_map,
+                        &mat.specular_map,
+                        &mat.environ_map,
+                        &mat.norm_detail_map,
+                        &mat.rim_map,
+                        &mat.alpha_mask,
+                    ];
+
+                    for tex in textures {
+                        add_object(tex, "Tex");
+                    }
+
+                    add_object(&mat.next_pass, "Mat");
+                },
+                Object::Mesh(mesh) => add_object(&mesh.mat, "Mat"),
+                Object::Tex(tex) if tex.use_ext_path && !tex.ext_path.is_empty() => {
+                    references.push((source.to_owned(), DependencyTarget::File(tex.ext_path.to_owned())));
+                },
+                Object::CubeTex(cube) => {
+                    let faces = [
+                        (&cube.right_ext_path, &cube.right),
+                        (&cube.left_ext_path, &cube.left),
+                        (&cube.top_ext_path, &cube.top),
+                        (&cube.bottom_ext_path, &cube.bottom),
+                        (&cube.front_ext_path, &cube.front),
+                        (&cube.back_ext_path, &cube.back),
+                    ];
+
+                    for (path, _) in faces.iter().filter(|(p, b)| !p.is_empty() && b.is_none()) {
+                        references.push((source.to_owned(), DependencyTarget::File(path.to_string())));
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        for sub_dir in dir.sub_dirs.iter() {
+            DependencyGraph::collect_references(sub_dir, info, references);
+        }
+    }
+
+    /// Checks if object is in directory (case-insensitive)
+    pub fn has_object(&self, name: &str) -> bool {
+        self.object_names.contains(&name.to_ascii_lowercase())
+    }
+
+    /// Gets references of object
+    pub fn get_dependencies<'a>(&'a self, source: &'a str) -> impl Iterator<Item = &'a Dependency> {
+        self.dependencies
+            .iter()
+            .filter(move |d| d.source.eq_ignore_ascii_case(source))
+    }
+
+    /// Gets dependencies which reference object or file
+    pub fn get_dependents<'a>(&'a self, target: &'a str) -> impl Iterator<Item = &'a Dependency> {
+        self.dependencies
+            .iter()
+            .filter(move |d| match &d.target {
+                DependencyTarget::Object { name, .. } => name.eq_ignore_ascii_case(target),
+                DependencyTarget::File(path) => path.eq_ignore_ascii_case(target),
+            })
+    }
+
+    /// Gets names of referenced objects not in directory (unique, in order of first reference)
+    pub fn get_external_objects(&self) -> Vec<&str> {
+        self.get_unique_targets(|t| match t {
+            DependencyTarget::Object { name, .. } => Some(name),
+            _ => None
+        })
+    }
+
+    /// Gets paths of referenced files (unique, in order of first reference)
+    pub fn get_external_files(&self) -> Vec<&str> {
+        self.get_unique_targets(|t| match t {
+            DependencyTarget::File(path) => Some(path),
+            _ => None
+        })
+    }
+
+    fn get_unique_targets<'a>(&'a self, get_target: impl Fn(&'a DependencyTarget) -> Option<&'a String>) -> Vec<&'a str> {
+        let mut found = HashSet::new();
+
+        self.dependencies
+            .iter()
+            .filter(|d| d.is_external)
+            .filter_map(|d| get_target(&d.target))
+            .filter(|t| found.insert(t.to_ascii_lowercase()))
+            .map(|t| t.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+    use crate::Platform;
+    use crate::io::IOEndian;
+
+    #[rstest]
+    fn dependency_graph_from_object_dir() {
+        let info = SystemInfo {
+            version: 25,
+            platform: Platform::X360,
+            endian: IOEndian::Big,
+        };
+
+        let mat = MatObject {
+            name: String::from("body.mat"),
+            diffuse_tex: String::from("body.tex"),
+            normal_map: String::from("shared_norm.tex"),
+            ..Default::default()
+        };
+
+        let mesh = MeshObject {
+            name: String::from("body.mesh"),
+            mat: String::from("body.mat"),
+            ..Default::default()
+        };
+
+        let other_mesh = MeshObject {
+            name: String::from("head.mesh"),
+            mat: String::from("Head.mat"),
+            ..Default::default()
+        };
+
+        let mut tex = Tex::new();
+        tex.name = String::from("body.tex");
+
+        let mut ext_tex = Tex::new();
+        ext_tex.name = String::from("logo.tex");
+        ext_tex.ext_path = String::from("../textures/logo.png");
+        ext_tex.use_ext_path = true;
+
+        let obj_dir = ObjectDir::ObjectDir(ObjectDirBase {
+            name: String::from("character"),
+            entries: vec![
+                Object::Mat(mat),
+                Object::Mesh(mesh),
+                Object::Mesh(other_mesh),
+                Object::Tex(tex),
+                Object::Tex(ext_tex),
+            ],
+            dir_entry: Some(ObjectDirEntry {
+                object_dir: Some(ObjectDirObject {
+                    sub_dir_paths: vec![String::from("../shared/head.milo")],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..ObjectDirBase::new()
+        });
+
+        let graph = DependencyGraph::from_object_dir(&obj_dir, &info);
+
+        assert_eq!(vec!["shared_norm.tex", "Head.mat"], graph.get_external_objects());
+        assert_eq!(vec!["../shared/head.milo", "../textures/logo.png"], graph.get_external_files());
+
+        assert_eq!(2, graph.get_dependencies("BODY.mat").count());
+        assert_eq!(
+            vec!["body.mesh"],
+            graph.get_dependents("body.mat").map(|d| d.source.as_str()).collect::<Vec<_>>()
+        );
+        assert!(graph.has_object("Body.Tex"));
+        assert!(!graph.has_object("head.mat"));
+    }
+}
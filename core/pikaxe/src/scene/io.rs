@@ -1,15 +1,39 @@
 use crate::dta::*;
 use crate::io::{BinaryStream, FileStream, SeekFrom, Stream};
-use crate::SystemInfo;
+use crate::{ErrorContext, SystemInfo};
 use pikaxe_traits::scene::{Color3, Color4, Matrix, Matrix3, MiloObject, Quat, Rect, Sphere, Vector2, Vector3};
 use std::error::Error;
 use std::path::Path;
 
 pub trait ObjectReadWrite {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>>;
+    /// Reads object from stream. Errors include version and offset (relative to start of object) as context.
+    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+        let start_pos = stream.pos();
+        let result = self.load_data(stream, info);
+
+        result.map_err(|e| get_load_error(e, stream, start_pos, info))
+    }
+
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>>;
     fn save(&self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>>;
 }
 
+fn get_load_error(error: Box<dyn Error>, stream: &mut dyn Stream, start_pos: u64, info: &SystemInfo) -> Box<dyn Error> {
+    let offset = stream.pos().saturating_sub(start_pos);
+
+    // Version is always first value of object data
+    let version = stream
+        .seek(SeekFrom::Start(start_pos))
+        .and_then(|_| BinaryStream::from_stream_with_endian(stream, info.endian).read_uint32())
+        .ok();
+
+    Box::new(crate::Error::from(error).with_context(ErrorContext {
+        version,
+        offset: Some(offset),
+        ..Default::default()
+    }))
+}
+
 pub fn save_to_file<T: ObjectReadWrite, S: AsRef<Path>>(obj: &T, out_path: S, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
     // Write to file
     let mut stream = FileStream::from_path_as_read_write_create(out_path.as_ref())?;
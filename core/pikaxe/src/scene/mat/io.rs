@@ -27,7 +27,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for MatObject {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -241,8 +241,10 @@ impl ObjectReadWrite for MatObject {
 
         if version > 42 {
             if version < 45 {
-                let bitfield = reader.read_uint32()?;
-                todo!("Parse point_lights from {}", bitfield);
+                return Err(Box::new(crate::Error::UnsupportedLoad {
+                    object_type: String::from("Mat"),
+                    reason: "reading point lights bitfield (v43-44) isn't implemented yet"
+                }));
             } else {
                 self.point_lights = reader.read_boolean()?;
             }
@@ -318,7 +320,10 @@ impl ObjectReadWrite for MatObject {
         save_object(self, &mut stream, info)?;
 
         if version <= 21 {
-            todo!("Saving not implemented for v21 materials");
+            return Err(Box::new(crate::Error::UnsupportedSave {
+                object_type: String::from("Mat"),
+                reason: "writing v21 or below isn't implemented yet"
+            }));
         }
 
         stream.write_uint32(self.blend as u32)?;
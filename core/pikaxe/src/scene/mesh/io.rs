@@ -32,7 +32,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for MeshObject {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -558,7 +558,10 @@ impl ObjectReadWrite for MeshObject {
                         stream.write_float32(v.tangent.w)?;
                     }
                 } else {
-                    todo!("Figure out how ng verts are packed in v36 meshes");
+                    return Err(Box::new(crate::Error::UnsupportedSave {
+                        object_type: String::from("Mesh"),
+                        reason: "packing of next-gen vertices for v36 isn't known yet"
+                    }));
                 }
             }
         }
@@ -599,7 +602,10 @@ impl ObjectReadWrite for MeshObject {
                     }
                 }
 
-                todo!("Figure out how to calculate additional group info");
+                return Err(Box::new(crate::Error::UnsupportedSave {
+                    object_type: String::from("Mesh"),
+                    reason: "calculating additional group info isn't known yet"
+                }));
             }
         }
 
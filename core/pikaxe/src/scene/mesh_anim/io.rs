@@ -21,7 +21,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for MeshAnim {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -56,7 +56,10 @@ impl ObjectReadWrite for MeshAnim {
     }
 
     fn save(&self, _stream: &mut dyn Stream, _info: &SystemInfo) -> Result<(), Box<dyn Error>> {
-        todo!("Implement save() for MeshAnim")
+        Err(Box::new(crate::Error::UnsupportedSave {
+            object_type: String::from("MeshAnim"),
+            reason: "not implemented yet"
+        }))
 
         /*let mut writer = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
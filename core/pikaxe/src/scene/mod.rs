@@ -13,6 +13,7 @@ mod char_lip_sync;
 mod character;
 mod color_palette;
 mod cube_tex;
+mod dependency;
 mod draw;
 mod group;
 mod io;
@@ -49,6 +50,7 @@ pub use char_lip_sync::*;
 pub use character::*;
 pub use color_palette::*;
 pub use cube_tex::*;
+pub use dependency::*;
 pub use draw::*;
 pub use group::*;
 pub use pikaxe_traits::scene::*;
@@ -1,6 +1,7 @@
-use crate::SystemInfo;
-use crate::io::MemoryStream;
+use crate::{ErrorContext, SystemInfo};
+use crate::io::{IOEndian, MemoryStream, Stream};
 use crate::scene::*;
+use std::error::Error;
 
 pub enum Object {
     Anim(AnimObject),
@@ -27,13 +28,27 @@ pub enum Object {
     Packed(PackedObject),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct PackedObject {
     pub name: String,
     pub object_type: String,
     pub data: Vec<u8>
 }
 
+impl PackedObject {
+    /// Gets object version (always first value of data)
+    pub fn get_version(&self, endian: IOEndian) -> Option<u32> {
+        let buffer = self.data
+            .get(..4)
+            .and_then(|b| b.try_into().ok())?;
+
+        Some(match endian {
+            IOEndian::Little => u32::from_le_bytes(buffer),
+            IOEndian::Big => u32::from_be_bytes(buffer),
+        })
+    }
+}
+
 impl Object {
     pub fn get_name(&self) -> &str {
         match self {
@@ -97,98 +112,157 @@ impl Object {
     }
 
     pub fn pack(&self, info: &SystemInfo) -> Option<Object> {
-        if self.is_packed() {
-            todo!("Already packed");
-        }
+        self.try_pack(info).ok().map(Object::Packed)
+    }
 
-        let obj: &dyn ObjectReadWrite  = match &self {
+    /// Packs object. Returns copy of data if already packed.
+    pub fn try_pack(&self, info: &SystemInfo) -> Result<PackedObject, crate::Error> {
+        let obj: &dyn ObjectReadWrite = match &self {
             Object::Anim(obj) => obj,
             Object::BandPlacer(obj) => obj,
             Object::Cam(obj) => obj,
+            Object::CharBone(obj) => obj,
             Object::CharClipSamples(obj) => obj,
+            Object::CharHair(obj) => obj,
+            Object::CharLipSync(obj) => obj,
             Object::ColorPalette(obj) => obj,
             Object::CubeTex(obj) => obj,
             Object::Draw(obj) => obj,
             Object::Group(obj) => obj,
             Object::Mat(obj) => obj,
             Object::Mesh(obj) => obj,
+            Object::MeshAnim(obj) => obj,
             Object::Morph(obj) => obj,
             Object::P9SongPref(obj) => obj,
             Object::PropAnim(obj) => obj,
+            Object::SynthSample(obj) => obj,
             Object::Tex(obj) => obj,
             Object::Trans(obj) => obj,
             Object::TransAnim(obj) => obj,
-            _ => todo!("Can't pack \"{}\" yet!", self.get_type()),
+            Object::Packed(packed) => return Ok(packed.clone()),
         };
 
         let mut data = Vec::new();
         let mut stream = MemoryStream::from_vector_as_read_write(&mut data);
 
-        if obj.save(&mut stream, info).is_err() {
-            println!("ERROR: Unable to pack {} with type {}", self.get_name(), self.get_type());
-            return None;
-        }
+        obj.save(&mut stream, info)
+            .map_err(|e| crate::Error::from(e).with_context(ErrorContext::from_entry(self.get_name(), self.get_type())))?;
 
         // Return packed object
-        Some(Object::Packed(PackedObject {
+        Ok(PackedObject {
             name: self.get_name().to_owned(),
             object_type: self.get_type().to_owned(),
             data,
-        }))
+        })
     }
 
     pub fn unpack(&self, info: &SystemInfo) -> Option<Object> {
-        match self {
-            Object::Packed(packed) => {
-                match packed.object_type.as_str() {
-                    "Anim" => unpack_object(packed, info).map(|o| Object::Anim(o)),
-                    "BandPlacer" => unpack_object(packed, info).map(|o| Object::BandPlacer(o)),
-                    "Cam" => unpack_object(packed, info).map(|o| Object::Cam(o)),
-                    "CharClipSamples" => unpack_object(packed, info).map(|o| Object::CharClipSamples(o)),
-                    "CharHair" => unpack_object(packed, info).map(|o| Object::CharHair(o)),
-                    "CharLipSync" => unpack_object(packed, info).map(|o| Object::CharLipSync(o)),
-                    "ColorPalette" => unpack_object(packed, info).map(|o| Object::ColorPalette(o)),
-                    "CubeTex" => unpack_object(packed, info).map(|o| Object::CubeTex(o)),
-                    "Draw" => unpack_object(packed, info).map(|o| Object::Draw(o)),
-                    "Group" => unpack_object(packed, info).map(|o| Object::Group(o)),
-                    "Mat" => unpack_object(packed, info).map(|o| Object::Mat(o)),
-                    "Mesh" => unpack_object(packed, info).map(|o| Object::Mesh(o)),
-                    "MeshAnim" => unpack_object(packed, info).map(|o| Object::MeshAnim(o)),
-                    "Morph" => unpack_object(packed, info).map(|o| Object::Morph(o)),
-                    "P9SongPref" => unpack_object(packed, info).map(|o| Object::P9SongPref(o)),
-                    "PropAnim" => unpack_object(packed, info).map(|o| Object::PropAnim(o)),
-                    "SynthSample" => unpack_object(packed, info).map(|o| Object::SynthSample(o)),
-                    "Tex" => {
-                        let mut stream = MemoryStream::from_slice_as_read(packed.data.as_slice());
-
-                        // TODO: Update tex to use same io traits
-                        match Tex::from_stream(&mut stream, info) {
-                            Ok(mut tex) => {
-                                tex.name = packed.name.to_owned();
-                                Some(Object::Tex(tex))
-                            },
-                            Err(_) => None,
-                        }
-                    },
-                    "Trans" => unpack_object(packed, info).map(|o| Object::Trans(o)),
-                    "TransAnim" => unpack_object(packed, info).map(|o| Object::TransAnim(o)),
-                    _ => None
-                }
+        self.try_unpack(info).ok().flatten()
+    }
+
+    /// Unpacks object. Returns `None` if object isn't packed or its type isn't supported.
+    pub fn try_unpack(&self, info: &SystemInfo) -> Result<Option<Object>, crate::Error> {
+        let Object::Packed(packed) = self else {
+            return Ok(None);
+        };
+
+        let obj = match packed.object_type.as_str() {
+            "Anim" => Object::Anim(unpack_object(packed, info)?),
+            "BandPlacer" => Object::BandPlacer(unpack_object(packed, info)?),
+            "Cam" => Object::Cam(unpack_object(packed, info)?),
+            "CharClipSamples" => Object::CharClipSamples(unpack_object(packed, info)?),
+            "CharHair" => Object::CharHair(unpack_object(packed, info)?),
+            "CharLipSync" => Object::CharLipSync(unpack_object(packed, info)?),
+            "ColorPalette" => Object::ColorPalette(unpack_object(packed, info)?),
+            "CubeTex" => Object::CubeTex(unpack_object(packed, info)?),
+            "Draw" => Object::Draw(unpack_object(packed, info)?),
+            "Group" => Object::Group(unpack_object(packed, info)?),
+            "Mat" => Object::Mat(unpack_object(packed, info)?),
+            "Mesh" => Object::Mesh(unpack_object(packed, info)?),
+            "MeshAnim" => Object::MeshAnim(unpack_object(packed, info)?),
+            "Morph" => Object::Morph(unpack_object(packed, info)?),
+            "P9SongPref" => Object::P9SongPref(unpack_object(packed, info)?),
+            "PropAnim" => Object::PropAnim(unpack_object(packed, info)?),
+            "SynthSample" => Object::SynthSample(unpack_object(packed, info)?),
+            "Tex" => {
+                let mut stream = MemoryStream::from_slice_as_read(packed.data.as_slice());
+
+                // TODO: Update tex to use same io traits
+                let mut tex = Tex::from_stream(&mut stream, info)
+                    .map_err(|e| get_unpack_error(packed, info, e))?;
+
+                tex.name = packed.name.to_owned();
+                Object::Tex(tex)
             },
-            _ => None
-        }
+            "Trans" => Object::Trans(unpack_object(packed, info)?),
+            "TransAnim" => Object::TransAnim(unpack_object(packed, info)?),
+            _ => return Ok(None)
+        };
+
+        Ok(Some(obj))
     }
 }
 
-fn unpack_object<T: Default + MiloObject + ObjectReadWrite>(packed: &PackedObject, info: &SystemInfo) -> Option<T> {
+/// Reads start of data as object of given type to find where its data ends. Returns `None` if type
+/// isn't supported or data can't be read as type (CubeTex bitmaps are read until end of data).
+pub(crate) fn get_object_data_size(object_type: &str, data: &[u8], info: &SystemInfo) -> Option<usize> {
+    let mut obj: Box<dyn ObjectReadWrite> = match object_type {
+        "Anim" => Box::new(AnimObject::default()),
+        "BandPlacer" => Box::new(BandPlacer::default()),
+        "Cam" => Box::new(CamObject::default()),
+        "CharClipSamples" => Box::new(CharClipSamples::default()),
+        "CharHair" => Box::new(CharHair::default()),
+        "CharLipSync" => Box::new(CharLipSync::default()),
+        "ColorPalette" => Box::new(ColorPalette::default()),
+        "Draw" => Box::new(DrawObject::default()),
+        "Group" => Box::new(GroupObject::default()),
+        "Mat" => Box::new(MatObject::default()),
+        "Mesh" => Box::new(MeshObject::default()),
+        "MeshAnim" => Box::new(MeshAnim::default()),
+        "Morph" => Box::new(Morph::default()),
+        "P9SongPref" => Box::new(P9SongPref::default()),
+        "PropAnim" => Box::new(PropAnim::default()),
+        "SynthSample" => Box::new(SynthSample::default()),
+        "Tex" => {
+            let mut stream = MemoryStream::from_slice_as_read(data);
+            let tex = Tex::from_stream(&mut stream, info).ok()?;
+
+            // Bitmap reads rest of data so size is calculated from encoding instead
+            return match &tex.bitmap {
+                Some(bitmap) => bitmap.calc_data_size(info)
+                    .filter(|size| *size <= bitmap.raw_data.len())
+                    .map(|size| data.len() - bitmap.raw_data.len() + size),
+                None => Some(stream.pos() as usize)
+            };
+        },
+        "Trans" => Box::new(TransObject::default()),
+        "TransAnim" => Box::new(TransAnim::default()),
+        _ => return None
+    };
+
+    let mut stream = MemoryStream::from_slice_as_read(data);
+    obj.load(&mut stream, info).ok()?;
+
+    Some(stream.pos() as usize)
+}
+
+fn unpack_object<T: Default + MiloObject + ObjectReadWrite>(packed: &PackedObject, info: &SystemInfo) -> Result<T, crate::Error> {
     let mut stream = MemoryStream::from_slice_as_read(packed.data.as_slice());
 
     let mut obj = T::default();
+    obj.load(&mut stream, info)
+        .map_err(|e| get_unpack_error(packed, info, e))?;
 
-    if obj.load(&mut stream, info).is_ok() {
-        obj.set_name(packed.name.to_owned());
-        return Some(obj);
-    }
+    obj.set_name(packed.name.to_owned());
+    Ok(obj)
+}
+
+/// Adds entry name, type and object version (always first value of data) to load error
+fn get_unpack_error(packed: &PackedObject, info: &SystemInfo, error: Box<dyn Error>) -> crate::Error {
+    let version = packed.get_version(info.endian);
 
-    None
-}
\ No newline at end of file
+    crate::Error::from(error).with_context(ErrorContext {
+        version,
+        ..ErrorContext::from_entry(&packed.name, &packed.object_type)
+    })
+}
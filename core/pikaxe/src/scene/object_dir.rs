@@ -1,15 +1,33 @@
-use crate::SystemInfo;
-use crate::io::{BinaryStream, FileSearchDepth, FileStream, MemoryStream, PathFinder, SeekFrom, Stream};
+use crate::{ErrorContext, SystemInfo};
+use crate::dta::{DataArray, DataString, parse_dta, RootData};
+use crate::io::{BinaryStream, DirectoryFileSystem, FileSearchDepth, FileStream, MemoryStream, normalize_vfs_path, PathFinder, SeekFrom, Stream, VirtualFileSystem};
 use crate::scene::*;
 use lazy_static::lazy_static;
 use log::warn;
 use regex::Regex;
+use std::collections::HashMap;
 use std::error::Error;
-
 use std::path::{Path, PathBuf};
+use thiserror::Error as ThisError;
 
 lazy_static! {
-    static ref MILO_ENTRY_REGEX: Regex = Regex::new(r"(?i)([/\\][a-z]+[/\\])[^/\\]+$").unwrap();
+    static ref MILO_ENTRY_REGEX: Regex = Regex::new(r"(?i)^[a-z]+/[^/]+$").unwrap();
+}
+
+/// Name of file storing directory values that aren't entries (name, type, inline sub directories)
+pub const DIR_INFO_FILE_NAME: &str = "dir.dta";
+
+#[derive(Debug, ThisError)]
+pub enum ObjectDirReadError {
+    #[error("Can't parse directory info from \"{path}\"")]
+    InvalidDirInfo {
+        path: String
+    },
+    #[error("Directory entry \"{name}\" of type \"{dir_type}\" not found")]
+    MissingDirectoryEntry {
+        name: String,
+        dir_type: String
+    },
 }
 
 pub enum ObjectDir {
@@ -20,7 +38,40 @@ pub struct ObjectDirBase {
     pub entries: Vec<Object>,
     pub name: String,
     pub dir_type: String,
+    /// String table values from directory header (v24+)
+    pub string_table_count: u32,
+    pub string_table_size: u32,
+    /// Unknown bool from directory header (v32+)
+    pub unknown_bool: bool,
+    /// Paths of objects outside of directory (v10)
+    pub external_paths: Vec<String>,
+    /// Directory entry data (v11+)
+    pub dir_entry: Option<ObjectDirEntry>,
+    /// Inline sub directories (stored inside directory entry)
     pub sub_dirs: Vec<ObjectDir>,
+    /// Packed data of unpacked entries by name. Written back when entry can't be packed again.
+    pub original_data: HashMap<String, Vec<u8>>,
+    /// System of original data (only written back when packing for compatible system)
+    pub original_info: Option<SystemInfo>,
+}
+
+#[derive(Debug, Default)]
+pub struct ObjectDirEntry {
+    /// Raw data with inline sub directories removed
+    pub data: Vec<u8>,
+    /// Position in data where inline sub directories are written
+    pub sub_dirs_offset: usize,
+    /// Parsed common fields (read-only, raw data is written back as is)
+    pub object_dir: Option<ObjectDirObject>,
+}
+
+#[derive(Default)]
+struct ObjectDirInfo {
+    name: String,
+    dir_type: String,
+    sub_dirs_offset: Option<usize>,
+    sub_dirs: Vec<String>,
+    external_paths: Vec<String>,
 }
 
 impl ObjectDirBase {
@@ -29,32 +80,79 @@ impl ObjectDirBase {
             entries: Vec::new(),
             name: String::new(),
             dir_type: String::new(),
+            string_table_count: 0,
+            string_table_size: 0,
+            unknown_bool: false,
+            external_paths: Vec::new(),
+            dir_entry: None,
             sub_dirs: Vec::new(),
+            original_data: HashMap::new(),
+            original_info: None,
         }
     }
 }
 
 impl<'a> ObjectDir {
-    pub fn from_path(path: &Path, _info: &SystemInfo) -> Result<ObjectDir, Box<dyn Error>> {
+    pub fn from_path(path: &Path, info: &SystemInfo) -> Result<ObjectDir, Box<dyn Error>> {
+        let fs = DirectoryFileSystem::new(path);
+        ObjectDir::from_vfs(&fs, "", info)
+    }
+
+    /// Reads entries from directory in virtual file system (e.g. loose folder or ark)
+    /// where each entry is stored as `<dir_path>/<type>/<name>`. If `<dir_path>/dir.dta` exists,
+    /// directory entry and inline sub directories (stored as `<dir_path>/<sub_dir_name>`) are read too.
+    pub fn from_vfs(fs: &dyn VirtualFileSystem, dir_path: &str, _info: &SystemInfo) -> Result<ObjectDir, Box<dyn Error>> {
         let mut obj_dir = ObjectDirBase::new();
+        let dir_path = normalize_vfs_path(dir_path);
+
+        let get_relative_path = |f: &'_ str| -> String {
+            match dir_path.is_empty() {
+                true => f.to_owned(),
+                _ => f[(dir_path.len() + 1)..].to_owned()
+            }
+        };
 
-        let files = path.find_files_with_depth(FileSearchDepth::Limited(1))?
+        let dir_info_path = match dir_path.is_empty() {
+            true => DIR_INFO_FILE_NAME.to_owned(),
+            _ => format!("{dir_path}/{DIR_INFO_FILE_NAME}")
+        };
+
+        let dir_info = match fs.exists(&dir_info_path) {
+            true => Some(ObjectDir::read_dir_info(&fs.read(&dir_info_path)?)
+                .ok_or_else(|| ObjectDirReadError::InvalidDirInfo { path: dir_info_path.to_owned() })?),
+            _ => None
+        };
+
+        let sub_dir_names = dir_info
+            .as_ref()
+            .map(|info| info.sub_dirs.to_owned())
+            .unwrap_or_default();
+
+        let files = fs.list_files(&dir_path)
             .into_iter()
-            .filter(|f| MILO_ENTRY_REGEX.is_match(f.to_str().unwrap()))
-            .collect::<Vec<PathBuf>>();
+            .filter(|f| {
+                let relative_path = get_relative_path(f);
+
+                // Ignore files from inline sub directories
+                let in_sub_dir = sub_dir_names
+                    .iter()
+                    .any(|sd| relative_path.split('/').next().is_some_and(|p| p.eq(sd)));
+
+                !in_sub_dir && MILO_ENTRY_REGEX.is_match(&relative_path)
+            })
+            .collect::<Vec<String>>();
 
         for file_path in files.iter() {
+            let mut path_parts = file_path.rsplit('/');
+
             // Gets file name
-            let entry_name = file_path
-                .file_name().unwrap()
-                .to_str().unwrap()
+            let entry_name = path_parts
+                .next().unwrap()
                 .to_owned();
 
             // Gets directory name as string and converts to pascal casing
-            let entry_type = file_path
-                .parent().unwrap()
-                .file_name().unwrap()
-                .to_str().unwrap()
+            let entry_type = path_parts
+                .next().unwrap()
                 .chars()
                 .enumerate()
                 .map(|(i, ch)| match i {
@@ -64,9 +162,7 @@ impl<'a> ObjectDir {
                 .collect::<String>();
 
             // Read data from file
-            let mut stream = FileStream::from_path_as_read_open(file_path)?;
-            let stream_len = stream.len()?;
-            let data = stream.read_bytes(stream_len)?;
+            let data = fs.read(file_path)?;
 
             // Add entry to collection
             obj_dir.entries.push(Object::Packed(PackedObject {
@@ -76,9 +172,115 @@ impl<'a> ObjectDir {
             }));
         }
 
+        let Some(dir_info) = dir_info else {
+            return Ok(ObjectDir::ObjectDir(obj_dir));
+        };
+
+        obj_dir.name = dir_info.name;
+        obj_dir.dir_type = dir_info.dir_type;
+        obj_dir.external_paths = dir_info.external_paths;
+
+        if let Some(sub_dirs_offset) = dir_info.sub_dirs_offset {
+            // Directory entry is stored with other entries
+            let entry_idx = obj_dir.entries
+                .iter()
+                .position(|e| e.get_name().eq(&obj_dir.name) && e.get_type().eq_ignore_ascii_case(&obj_dir.dir_type))
+                .ok_or_else(|| ObjectDirReadError::MissingDirectoryEntry {
+                    name: obj_dir.name.to_owned(),
+                    dir_type: obj_dir.dir_type.to_owned()
+                })?;
+
+            let Object::Packed(packed) = obj_dir.entries.remove(entry_idx) else {
+                unreachable!() // Entries are always packed here
+            };
+
+            obj_dir.dir_entry = Some(ObjectDirEntry {
+                data: packed.data,
+                sub_dirs_offset,
+                object_dir: None
+            });
+        }
+
+        for sub_dir_name in dir_info.sub_dirs.iter() {
+            let sub_dir_path = match dir_path.is_empty() {
+                true => sub_dir_name.to_owned(),
+                _ => format!("{dir_path}/{sub_dir_name}")
+            };
+
+            let sub_dir = ObjectDir::from_vfs(fs, &sub_dir_path, _info)?;
+            obj_dir.sub_dirs.push(sub_dir);
+        }
+
         Ok(ObjectDir::ObjectDir(obj_dir))
     }
 
+    /// Creates dta of directory values that aren't stored as entries.
+    /// Written to `dir.dta` when extracting to folder so directory can be packed again.
+    pub fn create_dir_info(&self) -> RootData {
+        let ObjectDir::ObjectDir(dir) = self;
+
+        let create_node = |key: &str, values: Vec<DataArray>| {
+            let mut node = vec![DataArray::Symbol(DataString::from(key))];
+            node.extend(values);
+
+            DataArray::Array(node)
+        };
+
+        let mut root = RootData::new();
+        root.data.push(create_node("name", vec![DataArray::String(DataString::from(dir.name.as_str()))]));
+        root.data.push(create_node("type", vec![DataArray::String(DataString::from(dir.dir_type.as_str()))]));
+
+        if let Some(dir_entry) = &dir.dir_entry {
+            root.data.push(create_node("sub_dirs_offset", vec![DataArray::Integer(dir_entry.sub_dirs_offset as i32)]));
+        }
+
+        let sub_dir_names = dir.sub_dirs
+            .iter()
+            .map(|ObjectDir::ObjectDir(sd)| DataArray::String(DataString::from(sd.name.as_str())))
+            .collect();
+
+        let external_paths = dir.external_paths
+            .iter()
+            .map(|p| DataArray::String(DataString::from(p.as_str())))
+            .collect();
+
+        root.data.push(create_node("sub_dirs", sub_dir_names));
+        root.data.push(create_node("external_paths", external_paths));
+
+        root
+    }
+
+    fn read_dir_info(data: &[u8]) -> Option<ObjectDirInfo> {
+        let root = parse_dta(data).ok()?;
+        let mut dir_info = ObjectDirInfo::default();
+
+        let get_string = |value: &DataArray| value
+            .as_string()
+            .and_then(|s| s.as_utf8())
+            .map(|s| s.to_owned());
+
+        for node in root.data.iter() {
+            let DataArray::Array(node) = node else {
+                continue;
+            };
+
+            let Some((DataArray::Symbol(key), values)) = node.split_first() else {
+                continue;
+            };
+
+            match (key.as_utf8()?, values) {
+                ("name", [value]) => dir_info.name = get_string(value)?,
+                ("type", [value]) => dir_info.dir_type = get_string(value)?,
+                ("sub_dirs_offset", [value]) => dir_info.sub_dirs_offset = Some(usize::try_from(value.as_integer()?).ok()?),
+                ("sub_dirs", values) => dir_info.sub_dirs = values.iter().map(get_string).collect::<Option<_>>()?,
+                ("external_paths", values) => dir_info.external_paths = values.iter().map(get_string).collect::<Option<_>>()?,
+                _ => return None
+            }
+        }
+
+        Some(dir_info)
+    }
+
     pub fn get_entries(&'a self) -> &'a Vec<Object> {
         match self {
             ObjectDir::ObjectDir(dir) => &dir.entries
@@ -91,6 +293,37 @@ impl<'a> ObjectDir {
         }
     }
 
+    pub fn get_sub_dirs(&'a self) -> &'a Vec<ObjectDir> {
+        match self {
+            ObjectDir::ObjectDir(dir) => &dir.sub_dirs
+        }
+    }
+
+    pub fn get_dependency_graph(&self, info: &SystemInfo) -> DependencyGraph {
+        DependencyGraph::from_object_dir(self, info)
+    }
+
+    /// Gets external paths to write for v10 directory. Existing paths (which can come from packed entries)
+    /// are kept unless object is now in directory, then missing objects referenced by entries are added.
+    pub fn get_external_paths(&self, info: &SystemInfo) -> Vec<String> {
+        let ObjectDir::ObjectDir(dir) = self;
+        let graph = self.get_dependency_graph(info);
+
+        let mut external_paths = dir.external_paths
+            .iter()
+            .filter(|p| !graph.has_object(p))
+            .map(|p| p.to_owned())
+            .collect::<Vec<_>>();
+
+        for name in graph.get_external_objects() {
+            if !external_paths.iter().any(|p| p.eq_ignore_ascii_case(name)) {
+                external_paths.push(name.to_owned());
+            }
+        }
+
+        external_paths
+    }
+
     pub(crate) fn take_entries(&mut self) -> Vec<Object> {
         self.get_entries_mut()
             .drain(..)
@@ -98,19 +331,48 @@ impl<'a> ObjectDir {
     }
 
     pub fn unpack_entries(&'a mut self, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
-        #[allow(irrefutable_let_patterns)]
-        if let ObjectDir::ObjectDir(obj_dir) = self {
-            for entry in obj_dir.entries.iter_mut() {
-                if let Some(new_entry) = entry.unpack(info) {
-                    *entry = new_entry;
-                } else {
-                    warn!("Unable to unpack \"{}\" ({})", entry.get_name(), entry.get_type());
-                }
-            }
+        for error in self.try_unpack_entries(info) {
+            warn!("Unable to unpack: {error}");
         }
 
         Ok(())
     }
+
+    /// Unpacks entries of directory and sub directories. Entries that can't be unpacked are left
+    /// packed and returned as errors (with entry name, type, version and offset).
+    pub fn try_unpack_entries(&'a mut self, info: &SystemInfo) -> Vec<crate::Error> {
+        self.try_unpack_entries_with_filter(info, |_| true)
+    }
+
+    /// Unpacks entries of directory and sub directories that match filter. Original data of unpacked
+    /// entries is kept in directory.
+    pub fn try_unpack_entries_with_filter(&'a mut self, info: &SystemInfo, filter: impl Fn(&Object) -> bool + Copy) -> Vec<crate::Error> {
+        let ObjectDir::ObjectDir(obj_dir) = self;
+        let mut errors = Vec::new();
+
+        for entry in obj_dir.entries.iter_mut().filter(|e| filter(e)) {
+            match entry.try_unpack(info) {
+                Ok(Some(new_entry)) => {
+                    if let Object::Packed(packed) = std::mem::replace(entry, new_entry) {
+                        obj_dir.original_data.insert(packed.name, packed.data);
+                        obj_dir.original_info = Some(*info);
+                    }
+                },
+                Ok(None) if entry.is_packed() => errors.push(
+                    crate::Error::UnsupportedObjectType { object_type: entry.get_type().to_owned() }
+                        .with_context(ErrorContext::from_entry(entry.get_name(), entry.get_type()))
+                ),
+                Ok(None) => {},
+                Err(error) => errors.push(error),
+            }
+        }
+
+        for sub_dir in obj_dir.sub_dirs.iter_mut() {
+            errors.append(&mut sub_dir.try_unpack_entries_with_filter(info, filter));
+        }
+
+        errors
+    }
 }
 
 impl ObjectDir {
@@ -144,4 +406,89 @@ impl ObjectDir {
             *class_name = name.to_owned();
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+
+    fn create_dir(name: &str, dir_entry_data: Vec<u8>, sub_dirs: Vec<ObjectDir>) -> ObjectDir {
+        let mut dir = ObjectDirBase::new();
+        dir.name = name.to_owned();
+        dir.dir_type = String::from("ObjectDir");
+        dir.dir_entry = Some(ObjectDirEntry {
+            sub_dirs_offset: dir_entry_data.len() / 2,
+            data: dir_entry_data,
+            object_dir: None
+        });
+        dir.sub_dirs = sub_dirs;
+
+        ObjectDir::ObjectDir(dir)
+    }
+
+    fn write_dir(dir: &ObjectDir, path: &Path) {
+        let ObjectDir::ObjectDir(dir_base) = dir;
+        std::fs::create_dir_all(path.join("Tex")).unwrap();
+        std::fs::create_dir_all(path.join("ObjectDir")).unwrap();
+
+        std::fs::write(path.join("Tex").join(format!("{}.tex", dir_base.name)), b"tex").unwrap();
+        std::fs::write(path.join("ObjectDir").join(&dir_base.name), &dir_base.dir_entry.as_ref().unwrap().data).unwrap();
+
+        let mut dir_info = Vec::new();
+        dir.create_dir_info().print(&mut dir_info).unwrap();
+        std::fs::write(path.join(DIR_INFO_FILE_NAME), dir_info).unwrap();
+
+        for sub_dir in dir_base.sub_dirs.iter() {
+            let ObjectDir::ObjectDir(sub_dir_base) = sub_dir;
+            write_dir(sub_dir, &path.join(&sub_dir_base.name));
+        }
+    }
+
+    #[rstest]
+    fn read_dir_with_sub_dirs_from_path() {
+        let dir_path = std::env::temp_dir().join("pikaxe_tests").join("object_dir_with_sub_dirs");
+        if dir_path.exists() {
+            std::fs::remove_dir_all(&dir_path).unwrap();
+        }
+
+        let sub_dir = create_dir("sub", vec![5, 6], Vec::new());
+        let dir = create_dir("root", vec![1, 2, 3, 4], vec![sub_dir]);
+        write_dir(&dir, &dir_path);
+
+        let ObjectDir::ObjectDir(dir) = ObjectDir::from_path(&dir_path, &SystemInfo::default()).unwrap();
+
+        assert_eq!("root", dir.name);
+        assert_eq!("ObjectDir", dir.dir_type);
+        assert_eq!(1, dir.entries.len());
+        assert_eq!("root.tex", dir.entries[0].get_name());
+
+        let dir_entry = dir.dir_entry.as_ref().unwrap();
+        assert_eq!(vec![1, 2, 3, 4], dir_entry.data);
+        assert_eq!(2, dir_entry.sub_dirs_offset);
+
+        assert_eq!(1, dir.sub_dirs.len());
+        let ObjectDir::ObjectDir(sub_dir) = &dir.sub_dirs[0];
+
+        assert_eq!("sub", sub_dir.name);
+        assert_eq!(1, sub_dir.entries.len());
+        assert_eq!("sub.tex", sub_dir.entries[0].get_name());
+        assert_eq!(vec![5, 6], sub_dir.dir_entry.as_ref().unwrap().data);
+        assert_eq!(1, sub_dir.dir_entry.as_ref().unwrap().sub_dirs_offset);
+    }
+
+    #[rstest]
+    fn read_dir_with_invalid_dir_info() {
+        let dir_path = std::env::temp_dir().join("pikaxe_tests").join("object_dir_invalid_dir_info");
+        if dir_path.exists() {
+            std::fs::remove_dir_all(&dir_path).unwrap();
+        }
+
+        std::fs::create_dir_all(&dir_path).unwrap();
+        std::fs::write(dir_path.join(DIR_INFO_FILE_NAME), b"(name \"root\")\n(sub_dirs_offset 4)").unwrap();
+
+        let result = ObjectDir::from_path(&dir_path, &SystemInfo::default());
+        let error = result.err().unwrap();
+
+        assert!(matches!(error.downcast_ref::<ObjectDirReadError>(), Some(ObjectDirReadError::MissingDirectoryEntry { .. })));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::io::BinaryStream;
+use crate::scene::*;
+use crate::SystemInfo;
+use std::error::Error;
+use thiserror::Error as ThisError;
+
+const MAX_OBJECT_DIR_REVISION: u32 = 27;
+
+#[derive(Debug, ThisError)]
+pub enum ObjectDirLoadError {
+    #[error("ObjectDir version {version} is not supported")]
+    ObjectDirVersionNotSupported {
+        version: u32
+    },
+    #[error("Directory type \"{dir_type}\" is not supported")]
+    DirectoryTypeNotSupported {
+        dir_type: String
+    },
+}
+
+/// Returns number of derived class revisions written before ObjectDir fields
+pub fn get_object_dir_class_depth(dir_type: &str) -> Option<usize> {
+    match dir_type {
+        "ObjectDir" => Some(0),
+        "RndDir" => Some(1),
+        "PanelDir" | "Character" => Some(2), // Derived from RndDir
+        "WorldDir" => Some(3), // Derived from PanelDir
+        _ => None
+    }
+}
+
+/// Reads directory entry fields up to inline sub directories. Class specific data of RndDir,
+/// PanelDir, WorldDir and Character after sub directories isn't parsed (only their revisions),
+/// so it's kept as raw data and its end is found from ADDE padding.
+pub(crate) fn load_object_dir(obj_dir: &mut ObjectDirObject, dir_type: &str, reader: &mut Box<BinaryStream>, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    let class_depth = get_object_dir_class_depth(dir_type)
+        .ok_or_else(|| ObjectDirLoadError::DirectoryTypeNotSupported {
+            dir_type: dir_type.to_owned()
+        })?;
+
+    obj_dir.class_revisions.clear();
+    for _ in 0..class_depth {
+        obj_dir.class_revisions.push(reader.read_uint32()?);
+    }
+
+    let revision = reader.read_uint32()?;
+    if revision > MAX_OBJECT_DIR_REVISION {
+        return Err(Box::new(ObjectDirLoadError::ObjectDirVersionNotSupported {
+            version: revision
+        }));
+    }
+
+    obj_dir.revision = revision;
+
+    if revision > 1 {
+        load_object_type(obj_dir, reader, info)?;
+    }
+
+    obj_dir.viewports.clear();
+    if revision >= 2 {
+        let viewport_count = reader.read_uint32()?;
+        for _ in 0..viewport_count {
+            let mut viewport = Matrix::default();
+            load_matrix(&mut viewport, reader)?;
+
+            obj_dir.viewports.push(viewport);
+        }
+
+        obj_dir.current_viewport = reader.read_uint32()?;
+    }
+
+    if revision >= 13 {
+        obj_dir.inline_proxy = reader.read_boolean()?;
+        obj_dir.proxy_path = reader.read_prefixed_string()?;
+    } else if revision >= 2 {
+        // Legacy proxy path
+        obj_dir.proxy_path = reader.read_prefixed_string()?;
+
+        if revision >= 4 {
+            obj_dir.unknown_string = reader.read_prefixed_string()?;
+        }
+    }
+
+    obj_dir.sub_dir_paths.clear();
+    if revision >= 3 {
+        let sub_dir_count = reader.read_uint32()?;
+        for _ in 0..sub_dir_count {
+            obj_dir.sub_dir_paths.push(reader.read_prefixed_string()?);
+        }
+    }
+
+    obj_dir.inline_sub_dir_names.clear();
+    obj_dir.inline_sub_dir_types.clear();
+    if revision >= 21 {
+        obj_dir.inline_sub_dir = reader.read_boolean()?;
+
+        let inline_count = reader.read_uint32()?;
+        for _ in 0..inline_count {
+            obj_dir.inline_sub_dir_names.push(reader.read_prefixed_string()?);
+        }
+
+        if revision >= 27 {
+            for _ in 0..inline_count {
+                obj_dir.inline_sub_dir_types.push(reader.read_uint32()?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes directory entry fields up to inline sub directories
+pub(crate) fn save_object_dir(obj_dir: &ObjectDirObject, writer: &mut Box<BinaryStream>, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    for class_revision in obj_dir.class_revisions.iter() {
+        writer.write_uint32(*class_revision)?;
+    }
+
+    let revision = obj_dir.revision;
+    writer.write_uint32(revision)?;
+
+    if revision > 1 {
+        save_object_type(obj_dir, writer, info)?;
+    }
+
+    if revision >= 2 {
+        writer.write_uint32(obj_dir.viewports.len() as u32)?;
+        for viewport in obj_dir.viewports.iter() {
+            save_matrix(viewport, writer)?;
+        }
+
+        writer.write_uint32(obj_dir.current_viewport)?;
+    }
+
+    if revision >= 13 {
+        writer.write_boolean(obj_dir.inline_proxy)?;
+        writer.write_prefixed_string(&obj_dir.proxy_path)?;
+    } else if revision >= 2 {
+        writer.write_prefixed_string(&obj_dir.proxy_path)?;
+
+        if revision >= 4 {
+            writer.write_prefixed_string(&obj_dir.unknown_string)?;
+        }
+    }
+
+    if revision >= 3 {
+        writer.write_uint32(obj_dir.sub_dir_paths.len() as u32)?;
+        for sub_dir_path in obj_dir.sub_dir_paths.iter() {
+            writer.write_prefixed_string(sub_dir_path)?;
+        }
+    }
+
+    if revision >= 21 {
+        writer.write_boolean(obj_dir.inline_sub_dir)?;
+
+        writer.write_uint32(obj_dir.inline_sub_dir_names.len() as u32)?;
+        for name in obj_dir.inline_sub_dir_names.iter() {
+            writer.write_prefixed_string(name)?;
+        }
+
+        if revision >= 27 {
+            for i in 0..obj_dir.inline_sub_dir_names.len() {
+                writer.write_uint32(obj_dir.inline_sub_dir_types.get(i).copied().unwrap_or_default())?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+mod io;
+
+use pikaxe_macros::*;
+use pikaxe_traits::scene::*;
+pub use io::*;
+
+/// Common fields of directory entry shared by ObjectDir and derived classes
+/// (RndDir, PanelDir, WorldDir, Character). Fields specific to derived classes aren't read.
+#[milo]
+#[derive(Debug)]
+pub struct ObjectDirObject {
+    /// Revisions of derived classes written before ObjectDir (most derived first)
+    pub class_revisions: Vec<u32>,
+    pub revision: u32,
+
+    pub viewports: Vec<Matrix>,
+    pub current_viewport: u32,
+
+    pub inline_proxy: bool,
+    pub proxy_path: String,
+    /// Stored after legacy proxy path (revisions 4-12)
+    pub unknown_string: String,
+
+    pub sub_dir_paths: Vec<String>,
+
+    pub inline_sub_dir: bool,
+    pub inline_sub_dir_names: Vec<String>,
+    pub inline_sub_dir_types: Vec<u32>,
+}
+
+impl Default for ObjectDirObject {
+    fn default() -> ObjectDirObject {
+        ObjectDirObject {
+            // Base object
+            name: String::default(),
+            type2: String::default(),
+            note: String::default(),
+
+            // ObjectDir object
+            class_revisions: Vec::new(),
+            revision: 27,
+
+            viewports: Vec::new(),
+            current_viewport: 0,
+
+            inline_proxy: true,
+            proxy_path: String::default(),
+            unknown_string: String::default(),
+
+            sub_dir_paths: Vec::new(),
+
+            inline_sub_dir: false,
+            inline_sub_dir_names: Vec::new(),
+            inline_sub_dir_types: Vec::new(),
+        }
+    }
+}
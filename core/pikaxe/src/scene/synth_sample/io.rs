@@ -33,7 +33,7 @@ fn is_sampledata_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for SynthSample {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -77,6 +77,9 @@ impl ObjectReadWrite for SynthSample {
     }
 
     fn save(&self, _stream: &mut dyn Stream, _info: &SystemInfo) -> Result<(), Box<dyn Error>> {
-        todo!()
+        Err(Box::new(crate::Error::UnsupportedSave {
+            object_type: String::from("SynthSample"),
+            reason: "not implemented yet"
+        }))
     }
 }
\ No newline at end of file
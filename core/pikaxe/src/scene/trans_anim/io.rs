@@ -21,7 +21,7 @@ fn is_version_supported(version: u32) -> bool {
 }
 
 impl ObjectReadWrite for TransAnim {
-    fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         let version = reader.read_uint32()?;
@@ -92,16 +92,20 @@ impl ObjectReadWrite for TransAnim {
         self.repeat_trans = reader.read_boolean()?;
 
         if version < 4 {
-            self.scale_spline = false;
-            todo!()
+            return Err(Box::new(crate::Error::UnsupportedLoad {
+                object_type: String::from("TransAnim"),
+                reason: "reading scale keys for v3 or below isn't implemented yet"
+            }));
         } else {
             self.scale_keys = load_keys_vector3(&mut reader)?;
             self.scale_spline = reader.read_boolean()?;
         }
 
         if version < 2 {
-            self.follow_path = false;
-            todo!()
+            return Err(Box::new(crate::Error::UnsupportedLoad {
+                object_type: String::from("TransAnim"),
+                reason: "reading follow path for v1 or below isn't implemented yet"
+            }));
         } else {
             self.follow_path = reader.read_boolean()?;
         }
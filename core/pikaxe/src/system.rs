@@ -1,3 +1,4 @@
+use crate::{GamePreset, PresetError};
 use crate::io::{IOEndian, MiloArchive};
 use std::path::{Path, PathBuf};
 
@@ -27,6 +28,16 @@ impl Default for SystemInfo {
 }
 
 impl Platform {
+    pub fn from_name(name: &str) -> Option<Platform> {
+        match name.to_lowercase().as_str() {
+            "ps2" => Some(Platform::PS2),
+            "ps3" => Some(Platform::PS3),
+            "wii" => Some(Platform::Wii),
+            "xbox 360" | "xbox360" | "x360" | "360" => Some(Platform::X360),
+            _ => None
+        }
+    }
+
     pub fn guess_platform(milo_path: &Path) -> Platform {
         match milo_path.extension() {
             Some(ext) => match ext.to_str() {
@@ -73,6 +84,15 @@ impl SystemInfo {
         }
     }
 
+    /// Gets system info from game preset (platform guessed from path) or guesses from archive if no preset is given
+    pub fn from_preset_or_guess(preset: Option<&str>, milo: &MiloArchive, milo_path: &Path) -> Result<SystemInfo, PresetError> {
+        match preset {
+            Some(name) => GamePreset::from_name(name)
+                .map(|p| p.get_system_info(Some(Platform::guess_platform(milo_path)))),
+            None => Ok(SystemInfo::guess_system_info(milo, milo_path))
+        }
+    }
+
     pub fn is_next_gen(&self) -> bool {
         match self.platform {
             Platform::PS3 | Platform::X360 => true,
@@ -1,6 +1,6 @@
 use crate::io::{BinaryStream, SeekFrom, Stream};
 use crate::scene::ObjectReadWrite;
-use crate::texture::{Bitmap, decode_dx_image, decode_tpl_image, encode_dx_image, get_dx_bpp, DXGI_Encoding, TPLEncoding};
+use crate::texture::{Bitmap, decode_dx_image, decode_tpl_image, encode_dx_image, encode_tpl_image, get_dx_bpp, DXGI_Encoding, TPLEncoding};
 use crate::system::{Platform, SystemInfo};
 use image::buffer::ConvertBuffer;
 use image::{ImageBuffer, ImageEncoder, ImageFormat, open, RgbaImage};
@@ -91,6 +91,25 @@ impl Bitmap {
                     raw_data: dx_img
                 }
             },
+            Platform::Wii => {
+                let mut tpl_img = vec![0u8; ((width as usize) * (height as usize)) / 2];
+
+                // Encode without mip maps for now
+                let rgba = &rgba[..(width as usize * height as usize * 4)];
+                encode_tpl_image(rgba, &mut tpl_img, width as u32, TPLEncoding::CMP);
+
+                Bitmap {
+                    bpp: 4,
+                    encoding: TPLEncoding::CMP as u32,
+                    mip_maps: 0,
+
+                    width,
+                    height,
+                    bpl: width / 2,
+
+                    raw_data: tpl_img
+                }
+            },
             _ => todo!("Support other platforms")
         }
     }
@@ -227,10 +246,37 @@ impl Bitmap {
         let Bitmap { width: w, height: h, mip_maps: mips, ..} = self;
         calc_rgba_size(*w, *h, *mips)
     }
+
+    /// Calculates size of raw data (palette + every level) from encoding. Returns `None` if encoding
+    /// isn't supported for platform.
+    pub fn calc_data_size(&self, info: &SystemInfo) -> Option<usize> {
+        let palette_size = match (info.platform, self.encoding, self.bpp) {
+            (Platform::X360 | Platform::PS3, 8 | 24 | 32, _) => 0,
+            (Platform::Wii, 72 | 328 | 584, _) => 0,
+            (Platform::PS2, 3, 4 | 8) => 1 << (self.bpp + 2),
+            _ => return None
+        };
+
+        let (mut width, mut height) = (self.width as usize, self.height as usize);
+        let mut data_size = palette_size;
+
+        for _ in 0..=self.mip_maps {
+            data_size += (width * height * self.bpp as usize) / 8;
+            width >>= 1;
+            height >>= 1;
+        }
+
+        Some(data_size)
+    }
 }
 
 impl ObjectReadWrite for Bitmap {
     fn load(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
+        // Bitmap is part of Tex data so errors get context from Tex
+        self.load_data(stream, info)
+    }
+
+    fn load_data(&mut self, stream: &mut dyn Stream, info: &SystemInfo) -> Result<(), Box<dyn Error>> {
         let mut reader = Box::new(BinaryStream::from_stream_with_endian(stream, info.endian));
 
         // 0 = 16-byte header (Amp), 1 = 32-byte header
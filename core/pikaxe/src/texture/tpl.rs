@@ -35,6 +35,83 @@ pub fn decode_tpl_image(tpl_img: &[u8], rgba: &mut [u8], width: u32, encoding: T
     };
 }
 
+pub fn encode_tpl_image(rgba: &[u8], tpl_img: &mut [u8], width: u32, encoding: TPLEncoding) {
+    match &encoding {
+        TPLEncoding::CMP => {
+            // CMP blocks are the same as DXT1 but re-ordered
+            let mut dx_img = vec![0u8; tpl_img.len()];
+            encode_dx_image(rgba, &mut dx_img, width, DXGI_Encoding::DXGI_FORMAT_BC1_UNORM, false);
+            convert_dxt1_to_cmp(&dx_img, tpl_img, width, false);
+        },
+        _ => todo!("Implement wii texture encoding for other formats"),
+    };
+}
+
+/// Re-orders DXT1 blocks into 8x8 CMP tiles (lossless). Width and height should be multiples of 8.
+pub fn convert_dxt1_to_cmp(dx_img: &[u8], tpl_img: &mut [u8], width: u32, is_360: bool) {
+    swap_dxt1_cmp_blocks(dx_img, tpl_img, width, is_360, true);
+}
+
+/// Re-orders 8x8 CMP tiles into DXT1 blocks (lossless). Width and height should be multiples of 8.
+pub fn convert_cmp_to_dxt1(tpl_img: &[u8], dx_img: &mut [u8], width: u32, is_360: bool) {
+    swap_dxt1_cmp_blocks(tpl_img, dx_img, width, is_360, false);
+}
+
+fn swap_dxt1_cmp_blocks(input: &[u8], output: &mut [u8], width: u32, is_360: bool, to_cmp: bool) {
+    const BLOCK_SIZE: usize = 8;
+
+    let block_x = (width >> 2) as usize;
+    let tile_x = (block_x >> 1).max(1);
+    let block_count = input.len().min(output.len()) / BLOCK_SIZE;
+
+    for i in 0..block_count {
+        // CMP tiles store 2x2 groups of blocks
+        let (tile, t) = (i >> 2, i & 3);
+        let bx = ((tile % tile_x) << 1) + (t & 1);
+        let by = ((tile / tile_x) << 1) + (t >> 1);
+        let d = (by * block_x) + bx; // Linear DXT block index
+
+        if d >= block_count {
+            continue;
+        }
+
+        let (in_idx, out_idx) = match to_cmp {
+            true => (d, i),
+            _ => (i, d)
+        };
+
+        let mut block = [0u8; BLOCK_SIZE];
+        block.copy_from_slice(&input[(in_idx * BLOCK_SIZE)..((in_idx + 1) * BLOCK_SIZE)]);
+
+        if is_360 && to_cmp {
+            swap_block_bytes(&mut block);
+        }
+
+        // Colors are big endian + indices are stored with first pixel in upper bits
+        block.swap(0, 1);
+        block.swap(2, 3);
+
+        for b in block[4..].iter_mut() {
+            *b = ((*b & 0b00_00_00_11) << 6)
+                | ((*b & 0b00_00_11_00) << 2)
+                | ((*b & 0b00_11_00_00) >> 2)
+                | ((*b & 0b11_00_00_00) >> 6);
+        }
+
+        if is_360 && !to_cmp {
+            swap_block_bytes(&mut block);
+        }
+
+        output[(out_idx * BLOCK_SIZE)..((out_idx + 1) * BLOCK_SIZE)].copy_from_slice(&block);
+    }
+}
+
+fn swap_block_bytes(block: &mut [u8; 8]) {
+    for i in (0..8).step_by(2) {
+        block.swap(i, i + 1);
+    }
+}
+
 fn decode_cmp_image(tpl_img: &[u8], rgba: &mut [u8], width: u32) {
     let bpp = get_tpl_bpp(&TPLEncoding::CMP);
 
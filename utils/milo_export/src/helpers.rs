@@ -24,6 +24,7 @@ const GDRB_VENUE_NAMES: [&str; 3] = [
 #[derive(Default)]
 pub struct GameAnalyzer {
     pub game_dir: PathBuf,
+    pub fs: OverlayFileSystem,
     pub song_ids: Vec<String>,
     pub cams: Cams,
     pub post_procs: Vec<String>,
@@ -35,10 +36,12 @@ pub struct GameAnalyzer {
 
 impl GameAnalyzer {
     pub fn new(game_path: PathBuf) -> GameAnalyzer {
-        let song_ids = find_song_ids(game_path.as_path());
+        let fs = create_game_fs(game_path.as_path());
+        let song_ids = find_song_ids(&fs);
 
         GameAnalyzer {
             game_dir: game_path,
+            fs,
             song_ids,
             ..Default::default()
          }
@@ -57,8 +60,8 @@ impl GameAnalyzer {
 
             // Open venue milo
             let venue_file_name = format!("{venue_name}.milo");
-            let venue_path = self.game_dir.join("world").join(venue_name).join(&venue_file_name);
-            let venue_milo_dir = try_open_milo(venue_path.as_path());
+            let venue_path = format!("world/{venue_name}/{venue_file_name}");
+            let venue_milo_dir = try_open_milo(&self.fs, &venue_path);
             if venue_milo_dir.is_err() {
                 continue;
             }
@@ -69,19 +72,15 @@ impl GameAnalyzer {
 
             // Open venue lighting milo
             let venue_lighting_file_name = format!("{venue_name}_lighting.milo");
-            let mut venue_lighting_path = self.game_dir.join("world").join(venue_name).join(&venue_lighting_file_name);
-            let mut venue_lighting_milo_dir = try_open_milo(venue_lighting_path.as_path());
+            let mut venue_lighting_path = format!("world/{venue_name}/{venue_lighting_file_name}");
+            let mut venue_lighting_milo_dir = try_open_milo(&self.fs, &venue_lighting_path);
 
             if venue_lighting_milo_dir.is_err() {
                 // Try looking in platform specific directory
                 for platform in ["ps3", "xbx"] {
-                    venue_lighting_path = self.game_dir
-                        .join("world")
-                        .join(venue_name)
-                        .join(platform)
-                        .join(&venue_lighting_file_name);
+                    venue_lighting_path = format!("world/{venue_name}/{platform}/{venue_lighting_file_name}");
 
-                    venue_lighting_milo_dir = try_open_milo(venue_lighting_path.as_path());
+                    venue_lighting_milo_dir = try_open_milo(&self.fs, &venue_lighting_path);
                     if venue_lighting_milo_dir.is_ok() {
                         break;
                     }
@@ -114,14 +113,9 @@ impl GameAnalyzer {
 
     fn process_songs(&mut self) {
         for song_name in self.song_ids.iter() {
-            let song_dir = self.game_dir
-                .join("songs")
-                .join(song_name);
-
             // Get cams
-            let milo_cams_file_name = format!("{song_name}_cams.milo");
-            let cams_path = song_dir.join(&milo_cams_file_name);
-            if let Ok((_, cams_milo_dir)) = try_open_milo(cams_path.as_path()) {
+            let cams_path = format!("songs/{song_name}/{song_name}_cams.milo");
+            if let Ok((_, cams_milo_dir)) = try_open_milo(&self.fs, &cams_path) {
                 let cams = get_names_for_type_from_dir(&cams_milo_dir.get_entries(), "BandCamShot");
 
                 self.cams.songs.push(ValueCollection {
@@ -136,15 +130,10 @@ impl GameAnalyzer {
                 values: Vec::new()
             };
 
-            let anims_file_name = format!("{song_name}.milo");
             for (_, long_char_name) in GDRB_CHARACTERS.iter() {
-                let anims_path = self.game_dir
-                    .join("char")
-                    .join(long_char_name)
-                    .join("song")
-                    .join(&anims_file_name);
+                let anims_path = format!("char/{long_char_name}/song/{song_name}.milo");
 
-                if let Ok((_, anims_milo_dir)) = try_open_milo(anims_path.as_path()) {
+                if let Ok((_, anims_milo_dir)) = try_open_milo(&self.fs, &anims_path) {
                     let clips = get_names_for_type_from_dir(&anims_milo_dir.get_entries(), "CharClipGroup");
 
                     song_clips.values.push(ValueCollection {
@@ -220,14 +209,9 @@ impl GameAnalyzer {
         mapped_cams.contains(&"test");
 
         for song_name in self.song_ids.iter() {
-            let song_dir = self.game_dir
-                .join("songs")
-                .join(song_name);
-
             // Get prop anims
-            let milo_file_name = format!("{song_name}_ap.milo");
-            let milo_path = song_dir.join(&milo_file_name);
-            let prop_anim_entry = try_open_milo(milo_path.as_path())
+            let milo_path = format!("songs/{song_name}/{song_name}_ap.milo");
+            let prop_anim_entry = try_open_milo(&self.fs, &milo_path)
                 .ok()
                 .and_then(|(sys_info, milo_dir)| milo_dir
                     .get_entries()
@@ -344,12 +328,9 @@ impl GameAnalyzer {
 
     fn process_post_procs(&mut self) {
         // Read post procs
-        let post_procs_path = self.game_dir
-            .join("world")
-            .join("shared")
-            .join("camera.milo");
+        let post_procs_path = "world/shared/camera.milo";
 
-        if let Ok((_, post_procs_dir)) = try_open_milo(post_procs_path.as_path()) {
+        if let Ok((_, post_procs_dir)) = try_open_milo(&self.fs, post_procs_path) {
             self.post_procs = get_names_for_type_from_dir(&post_procs_dir.get_entries(), "PostProc");
         }
     }
@@ -394,27 +375,22 @@ impl GameAnalyzer {
     }
 }
 
-fn try_open_milo(milo_path: &Path) -> Result<(SystemInfo, ObjectDir), Box<dyn Error>> {
-    let dir_path = milo_path
-        .parent()
-        .unwrap();
-
-    let file_name = milo_path
-        .file_name()
-        .and_then(|f| f.to_str())
-        .unwrap();
+fn try_open_milo(fs: &dyn VirtualFileSystem, milo_path: &str) -> Result<(SystemInfo, ObjectDir), Box<dyn Error>> {
+    let (dir_path, file_name) = milo_path
+        .rsplit_once('/')
+        .unwrap_or(("", milo_path));
 
     for ext in PLATFORMS.iter() {
-        let milo_file_name = PathBuf::from(format!("{file_name}_{ext}"));
+        let milo_file_name = format!("{file_name}_{ext}");
 
         // First try regular path
-        let mut result = open_milo(dir_path.join(&milo_file_name).as_path());
+        let mut result = open_milo(fs, &format!("{dir_path}/{milo_file_name}"));
         if result.is_ok() {
             return result;
         }
 
         // Then try w/ gen path
-        result = open_milo(dir_path.join("gen").join(&milo_file_name).as_path());
+        result = open_milo(fs, &format!("{dir_path}/gen/{milo_file_name}"));
         if result.is_ok() {
             return result;
         }
@@ -423,13 +399,14 @@ fn try_open_milo(milo_path: &Path) -> Result<(SystemInfo, ObjectDir), Box<dyn Er
     Err(Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "File not found")))
 }
 
-fn open_milo(milo_path: &Path) -> Result<(SystemInfo, ObjectDir), Box<dyn Error>> {
+fn open_milo(fs: &dyn VirtualFileSystem, milo_path: &str) -> Result<(SystemInfo, ObjectDir), Box<dyn Error>> {
     // Open milo
-    let mut stream = FileStream::from_path_as_read_open(&milo_path)?;
+    let data = fs.read(milo_path)?;
+    let mut stream = MemoryStream::from_slice_as_read(&data);
     let milo = MiloArchive::from_stream(&mut stream)?;
 
     // Unpack dir and entries
-    let system_info = SystemInfo::guess_system_info(&milo, &milo_path);
+    let system_info = SystemInfo::guess_system_info(&milo, Path::new(milo_path));
     let obj_dir = milo.unpack_directory(&system_info)?;
 
     Ok((system_info, obj_dir))
@@ -447,15 +424,57 @@ fn get_names_for_type_from_dir(entries: &[Object], entry_type: &str) -> Vec<Stri
     entries
 }
 
-fn find_song_ids(game_dir: &Path) -> Vec<String> {
-    game_dir.join("songs")
-        .read_dir()
-        .unwrap()
-        .map(|d| d.unwrap().path())
-        .filter(|d| d.is_dir())
-        .map(|p| p.file_name().and_then(|f| f.to_str()).map(|s| s.to_string()).unwrap())
+fn create_game_fs(game_path: &Path) -> OverlayFileSystem {
+    let mut fs = OverlayFileSystem::new();
+
+    if game_path.is_file() {
+        // Read directly from hdr/ark
+        if let Ok(ark_fs) = ArkFileSystem::from_path(game_path) {
+            fs.push_layer(ark_fs);
+        }
+
+        return fs;
+    }
+
+    // Add arks first so loose files take priority
+    let hdr_paths = [game_path.to_path_buf(), game_path.join("gen")]
+        .iter()
+        .flat_map(|d| d.find_files_with_depth(FileSearchDepth::Immediate).unwrap_or_default())
+        .filter(|f| f
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case("hdr"))
+            .unwrap_or_default())
+        .collect::<Vec<_>>();
+
+    for hdr_path in hdr_paths {
+        if let Ok(ark_fs) = ArkFileSystem::from_path(&hdr_path) {
+            fs.push_layer(ark_fs);
+        }
+    }
+
+    fs.push_layer(DirectoryFileSystem::new(game_path));
+    fs
+}
+
+fn find_song_ids(fs: &dyn VirtualFileSystem) -> Vec<String> {
+    let mut song_ids = fs
+        .list_files("songs")
+        .into_iter()
+        .filter_map(|f| {
+            // Only take song directories (songs/<id>/...)
+            let mut parts = f.split('/').skip(1);
+            let song_id = parts.next()?;
+            parts.next()?;
+
+            Some(song_id.to_string())
+        })
         .filter(|s| s.ne("gen"))
-        .collect::<Vec<_>>()
+        .collect::<Vec<_>>();
+
+    song_ids.sort();
+    song_ids.dedup();
+    song_ids
 }
 
 #[derive(Default, Deserialize, Serialize)]